@@ -0,0 +1,236 @@
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use crate::claim::Claim;
+use crate::evt::Event;
+use crate::request::Request;
+use crate::RingBuffer;
+
+/// Pending durability work of a batch(synced at once on commit).
+#[derive(Default)]
+struct Barrier {
+    files: Vec<File>,
+    renames: Vec<(PathBuf, PathBuf)>,
+    dirs: BTreeSet<PathBuf>,
+    claims: Vec<Claim>,
+}
+
+thread_local! {
+    static PENDING: RefCell<Option<Barrier>> = const { RefCell::new(None) };
+}
+
+fn with_pending<F>(f: F) -> bool
+where
+    F: FnOnce(&mut Barrier),
+{
+    PENDING.with(|p| p.borrow_mut().as_mut().map(f).is_some())
+}
+
+fn parent(p: &Path) -> PathBuf {
+    p.parent().unwrap_or_else(|| Path::new(".")).to_path_buf()
+}
+
+/// Defers `sync_data` of the written file if a batch is running on this thread.
+///
+/// The file will be returned back if no batch is running.
+pub fn defer_sync<P>(f: File, p: P) -> Option<File>
+where
+    P: AsRef<Path>,
+{
+    let mut f: Option<File> = Some(f);
+    with_pending(|b: &mut Barrier| {
+        b.files.extend(f.take());
+        b.dirs.insert(parent(p.as_ref()));
+    });
+    f
+}
+
+/// Defers the rename of a written temporary file if a batch is running on this thread.
+///
+/// The rename will be done after the data of all files synced.
+pub fn defer_rename<P, Q>(tmp: P, p: Q) -> bool
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let pair = (tmp.as_ref().to_path_buf(), p.as_ref().to_path_buf());
+    with_pending(|b: &mut Barrier| {
+        b.dirs.insert(parent(&pair.1));
+        b.renames.push(pair);
+    })
+}
+
+/// Holds the slot claim until the batch commits if a batch is running on this thread.
+///
+/// The claim will be returned back(released on drop) if no batch is running.
+pub fn defer_release(c: Claim) -> Option<Claim> {
+    let mut c: Option<Claim> = Some(c);
+    with_pending(|b: &mut Barrier| b.claims.extend(c.take()));
+    c
+}
+
+/// Removes temporary files of the barrier(claims will be released on drop).
+fn rollback(b: Barrier) {
+    b.renames.iter().for_each(|(tmp, _)| {
+        std::fs::remove_file(tmp).ok();
+    });
+}
+
+fn commit(b: Barrier) -> Result<(), Event> {
+    let synced: Result<(), Event> = b.files.iter().try_for_each(|f: &File| {
+        f.sync_data()
+            .map_err(|e| Event::UnexpectedError(format!("Unable to save to storage: {}", e)))
+    });
+    if let Err(e) = synced {
+        rollback(b);
+        return Err(e);
+    }
+    let renamed: Vec<Result<(), Event>> = b
+        .renames
+        .iter()
+        .map(|(tmp, p)| {
+            std::fs::rename(tmp, p).map_err(|e| {
+                std::fs::remove_file(tmp).ok();
+                Event::UnexpectedError(format!("Unable to rename temporary item: {}", e))
+            })
+        })
+        .collect();
+    let dirs: Result<(), Event> = b.dirs.iter().try_for_each(crate::write::sync_dir);
+    renamed.into_iter().collect::<Result<(), Event>>()?;
+    dirs
+}
+
+/// Running batch of this thread.
+///
+/// The barrier will be cleared on drop; a barrier left by a panicking closure will be rolled
+/// back.
+struct Running;
+
+impl Running {
+    fn start() -> Self {
+        PENDING.with(|p| *p.borrow_mut() = Some(Barrier::default()));
+        Self
+    }
+
+    fn commit(self) -> Result<(), Event> {
+        let barrier: Barrier = PENDING.with(|p| p.borrow_mut().take()).unwrap_or_default();
+        commit(barrier)
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        let left: Option<Barrier> = PENDING.try_with(|p| p.borrow_mut().take()).ok().flatten();
+        if let Some(b) = left {
+            rollback(b);
+        }
+    }
+}
+
+fn is_written(e: &Event) -> bool {
+    matches!(
+        e,
+        Event::Success | Event::ItemWrote(_) | Event::ItemOverwrote(_)
+    )
+}
+
+/// Runs the closure with a single durability barrier(data plus directory) for all writes.
+///
+/// Writes(and truncates) done in the closure on this thread will be synced once, after the
+/// closure returns. Successful results will be replaced with the error if the barrier fails.
+/// A nested batch joins the running one.
+/// Pending writes will be rolled back if the closure panics.
+pub fn durable<F>(f: F) -> Vec<Event>
+where
+    F: FnOnce() -> Vec<Event>,
+{
+    let nested: bool = PENDING.with(|p| p.borrow().is_some());
+    if nested {
+        return f();
+    }
+    let running = Running::start();
+    let results: Vec<Event> = f();
+    match running.commit() {
+        Ok(_) => results,
+        Err(e) => results
+            .into_iter()
+            .map(|r: Event| match is_written(&r) {
+                true => e.clone(),
+                false => r,
+            })
+            .collect(),
+    }
+}
+
+/// Handles the requests with a single durability barrier(`Event::Batch` keeps the order).
+pub fn handle_many<R>(buf: &mut R, reqs: Vec<Request>) -> Event
+where
+    R: RingBuffer,
+{
+    let results: Vec<Event> =
+        durable(|| reqs.into_iter().map(|q: Request| buf.handle(q)).collect());
+    Event::Batch(results)
+}
+
+#[cfg(test)]
+mod test_batch {
+
+    mod durable {
+        use std::path::Path;
+
+        use crate::batch;
+        use crate::evt::Event;
+        use crate::write;
+
+        #[test]
+        fn test_nothing() {
+            let results = batch::durable(|| vec![Event::Success, Event::BadRequest]);
+            assert_eq!(results, vec![Event::Success, Event::BadRequest]);
+        }
+
+        #[test]
+        #[ignore]
+        fn test_deferred() {
+            let dirname = Path::new("./test.d/batch/durable/deferred.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let p = dirname.join("cursor");
+            let results = batch::durable(|| {
+                write::write_atomic(b"1", &p).unwrap();
+                write::write_atomic(b"2", &p).unwrap();
+                // nested batches join the running one
+                batch::durable(Vec::new);
+                assert!(!p.exists());
+                vec![Event::Success]
+            });
+            assert_eq!(results, vec![Event::Success]);
+            assert_eq!(std::fs::read(&p).unwrap(), b"2");
+            assert_eq!(std::fs::read_dir(dirname).unwrap().count(), 1);
+        }
+
+        #[test]
+        #[ignore]
+        fn test_panic() {
+            let dirname = Path::new("./test.d/batch/durable/panic.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let p = dirname.join("cursor");
+            let panicked = std::panic::catch_unwind(|| {
+                batch::durable(|| {
+                    write::write_atomic(b"1", &p).unwrap();
+                    panic!("interrupted");
+                })
+            });
+            assert!(panicked.is_err());
+            assert_eq!(std::fs::read_dir(dirname).unwrap().count(), 0);
+
+            // no batch is running
+            write::write_atomic(b"2", &p).unwrap();
+            assert_eq!(std::fs::read(&p).unwrap(), b"2");
+        }
+    }
+}
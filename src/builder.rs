@@ -0,0 +1,392 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::checksum::{Checksum, Nop};
+use crate::clock::{Clock, SystemClock};
+use crate::codec::{Codec, Identity};
+use crate::envelope::{Envelope, Plain};
+use crate::evt::Event;
+use crate::full::Layout;
+use crate::header::ItemFormat;
+use crate::naming::HexNames;
+use crate::next;
+use crate::push::FullPolicy;
+use crate::read;
+use crate::request::Request;
+use crate::ttl::Ttl;
+use crate::u::buf;
+use crate::write::{self, WriteMode};
+use crate::RingBuffer;
+
+/// Random ring buffer assembled by `FsRingBufferBuilder`(can be moved to another thread).
+pub struct Ring {
+    inner: Box<dyn RingBuffer + Send>,
+}
+
+impl RingBuffer for Ring {
+    fn handle(&mut self, req: Request) -> Event {
+        self.inner.handle(req)
+    }
+}
+
+/// Builder of a random ring buffer.
+///
+/// Items will be stored without a header unless a format or expiry rules are set
+/// (`with_format`, `with_ttl`; see `header::Header`).
+///
+/// Defaults: flat layout, u8 names, no checksum, no expiry, `WriteMode::InPlace`,
+/// `FullPolicy::Reject` and /dev/urandom.
+pub struct FsRingBufferBuilder<K, D = Identity, E = Plain, T = SystemClock> {
+    layout: Layout,
+    names: HexNames,
+    capacity: Option<u64>,
+    format: ItemFormat<K, D, E>,
+    ttl: Ttl<T>,
+    header: bool,
+    mode: WriteMode,
+    policy: FullPolicy,
+    random: Option<Box<dyn Read + Send>>,
+}
+
+impl FsRingBufferBuilder<Nop> {
+    /// Creates new builder which uses the directory to read/write buffer files(flat layout).
+    pub fn new<P>(dirname: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            layout: Layout::flat(dirname),
+            names: HexNames::u8(),
+            capacity: None,
+            format: ItemFormat::new(Nop),
+            ttl: Ttl::new(SystemClock, None),
+            header: false,
+            mode: WriteMode::default(),
+            policy: FullPolicy::default(),
+            random: None,
+        }
+    }
+}
+
+impl<K, D, E, T> FsRingBufferBuilder<K, D, E, T>
+where
+    K: Checksum + Clone + Send + Sync + 'static,
+    D: Codec + Clone + Send + Sync + 'static,
+    E: Envelope + Clone + Send + Sync + 'static,
+    T: Clock + Clone + Send + Sync + 'static,
+{
+    /// Sets the directory layout of buffer files(flat or sharded).
+    pub fn with_layout(self, layout: Layout) -> Self {
+        Self { layout, ..self }
+    }
+
+    /// Sets the naming scheme(the capacity of the scheme will be used if no capacity set).
+    pub fn with_names(self, names: HexNames) -> Self {
+        Self { names, ..self }
+    }
+
+    /// Sets the number of slots(must fit the name width).
+    pub fn with_capacity(self, capacity: u64) -> Self {
+        Self {
+            capacity: Some(capacity),
+            ..self
+        }
+    }
+
+    /// Sets the checksum used to write and verify items(the rest of the format is kept).
+    pub fn with_checksum<C>(self, checksum: C) -> FsRingBufferBuilder<C, D, E, T>
+    where
+        C: Checksum + Clone + Send + Sync + 'static,
+    {
+        let format = ItemFormat {
+            checker: checksum,
+            codec: self.format.codec,
+            envelope: self.format.envelope,
+            compat: self.format.compat,
+            max_file: self.format.max_file,
+        };
+        FsRingBufferBuilder {
+            layout: self.layout,
+            names: self.names,
+            capacity: self.capacity,
+            format,
+            ttl: self.ttl,
+            header: self.header,
+            mode: self.mode,
+            policy: self.policy,
+            random: self.random,
+        }
+    }
+
+    /// Sets the item format(items will be stored with a header).
+    pub fn with_format<C, F, G>(
+        self,
+        format: ItemFormat<C, F, G>,
+    ) -> FsRingBufferBuilder<C, F, G, T>
+    where
+        C: Checksum + Clone + Send + Sync + 'static,
+        F: Codec + Clone + Send + Sync + 'static,
+        G: Envelope + Clone + Send + Sync + 'static,
+    {
+        FsRingBufferBuilder {
+            layout: self.layout,
+            names: self.names,
+            capacity: self.capacity,
+            format,
+            ttl: self.ttl,
+            header: true,
+            mode: self.mode,
+            policy: self.policy,
+            random: self.random,
+        }
+    }
+
+    /// Sets the expiry rules(items will be stored with a header to keep the creation time).
+    pub fn with_ttl<C>(self, ttl: Ttl<C>) -> FsRingBufferBuilder<K, D, E, C>
+    where
+        C: Clock + Clone + Send + Sync + 'static,
+    {
+        FsRingBufferBuilder {
+            layout: self.layout,
+            names: self.names,
+            capacity: self.capacity,
+            format: self.format,
+            ttl,
+            header: true,
+            mode: self.mode,
+            policy: self.policy,
+            random: self.random,
+        }
+    }
+
+    /// Sets the strategy to write an item file.
+    pub fn with_mode(self, mode: WriteMode) -> Self {
+        Self { mode, ..self }
+    }
+
+    /// Sets the strategy to push an item into a full buffer.
+    pub fn with_policy(self, policy: FullPolicy) -> Self {
+        Self { policy, ..self }
+    }
+
+    /// Sets the source of random bytes used to choose slots.
+    pub fn with_random<R>(self, random: R) -> Self
+    where
+        R: Read + Send + 'static,
+    {
+        Self {
+            random: Some(Box::new(random)),
+            ..self
+        }
+    }
+
+    /// Checks the combination(`Event::BadRequest` if invalid).
+    ///
+    /// - The capacity must fit the name width.
+    /// - The checksum size must match the length of computed checksums(up to 255 bytes).
+    pub fn validate(&self) -> Result<HexNames, Event> {
+        let names: HexNames = match self.capacity {
+            None => self.names,
+            Some(c) => self.names.with_capacity(c)?,
+        };
+        let checker: &K = &self.format.checker;
+        let size: usize = checker.size();
+        let computed: usize = checker.compute(&[]).len();
+        (size == computed && size <= u8::MAX as usize)
+            .then_some(names)
+            .ok_or(Event::BadRequest)
+    }
+
+    /// Creates new ring buffer.
+    ///
+    /// Orphaned temporary files(atomic writes) and stale slot claims will be removed on open.
+    pub fn build(self) -> Result<Ring, Event> {
+        let names: HexNames = self.validate()?;
+        let random: Box<dyn Read + Send> = match self.random {
+            Some(r) => r,
+            None => Box::new(File::open("/dev/urandom").map_err(|e| {
+                Event::UnexpectedError(format!("Unable to open random source file: {}", e))
+            })?),
+        };
+        let get_name = next::u::next_random_hex_new_from_read(names, random);
+
+        let layout: Layout = self.layout;
+        let (mode, policy) = (self.mode, self.policy);
+        let inner: Box<dyn RingBuffer + Send> = match self.header {
+            false => {
+                let checksum = Arc::new(self.format.checker);
+                let (check_get, check_push, check_over) =
+                    (checksum.clone(), checksum.clone(), checksum.clone());
+                let get = read::read_handler_new_with_checksum(
+                    layout.path_builder(),
+                    checksum.size(),
+                    move |dat: &[u8]| check_get.compute(dat),
+                );
+                let unchecked = write::writer_unchecked_new_layout_with_checksum_mode(
+                    layout.clone(),
+                    move |dat: &[u8]| check_push.compute(dat),
+                    mode,
+                );
+                let overwriter = write::writer_unchecked_new_layout_with_checksum_mode(
+                    layout.clone(),
+                    move |dat: &[u8]| check_over.compute(dat),
+                    mode,
+                );
+                let push = buf::push_handler_layout_new(
+                    &layout, names, get_name, unchecked, overwriter, mode, policy,
+                );
+                Box::new(buf::ring_buffer_impl_layout_new_with_push(
+                    &layout, names, get, push,
+                )?)
+            }
+            true => {
+                let clock: T = self.ttl.clock().clone();
+                let unchecked = write::writer_unchecked_new_layout_with_format_clock(
+                    layout.clone(),
+                    self.format.clone(),
+                    mode,
+                    clock.clone(),
+                );
+                let overwriter = write::writer_unchecked_new_layout_with_format_clock(
+                    layout.clone(),
+                    self.format.clone(),
+                    mode,
+                    clock,
+                );
+                let get = read::read_handler_new_with_format(
+                    layout.path_builder(),
+                    self.format,
+                    self.ttl,
+                );
+                let push = buf::push_handler_layout_new(
+                    &layout, names, get_name, unchecked, overwriter, mode, policy,
+                );
+                Box::new(buf::ring_buffer_impl_layout_new_with_push(
+                    &layout, names, get, push,
+                )?)
+            }
+        };
+        Ok(Ring { inner })
+    }
+}
+
+#[cfg(test)]
+mod test_builder {
+
+    mod fs_ring_buffer_builder {
+        use std::path::Path;
+
+        use std::time::{Duration, SystemTime};
+
+        use crate::builder::{FsRingBufferBuilder, Ring};
+        use crate::checksum::{Checksum, Crc32c};
+        use crate::clock::ManualClock;
+        use crate::evt::Event;
+        use crate::ext::RingBufferExt;
+        use crate::full::Layout;
+        use crate::header::ItemFormat;
+        use crate::item::{Item, Name};
+        use crate::naming::HexNames;
+        use crate::push::FullPolicy;
+        use crate::request::Request;
+        use crate::ttl::Ttl;
+        use crate::write::WriteMode;
+        use crate::RingBuffer;
+
+        impl Checksum for Liar {
+            fn size(&self) -> usize {
+                4
+            }
+
+            fn compute(&self, _: &[u8]) -> Vec<u8> {
+                vec![0; 8]
+            }
+        }
+
+        #[derive(Clone)]
+        struct Liar;
+
+        fn assert_send<S: Send>() {}
+
+        #[test]
+        fn test_send() {
+            assert_send::<Ring>();
+        }
+
+        #[test]
+        fn test_invalid() {
+            let b = FsRingBufferBuilder::new("./test.d/builder/invalid.d");
+            assert_eq!(b.with_capacity(257).build().err(), Some(Event::BadRequest));
+
+            let b = FsRingBufferBuilder::new("./test.d/builder/invalid.d");
+            assert_eq!(b.with_checksum(Liar).build().err(), Some(Event::BadRequest));
+
+            let b = FsRingBufferBuilder::new("./test.d/builder/invalid.d")
+                .with_names(HexNames::u16())
+                .with_capacity(1000)
+                .with_checksum(Crc32c);
+            assert_eq!(b.validate().map(|n: HexNames| n.capacity()), Ok(1000));
+        }
+
+        #[test]
+        #[ignore]
+        fn test_build() {
+            let dirname = Path::new("./test.d/builder/fs_ring_buffer_builder/build.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let random: &[u8] = &[0, 0, 0, 0, 0, 0, 0, 0x42];
+            let mut rb: Ring = FsRingBufferBuilder::new(dirname)
+                .with_capacity(16)
+                .with_checksum(Crc32c)
+                .with_mode(WriteMode::Atomic)
+                .with_policy(FullPolicy::Reject)
+                .with_random(random)
+                .build()
+                .unwrap();
+            let pushed: Name = rb.push(Item::from(b"item".as_slice())).unwrap();
+            assert_eq!(pushed, Name::from("02"));
+            assert_eq!(std::fs::read(dirname.join("02")).unwrap().len(), 8);
+            assert_eq!(
+                rb.get(pushed).unwrap().into_item(),
+                Item::from(b"item".as_slice())
+            );
+        }
+
+        #[test]
+        #[ignore]
+        fn test_format() {
+            let dirname = Path::new("./test.d/builder/fs_ring_buffer_builder/format.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let clock = ManualClock::new(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000));
+            let random: &[u8] = &[0, 0, 0, 0, 0, 0, 0x42, 0x42];
+            let mut rb: Ring = FsRingBufferBuilder::new(dirname)
+                .with_layout(Layout::sharded(dirname, 1, 2).unwrap())
+                .with_names(HexNames::u16())
+                .with_format(ItemFormat::new(Crc32c))
+                .with_ttl(Ttl::new(clock.clone(), Some(Duration::from_secs(60))))
+                .with_mode(WriteMode::Atomic)
+                .with_random(random)
+                .build()
+                .unwrap();
+            let pushed: Name = rb.push(Item::from(b"item".as_slice())).unwrap();
+            assert_eq!(pushed, Name::from("4242"));
+            assert!(dirname.join("42").join("4242").exists());
+            assert_eq!(
+                rb.get(pushed.clone()).unwrap().into_item(),
+                Item::from(b"item".as_slice())
+            );
+
+            clock.advance(Duration::from_secs(61));
+            assert_eq!(
+                rb.handle(Request::Get(pushed.clone())),
+                Event::Expired(pushed)
+            );
+            assert_eq!(rb.handle(Request::Expire), Event::ExpiredItemsRemoved(1));
+        }
+    }
+}
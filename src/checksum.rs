@@ -0,0 +1,535 @@
+use std::sync::Arc;
+
+/// Computes checksum of item data.
+///
+/// The checksum size will be used to split an item file into data and checksum.
+pub trait Checksum {
+    /// Checksum byte length(the length of `compute` result).
+    fn size(&self) -> usize;
+
+    /// Computes checksum.
+    fn compute(&self, dat: &[u8]) -> Vec<u8>;
+
+    /// Algorithm id stored in item headers.
+    fn id(&self) -> u8 {
+        ID_CUSTOM
+    }
+
+    /// Creates new incremental state(`None`: not incremental).
+    ///
+    /// Stream reads/writes need the state(constant memory); `Event::BadRequest` will be returned
+    /// for checksums without the state.
+    fn state(&self) -> Option<Box<dyn ChecksumState + '_>> {
+        None
+    }
+}
+
+/// Incrementally computed checksum(see `Checksum::state`).
+pub trait ChecksumState {
+    /// Feeds the bytes.
+    fn update(&mut self, dat: &[u8]);
+
+    /// Computes checksum of all fed bytes(same as `Checksum::compute`).
+    fn finish(&self) -> Vec<u8>;
+}
+
+/// Algorithm id of `Nop`.
+pub const ID_NOP: u8 = 0;
+
+/// Algorithm id of `Crc32c`.
+pub const ID_CRC32C: u8 = 1;
+
+/// Algorithm id of `Crc64`.
+pub const ID_CRC64: u8 = 2;
+
+/// Algorithm id of `XxHash64`.
+pub const ID_XXHASH64: u8 = 3;
+
+/// Algorithm id of user defined algorithms.
+pub const ID_CUSTOM: u8 = 0xff;
+
+impl<K> Checksum for &K
+where
+    K: Checksum + ?Sized,
+{
+    fn size(&self) -> usize {
+        (**self).size()
+    }
+
+    fn compute(&self, dat: &[u8]) -> Vec<u8> {
+        (**self).compute(dat)
+    }
+
+    fn id(&self) -> u8 {
+        (**self).id()
+    }
+
+    fn state(&self) -> Option<Box<dyn ChecksumState + '_>> {
+        (**self).state()
+    }
+}
+
+impl<K> Checksum for Arc<K>
+where
+    K: Checksum + ?Sized,
+{
+    fn size(&self) -> usize {
+        (**self).size()
+    }
+
+    fn compute(&self, dat: &[u8]) -> Vec<u8> {
+        (**self).compute(dat)
+    }
+
+    fn id(&self) -> u8 {
+        (**self).id()
+    }
+
+    fn state(&self) -> Option<Box<dyn ChecksumState + '_>> {
+        (**self).state()
+    }
+}
+
+/// Converts `Checksum` into a closure.
+pub fn checksum2fn<K>(checker: K) -> impl Fn(&[u8]) -> Vec<u8>
+where
+    K: Checksum,
+{
+    move |dat: &[u8]| checker.compute(dat)
+}
+
+/// No checksum(0 byte).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Nop;
+
+impl Checksum for Nop {
+    fn size(&self) -> usize {
+        0
+    }
+
+    fn compute(&self, _: &[u8]) -> Vec<u8> {
+        vec![]
+    }
+
+    fn id(&self) -> u8 {
+        ID_NOP
+    }
+
+    fn state(&self) -> Option<Box<dyn ChecksumState + '_>> {
+        Some(Box::new(Nop))
+    }
+}
+
+impl ChecksumState for Nop {
+    fn update(&mut self, _: &[u8]) {}
+
+    fn finish(&self) -> Vec<u8> {
+        vec![]
+    }
+}
+
+const fn crc32_table_new(poly: u32) -> [u32; 256] {
+    let mut table: [u32; 256] = [0; 256];
+    let mut i: usize = 0;
+    while i < 256 {
+        let mut c: u32 = i as u32;
+        let mut k: usize = 0;
+        while k < 8 {
+            c = match c & 1 {
+                1 => (c >> 1) ^ poly,
+                _ => c >> 1,
+            };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+const fn crc64_table_new(poly: u64) -> [u64; 256] {
+    let mut table: [u64; 256] = [0; 256];
+    let mut i: usize = 0;
+    while i < 256 {
+        let mut c: u64 = i as u64;
+        let mut k: usize = 0;
+        while k < 8 {
+            c = match c & 1 {
+                1 => (c >> 1) ^ poly,
+                _ => c >> 1,
+            };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+// Castagnoli(reflected)
+const CRC32C_TABLE: [u32; 256] = crc32_table_new(0x82f6_3b78);
+
+// ECMA-182(reflected, same as xz)
+const CRC64_TABLE: [u64; 256] = crc64_table_new(0xc96c_5795_d787_0f42);
+
+fn crc32c_update(c: u32, dat: &[u8]) -> u32 {
+    dat.iter().fold(c, |c: u32, b: &u8| {
+        CRC32C_TABLE[((c ^ u32::from(*b)) & 0xff) as usize] ^ (c >> 8)
+    })
+}
+
+fn crc64_update(c: u64, dat: &[u8]) -> u64 {
+    dat.iter().fold(c, |c: u64, b: &u8| {
+        CRC64_TABLE[((c ^ u64::from(*b)) & 0xff) as usize] ^ (c >> 8)
+    })
+}
+
+/// Computes CRC-32C(Castagnoli).
+pub fn crc32c(dat: &[u8]) -> u32 {
+    !crc32c_update(!0, dat)
+}
+
+/// Computes CRC-64/XZ(ECMA-182).
+pub fn crc64(dat: &[u8]) -> u64 {
+    !crc64_update(!0, dat)
+}
+
+struct Crc32cState(u32);
+
+impl ChecksumState for Crc32cState {
+    fn update(&mut self, dat: &[u8]) {
+        self.0 = crc32c_update(self.0, dat)
+    }
+
+    fn finish(&self) -> Vec<u8> {
+        (!self.0).to_be_bytes().to_vec()
+    }
+}
+
+struct Crc64State(u64);
+
+impl ChecksumState for Crc64State {
+    fn update(&mut self, dat: &[u8]) {
+        self.0 = crc64_update(self.0, dat)
+    }
+
+    fn finish(&self) -> Vec<u8> {
+        (!self.0).to_be_bytes().to_vec()
+    }
+}
+
+/// CRC-32C checksum(4 bytes, big endian).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Crc32c;
+
+impl Checksum for Crc32c {
+    fn size(&self) -> usize {
+        4
+    }
+
+    fn compute(&self, dat: &[u8]) -> Vec<u8> {
+        crc32c(dat).to_be_bytes().to_vec()
+    }
+
+    fn id(&self) -> u8 {
+        ID_CRC32C
+    }
+
+    fn state(&self) -> Option<Box<dyn ChecksumState + '_>> {
+        Some(Box::new(Crc32cState(!0)))
+    }
+}
+
+/// CRC-64/XZ checksum(8 bytes, big endian).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Crc64;
+
+impl Checksum for Crc64 {
+    fn size(&self) -> usize {
+        8
+    }
+
+    fn compute(&self, dat: &[u8]) -> Vec<u8> {
+        crc64(dat).to_be_bytes().to_vec()
+    }
+
+    fn id(&self) -> u8 {
+        ID_CRC64
+    }
+
+    fn state(&self) -> Option<Box<dyn ChecksumState + '_>> {
+        Some(Box::new(Crc64State(!0)))
+    }
+}
+
+const XXH_P1: u64 = 0x9e37_79b1_85eb_ca87;
+const XXH_P2: u64 = 0xc2b2_ae3d_27d4_eb4f;
+const XXH_P3: u64 = 0x1656_67b1_9e37_79f9;
+const XXH_P4: u64 = 0x85eb_ca77_c2b2_ae63;
+const XXH_P5: u64 = 0x27d4_eb2f_1656_67c5;
+
+fn le64(b: &[u8]) -> u64 {
+    b.iter()
+        .rev()
+        .fold(0, |u: u64, byte: &u8| (u << 8) | u64::from(*byte))
+}
+
+fn xxh64_round(acc: u64, lane: u64) -> u64 {
+    acc.wrapping_add(lane.wrapping_mul(XXH_P2))
+        .rotate_left(31)
+        .wrapping_mul(XXH_P1)
+}
+
+fn xxh64_merge(acc: u64, v: u64) -> u64 {
+    (acc ^ xxh64_round(0, v))
+        .wrapping_mul(XXH_P1)
+        .wrapping_add(XXH_P4)
+}
+
+fn xxh64_init(seed: u64) -> [u64; 4] {
+    [
+        seed.wrapping_add(XXH_P1).wrapping_add(XXH_P2),
+        seed.wrapping_add(XXH_P2),
+        seed,
+        seed.wrapping_sub(XXH_P1),
+    ]
+}
+
+fn xxh64_stripe(v: [u64; 4], stripe: &[u8]) -> [u64; 4] {
+    [
+        xxh64_round(v[0], le64(&stripe[0..8])),
+        xxh64_round(v[1], le64(&stripe[8..16])),
+        xxh64_round(v[2], le64(&stripe[16..24])),
+        xxh64_round(v[3], le64(&stripe[24..32])),
+    ]
+}
+
+fn xxh64_converge(v: [u64; 4]) -> u64 {
+    let h: u64 = v[0]
+        .rotate_left(1)
+        .wrapping_add(v[1].rotate_left(7))
+        .wrapping_add(v[2].rotate_left(12))
+        .wrapping_add(v[3].rotate_left(18));
+    v.iter().fold(h, |h: u64, lane: &u64| xxh64_merge(h, *lane))
+}
+
+fn xxh64_avalanche(h: u64) -> u64 {
+    let h: u64 = (h ^ (h >> 33)).wrapping_mul(XXH_P2);
+    let h: u64 = (h ^ (h >> 29)).wrapping_mul(XXH_P3);
+    h ^ (h >> 32)
+}
+
+/// Computes xxHash64 from the lanes of all stripes and the rest(shorter than a stripe).
+fn xxh64_digest(seed: u64, v: [u64; 4], total: u64, rest: &[u8]) -> u64 {
+    let h: u64 = match total {
+        0..=31 => seed.wrapping_add(XXH_P5),
+        _ => xxh64_converge(v),
+    };
+    let h: u64 = h.wrapping_add(total);
+
+    let words = rest.chunks_exact(8);
+    let tail: &[u8] = words.remainder();
+    let h: u64 = words.fold(h, |h: u64, w: &[u8]| {
+        (h ^ xxh64_round(0, le64(w)))
+            .rotate_left(27)
+            .wrapping_mul(XXH_P1)
+            .wrapping_add(XXH_P4)
+    });
+
+    let halves = tail.chunks_exact(4);
+    let bytes: &[u8] = halves.remainder();
+    let h: u64 = halves.fold(h, |h: u64, w: &[u8]| {
+        (h ^ le64(w).wrapping_mul(XXH_P1))
+            .rotate_left(23)
+            .wrapping_mul(XXH_P2)
+            .wrapping_add(XXH_P3)
+    });
+
+    let h: u64 = bytes.iter().fold(h, |h: u64, b: &u8| {
+        (h ^ u64::from(*b).wrapping_mul(XXH_P5))
+            .rotate_left(11)
+            .wrapping_mul(XXH_P1)
+    });
+    xxh64_avalanche(h)
+}
+
+/// Computes xxHash64.
+pub fn xxh64(dat: &[u8], seed: u64) -> u64 {
+    let split: usize = dat.len() - dat.len() % 32;
+    let v: [u64; 4] = dat[..split]
+        .chunks_exact(32)
+        .fold(xxh64_init(seed), xxh64_stripe);
+    xxh64_digest(seed, v, dat.len() as u64, &dat[split..])
+}
+
+struct XxHash64State {
+    seed: u64,
+    v: [u64; 4],
+    total: u64,
+    // bytes of an incomplete stripe
+    rest: Vec<u8>,
+}
+
+impl ChecksumState for XxHash64State {
+    fn update(&mut self, dat: &[u8]) {
+        self.total += dat.len() as u64;
+        self.rest.extend_from_slice(dat);
+        let split: usize = self.rest.len() - self.rest.len() % 32;
+        self.v = self.rest[..split]
+            .chunks_exact(32)
+            .fold(self.v, xxh64_stripe);
+        self.rest.drain(..split);
+    }
+
+    fn finish(&self) -> Vec<u8> {
+        xxh64_digest(self.seed, self.v, self.total, &self.rest)
+            .to_be_bytes()
+            .to_vec()
+    }
+}
+
+/// xxHash64 checksum(8 bytes, big endian).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct XxHash64 {
+    seed: u64,
+}
+
+impl XxHash64 {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl Checksum for XxHash64 {
+    fn size(&self) -> usize {
+        8
+    }
+
+    fn compute(&self, dat: &[u8]) -> Vec<u8> {
+        xxh64(dat, self.seed).to_be_bytes().to_vec()
+    }
+
+    fn id(&self) -> u8 {
+        ID_XXHASH64
+    }
+
+    fn state(&self) -> Option<Box<dyn ChecksumState + '_>> {
+        Some(Box::new(XxHash64State {
+            seed: self.seed,
+            v: xxh64_init(self.seed),
+            total: 0,
+            rest: Vec::with_capacity(32),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test_checksum {
+
+    mod crc32c {
+        use crate::checksum::{self, Checksum, Crc32c};
+
+        #[test]
+        fn test_check() {
+            assert_eq!(checksum::crc32c(b"123456789"), 0xe306_9283);
+            assert_eq!(checksum::crc32c(b""), 0);
+        }
+
+        #[test]
+        fn test_size() {
+            let c = Crc32c;
+            assert_eq!(c.compute(b"123456789"), vec![0xe3, 0x06, 0x92, 0x83]);
+            assert_eq!(c.compute(b"").len(), c.size());
+        }
+    }
+
+    mod crc64 {
+        use crate::checksum::{self, Checksum, Crc64};
+
+        #[test]
+        fn test_check() {
+            assert_eq!(checksum::crc64(b"123456789"), 0x995d_c9bb_df19_39fa);
+            assert_eq!(checksum::crc64(b""), 0);
+        }
+
+        #[test]
+        fn test_size() {
+            let c = Crc64;
+            assert_eq!(c.compute(b"123456789").len(), c.size());
+        }
+    }
+
+    mod xxh64 {
+        use crate::checksum::{self, Checksum, XxHash64};
+
+        #[test]
+        fn test_short() {
+            assert_eq!(checksum::xxh64(b"", 0), 0xef46_db37_51d8_e999);
+            assert_eq!(checksum::xxh64(b"a", 0), 0xd24e_c4f1_a98c_6e5b);
+            assert_eq!(checksum::xxh64(b"abc", 0), 0x44bc_2cf5_ad77_0999);
+        }
+
+        #[test]
+        fn test_long() {
+            let dat: &[u8] = b"Nobody inspects the spammish repetition";
+            assert_eq!(checksum::xxh64(dat, 0), 0xfbce_a83c_8a37_8bf1);
+        }
+
+        #[test]
+        fn test_seed() {
+            let c = XxHash64::new(42);
+            assert_ne!(c.compute(b"abc"), XxHash64::default().compute(b"abc"));
+            assert_eq!(c.compute(b"abc").len(), c.size());
+        }
+    }
+
+    mod checksum_state {
+        use crate::checksum::{Checksum, Crc32c, Crc64, Nop, XxHash64};
+
+        struct Sum;
+
+        impl Checksum for Sum {
+            fn size(&self) -> usize {
+                1
+            }
+
+            fn compute(&self, dat: &[u8]) -> Vec<u8> {
+                vec![dat.iter().fold(0, |s: u8, b: &u8| s.wrapping_add(*b))]
+            }
+        }
+
+        fn chunked<K>(c: &K, dat: &[u8], size: usize) -> Vec<u8>
+        where
+            K: Checksum + ?Sized,
+        {
+            let mut state = c.state().unwrap();
+            dat.chunks(size)
+                .for_each(|chunk: &[u8]| state.update(chunk));
+            state.finish()
+        }
+
+        #[test]
+        fn test_chunks() {
+            let dat: Vec<u8> = (0..1000u32).map(|i: u32| (i * 7) as u8).collect();
+            let checkers: Vec<Box<dyn Checksum>> = vec![
+                Box::new(Nop),
+                Box::new(Crc32c),
+                Box::new(Crc64),
+                Box::new(XxHash64::new(42)),
+            ];
+            for c in checkers.iter().map(Box::as_ref) {
+                for size in [1, 7, 31, 32, 33, 1000] {
+                    assert_eq!(chunked(c, &dat, size), c.compute(&dat));
+                    assert_eq!(chunked(c, &dat[..17], size), c.compute(&dat[..17]));
+                }
+                assert_eq!(c.state().unwrap().finish(), c.compute(b""));
+            }
+            assert!(Sum.state().is_none());
+            assert!(std::sync::Arc::new(Sum).state().is_none());
+        }
+    }
+}
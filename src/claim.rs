@@ -0,0 +1,320 @@
+use std::ffi::OsStr;
+use std::fs::{DirEntry, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::evt::Event;
+use crate::tmp;
+
+const CLAIM_PREFIX: &str = ".";
+const CLAIM_SUFFIX: &str = ".claim";
+
+/// Claims of dead owners not modified within this duration will be removed by `remove_stale`.
+pub const CLAIM_AGE: Duration = Duration::from_secs(60);
+
+/// Byte length of a claim file.
+///
+/// | size | field                         |
+/// |------|-------------------------------|
+/// |    4 | owner process id(BE)          |
+/// |    8 | per process claim counter(BE) |
+pub const CLAIM_SIZE: usize = 12;
+
+static CLAIM_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Checks if the file name is a claim file name created by `claim_path_new`.
+///
+/// * .42.claim -> true
+/// * 42        -> false
+pub fn is_claim_name(s: &str) -> bool {
+    s.len() > CLAIM_PREFIX.len() + CLAIM_SUFFIX.len()
+        && s.starts_with(CLAIM_PREFIX)
+        && s.ends_with(CLAIM_SUFFIX)
+}
+
+/// Creates a claim path for the item path(same directory, hidden, shared by all processes).
+///
+/// Claim files are not temporary files(see `tmp::is_tmp_name`); claims held by running
+/// writers will be kept and only stale claims will be removed(see `remove_stale`).
+///
+/// * ./ring.d/42 -> ./ring.d/.42.claim
+pub fn claim_path_new<P>(p: P) -> Result<PathBuf, Event>
+where
+    P: AsRef<Path>,
+{
+    let p: &Path = p.as_ref();
+    p.file_name()
+        .and_then(OsStr::to_str)
+        .map(|name: &str| p.with_file_name(format!("{}{}{}", CLAIM_PREFIX, name, CLAIM_SUFFIX)))
+        .ok_or_else(|| Event::UnexpectedError(format!("Invalid item path: {:#?}", p)))
+}
+
+/// An exclusive claim of a slot(the claim file will be removed on drop).
+#[derive(Debug)]
+pub struct Claim {
+    path: PathBuf,
+    owner: [u8; CLAIM_SIZE],
+}
+
+fn owner_new() -> [u8; CLAIM_SIZE] {
+    let mut owner = [0; CLAIM_SIZE];
+    owner[..4].copy_from_slice(&std::process::id().to_be_bytes());
+    owner[4..].copy_from_slice(&CLAIM_COUNTER.fetch_add(1, Ordering::Relaxed).to_be_bytes());
+    owner
+}
+
+fn read_owner(p: &Path) -> Option<[u8; CLAIM_SIZE]> {
+    std::fs::read(p)
+        .ok()
+        .and_then(|b: Vec<u8>| b.as_slice().try_into().ok())
+}
+
+impl Claim {
+    /// Claims the item path by creating its claim file exclusively(`create_new`).
+    ///
+    /// `Event::Again` will be returned if another writer(thread or process) holds the claim.
+    pub fn acquire<P>(p: P) -> Result<Self, Event>
+    where
+        P: AsRef<Path>,
+    {
+        let path: PathBuf = claim_path_new(p)?;
+        let owner: [u8; CLAIM_SIZE] = owner_new();
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut f) => {
+                // a claim without an owner will be removed after CLAIM_AGE
+                f.write_all(&owner).ok();
+                Ok(Self { path, owner })
+            }
+            Err(e) => match e.kind() {
+                ErrorKind::AlreadyExists => Err(Event::Again),
+                _ => Err(Event::UnexpectedError(format!(
+                    "Unable to claim {:#?}: {}",
+                    path, e
+                ))),
+            },
+        }
+    }
+}
+
+impl Drop for Claim {
+    /// Removes the claim file unless it was removed as stale and claimed by another writer.
+    fn drop(&mut self) {
+        if read_owner(&self.path) == Some(self.owner) {
+            std::fs::remove_file(&self.path).ok();
+        }
+    }
+}
+
+fn is_stale(d: &DirEntry, age: Duration) -> bool {
+    let live: bool = read_owner(&d.path())
+        .map(|o| u32::from_be_bytes([o[0], o[1], o[2], o[3]]))
+        .and_then(tmp::pid_alive)
+        .unwrap_or(false);
+    !live && tmp::is_old(d, age)
+}
+
+fn remove_claim(d: DirEntry, age: Duration) -> Result<u64, Event> {
+    let is_dir: bool = d.file_type().map(|t| t.is_dir()).unwrap_or(false);
+    if is_dir {
+        return remove_stale(d.path(), age);
+    }
+    let is_claim: bool = d.file_name().to_str().map(is_claim_name).unwrap_or(false);
+    match is_claim && is_stale(&d, age) {
+        false => Ok(0),
+        true => match std::fs::remove_file(d.path()) {
+            Ok(_) => Ok(1),
+            Err(e) => match e.kind() {
+                ErrorKind::NotFound => Ok(0),
+                _ => Err(Event::UnexpectedError(format!(
+                    "Unable to remove stale claim: {}",
+                    e
+                ))),
+            },
+        },
+    }
+}
+
+/// Removes stale claims(left by crashed writers).
+///
+/// A claim is stale if its owner process is not running and it was not modified within the
+/// duration(same as orphaned temporary files). An owner not found in this host(another pid
+/// namespace or host sharing the directory) is treated as not running.
+/// Shard directories will be scanned recursively.
+/// Returns the number of removed files.
+pub fn remove_stale<P>(dirname: P, age: Duration) -> Result<u64, Event>
+where
+    P: AsRef<Path>,
+{
+    let dirents = std::fs::read_dir(dirname)
+        .map_err(|e| Event::UnexpectedError(format!("Unable to read dir: {}", e)))?;
+    dirents.into_iter().try_fold(0, |tot, r| {
+        let d: DirEntry =
+            r.map_err(|e| Event::UnexpectedError(format!("Unable to read dir entry: {}", e)))?;
+        remove_claim(d, age).map(|cnt: u64| cnt + tot)
+    })
+}
+
+#[cfg(test)]
+mod test_claim {
+
+    mod claim_path_new {
+        use std::path::{Path, PathBuf};
+
+        use crate::claim;
+        use crate::tmp;
+
+        #[test]
+        fn test_same_dir() {
+            let p: PathBuf = claim::claim_path_new("./test.d/42").unwrap();
+            assert_eq!(p, Path::new("./test.d/.42.claim"));
+            let name: &str = p.file_name().unwrap().to_str().unwrap();
+            assert!(claim::is_claim_name(name));
+            assert!(!tmp::is_tmp_name(name));
+        }
+
+        #[test]
+        fn test_no_name() {
+            assert!(claim::claim_path_new("/").is_err());
+        }
+    }
+
+    mod claim {
+        use std::path::Path;
+
+        use crate::claim::Claim;
+        use crate::evt::Event;
+
+        #[test]
+        #[ignore]
+        fn test_exclusive() {
+            let dirname = Path::new("./test.d/claim/claim/exclusive.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let p = dirname.join("42");
+            let c: Claim = Claim::acquire(&p).unwrap();
+            assert_eq!(Claim::acquire(&p).unwrap_err(), Event::Again);
+            assert!(Claim::acquire(dirname.join("43")).is_ok());
+            drop(c);
+            assert!(Claim::acquire(&p).is_ok());
+            assert!(!dirname.join(".42.claim").exists());
+        }
+
+        #[test]
+        #[ignore]
+        fn test_drop_reclaimed() {
+            let dirname = Path::new("./test.d/claim/claim/drop_reclaimed.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let p = dirname.join("42");
+            let stale: Claim = Claim::acquire(&p).unwrap();
+            // removed as stale, then claimed by another writer
+            std::fs::remove_file(dirname.join(".42.claim")).unwrap();
+            let c: Claim = Claim::acquire(&p).unwrap();
+            drop(stale);
+            assert_eq!(Claim::acquire(&p).unwrap_err(), Event::Again);
+            drop(c);
+            assert!(!dirname.join(".42.claim").exists());
+        }
+    }
+
+    mod remove_stale {
+        use std::io::{BufRead, BufReader, Write};
+        use std::path::Path;
+        use std::process::{Child, Command, Stdio};
+        use std::time::{Duration, SystemTime};
+
+        use crate::claim::{self, Claim};
+        use crate::evt::Event;
+        use crate::tmp;
+
+        const CHILD_ENV: &str = "FSRING_TEST_CLAIM_CHILD";
+
+        /// Holds a claim until stdin closes(runs in a child process only).
+        #[test]
+        #[ignore]
+        fn test_child() {
+            let p: String = match std::env::var(CHILD_ENV) {
+                Ok(p) => p,
+                Err(_) => return,
+            };
+            let _c: Claim = Claim::acquire(p).unwrap();
+            println!("claimed");
+            std::io::stdout().flush().unwrap();
+            std::io::stdin().read_line(&mut String::new()).ok();
+        }
+
+        #[test]
+        #[ignore]
+        fn test_processes() {
+            let dirname = Path::new("./test.d/claim/remove_stale/processes.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+            let p = dirname.join("42");
+
+            let mut child: Child = Command::new(std::env::current_exe().unwrap())
+                .args([
+                    "--exact",
+                    "claim::test_claim::remove_stale::test_child",
+                    "--include-ignored",
+                    "--nocapture",
+                ])
+                .env(CHILD_ENV, &p)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()
+                .unwrap();
+            let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+            assert!(lines.any(|l| l.unwrap().ends_with("claimed")));
+
+            // the claim of a running process survives the orphan cleaner
+            let clean = tmp::orphan_cleaner_new_default(dirname);
+            assert_eq!(clean(), Ok(0));
+            assert_eq!(Claim::acquire(&p).unwrap_err(), Event::Again);
+
+            // the claim of a crashed process will be removed once old
+            child.kill().unwrap();
+            child.wait().unwrap();
+            assert_eq!(clean(), Ok(0));
+            std::fs::File::options()
+                .write(true)
+                .open(dirname.join(".42.claim"))
+                .unwrap()
+                .set_modified(SystemTime::UNIX_EPOCH)
+                .unwrap();
+            assert_eq!(clean(), Ok(1));
+            assert!(Claim::acquire(&p).is_ok());
+        }
+
+        #[test]
+        #[ignore]
+        fn test_old() {
+            let dirname = Path::new("./test.d/claim/remove_stale/old.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let c: Claim = Claim::acquire(dirname.join("42")).unwrap();
+            let held = dirname.join(".42.claim");
+            assert_eq!(claim::remove_stale(dirname, claim::CLAIM_AGE), Ok(0));
+
+            // the old claim of a live owner(long push or batch) survives
+            std::fs::File::options()
+                .write(true)
+                .open(&held)
+                .unwrap()
+                .set_modified(SystemTime::UNIX_EPOCH)
+                .unwrap();
+            assert_eq!(claim::remove_stale(dirname, Duration::from_secs(60)), Ok(0));
+            assert!(held.exists());
+            assert_eq!(
+                Claim::acquire(dirname.join("42")).unwrap_err(),
+                Event::Again
+            );
+            drop(c);
+            assert!(!held.exists());
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// Gets the current time(use `ManualClock` for deterministic tests).
+pub trait Clock {
+    fn now(&self) -> SystemTime;
+}
+
+/// Uses `SystemTime::now`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Clock which moves only when asked(clones share the same time).
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    micros: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    /// Creates new clock which starts at the time(microseconds since unix epoch).
+    pub fn new(t: SystemTime) -> Self {
+        let c = Self::default();
+        c.set(t);
+        c
+    }
+
+    pub fn set(&self, t: SystemTime) {
+        let micros: u64 = t
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d: Duration| d.as_micros().try_into().unwrap_or(u64::MAX))
+            .unwrap_or(0);
+        self.micros.store(micros, Ordering::SeqCst)
+    }
+
+    pub fn advance(&self, d: Duration) {
+        let micros: u64 = d.as_micros().try_into().unwrap_or(u64::MAX);
+        self.micros.fetch_add(micros, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_micros(self.micros.load(Ordering::SeqCst))
+    }
+}
+
+#[cfg(test)]
+mod test_clock {
+
+    mod manual_clock {
+        use std::time::{Duration, SystemTime};
+
+        use crate::clock::{Clock, ManualClock};
+
+        #[test]
+        fn test_shared() {
+            let t: SystemTime = SystemTime::UNIX_EPOCH + Duration::from_secs(42);
+            let c = ManualClock::new(t);
+            let shared = c.clone();
+            c.advance(Duration::from_secs(1));
+            assert_eq!(shared.now(), t + Duration::from_secs(1));
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use std::sync::Arc;
+
+use crate::evt::Event;
+
+/// Encodes payloads before writing and decodes them after reading.
+pub trait Codec {
+    /// Codec id stored in item headers.
+    fn id(&self) -> u8;
+
+    fn encode(&self, dat: &[u8]) -> Vec<u8>;
+
+    fn decode(&self, dat: &[u8]) -> Result<Vec<u8>, Event>;
+}
+
+impl<D> Codec for &D
+where
+    D: Codec + ?Sized,
+{
+    fn id(&self) -> u8 {
+        (**self).id()
+    }
+
+    fn encode(&self, dat: &[u8]) -> Vec<u8> {
+        (**self).encode(dat)
+    }
+
+    fn decode(&self, dat: &[u8]) -> Result<Vec<u8>, Event> {
+        (**self).decode(dat)
+    }
+}
+
+impl<D> Codec for Arc<D>
+where
+    D: Codec + ?Sized,
+{
+    fn id(&self) -> u8 {
+        (**self).id()
+    }
+
+    fn encode(&self, dat: &[u8]) -> Vec<u8> {
+        (**self).encode(dat)
+    }
+
+    fn decode(&self, dat: &[u8]) -> Result<Vec<u8>, Event> {
+        (**self).decode(dat)
+    }
+}
+
+/// Codec id of `Identity`.
+pub const ID_IDENTITY: u8 = 0;
+
+/// Codec id of `Lz77`.
+pub const ID_LZ77: u8 = 1;
+
+/// Stores payloads as is.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Identity;
+
+impl Codec for Identity {
+    fn id(&self) -> u8 {
+        ID_IDENTITY
+    }
+
+    fn encode(&self, dat: &[u8]) -> Vec<u8> {
+        dat.to_vec()
+    }
+
+    fn decode(&self, dat: &[u8]) -> Result<Vec<u8>, Event> {
+        Ok(dat.to_vec())
+    }
+}
+
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = MIN_MATCH + 0x7f;
+const MAX_LITERALS: usize = 0x80;
+const MAX_OFFSET: usize = 0xffff;
+const HASH_BITS: u32 = 14;
+
+fn hash3(b: &[u8]) -> usize {
+    let u: u32 = u32::from(b[0]) | u32::from(b[1]) << 8 | u32::from(b[2]) << 16;
+    (u.wrapping_mul(0x9e37_79b1) >> (32 - HASH_BITS)) as usize
+}
+
+fn push_literals(out: &mut Vec<u8>, lit: &[u8]) {
+    lit.chunks(MAX_LITERALS).for_each(|chunk: &[u8]| {
+        out.push((chunk.len() - 1) as u8);
+        out.extend(chunk);
+    })
+}
+
+fn match_len(src: &[u8], cand: usize, i: usize) -> usize {
+    src[i..]
+        .iter()
+        .zip(&src[cand..])
+        .take(MAX_MATCH)
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+/// Compresses bytes(LZ77, 64KiB window).
+///
+/// * 8 bytes: original length(big endian)
+/// * 0x00..=0x7f: literal run(1..=128 bytes) follows
+/// * 0x80..=0xff: match(3..=130 bytes) with 2 bytes offset(big endian)
+pub fn lz77_compress(src: &[u8]) -> Vec<u8> {
+    let mut out: Vec<u8> = Vec::with_capacity(src.len() / 2 + 16);
+    out.extend((src.len() as u64).to_be_bytes());
+    let mut table: Vec<usize> = vec![usize::MAX; 1 << HASH_BITS];
+    let mut lit_start: usize = 0;
+    let mut i: usize = 0;
+    while i + MIN_MATCH <= src.len() {
+        let h: usize = hash3(&src[i..]);
+        let cand: usize = table[h];
+        table[h] = i;
+        let len: usize = match cand {
+            usize::MAX => 0,
+            c if MAX_OFFSET < i - c => 0,
+            c => match_len(src, c, i),
+        };
+        if len < MIN_MATCH {
+            i += 1;
+            continue;
+        }
+        push_literals(&mut out, &src[lit_start..i]);
+        out.push(0x80 | (len - MIN_MATCH) as u8);
+        out.extend(((i - cand) as u16).to_be_bytes());
+        let end: usize = i + len;
+        (i + 1..end.min(src.len() + 1 - MIN_MATCH)).for_each(|j: usize| {
+            table[hash3(&src[j..])] = j;
+        });
+        i = end;
+        lit_start = i;
+    }
+    push_literals(&mut out, &src[lit_start..]);
+    out
+}
+
+/// Decompresses bytes compressed by `lz77_compress`.
+pub fn lz77_decompress(src: &[u8]) -> Result<Vec<u8>, Event> {
+    let invalid = |s: &str| Event::UnexpectedError(format!("Invalid compressed data: {}", s));
+    let (len, mut rest) = match src {
+        [a, b, c, d, e, f, g, h, rest @ ..] => {
+            (u64::from_be_bytes([*a, *b, *c, *d, *e, *f, *g, *h]), rest)
+        }
+        _ => return Err(invalid("too short")),
+    };
+    let len: usize = len.try_into().map_err(|_| invalid("too long"))?;
+    // every op(2..=3 bytes) yields at most MAX_MATCH bytes.
+    let mut out: Vec<u8> = Vec::with_capacity(len.min(src.len().saturating_mul(MAX_MATCH)));
+    while let Some((&op, tail)) = rest.split_first() {
+        rest = match op {
+            0x00..=0x7f => {
+                let n: usize = usize::from(op) + 1;
+                let lit: &[u8] = tail.get(..n).ok_or_else(|| invalid("truncated literals"))?;
+                out.extend(lit);
+                &tail[n..]
+            }
+            _ => {
+                let n: usize = usize::from(op & 0x7f) + MIN_MATCH;
+                let off: usize = match tail {
+                    [hi, lo, ..] => usize::from(u16::from_be_bytes([*hi, *lo])),
+                    _ => return Err(invalid("truncated match")),
+                };
+                let start: usize = out
+                    .len()
+                    .checked_sub(off)
+                    .filter(|_| 0 < off)
+                    .ok_or_else(|| invalid("bad offset"))?;
+                (start..start + n).for_each(|j: usize| out.push(out[j]));
+                &tail[2..]
+            }
+        };
+        if len < out.len() {
+            return Err(invalid("too long"));
+        }
+    }
+    (out.len() == len)
+        .then_some(out)
+        .ok_or_else(|| invalid("length mismatch"))
+}
+
+/// Dependency-free LZ77 compressor(see `lz77_compress`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Lz77;
+
+impl Codec for Lz77 {
+    fn id(&self) -> u8 {
+        ID_LZ77
+    }
+
+    fn encode(&self, dat: &[u8]) -> Vec<u8> {
+        lz77_compress(dat)
+    }
+
+    fn decode(&self, dat: &[u8]) -> Result<Vec<u8>, Event> {
+        lz77_decompress(dat)
+    }
+}
+
+/// Checks if bytes encoded by the codec id can be decoded(built-in codecs or the custom codec).
+pub fn is_known<D>(id: u8, custom: &D) -> bool
+where
+    D: Codec,
+{
+    matches!(id, ID_IDENTITY | ID_LZ77) || id == custom.id()
+}
+
+/// Decodes bytes by the codec id(built-in codecs, or the custom codec which has the id).
+pub fn decode_by_id<D>(id: u8, dat: &[u8], custom: &D) -> Result<Vec<u8>, Event>
+where
+    D: Codec,
+{
+    match id {
+        ID_IDENTITY => Identity.decode(dat),
+        ID_LZ77 => Lz77.decode(dat),
+        i if i == custom.id() => custom.decode(dat),
+        _ => Err(Event::UnexpectedError(format!("Unknown codec: {}", id))),
+    }
+}
+
+#[cfg(test)]
+mod test_codec {
+
+    mod lz77 {
+        use crate::codec::{self, Codec, Lz77};
+
+        fn roundtrip(dat: &[u8]) -> Vec<u8> {
+            let c: Vec<u8> = Lz77.encode(dat);
+            assert_eq!(Lz77.decode(&c).unwrap(), dat);
+            c
+        }
+
+        #[test]
+        fn test_empty() {
+            assert_eq!(roundtrip(b"").len(), 8);
+        }
+
+        #[test]
+        fn test_short() {
+            roundtrip(b"a");
+            roundtrip(b"abcabc");
+        }
+
+        #[test]
+        fn test_json() {
+            let line: &[u8] = br#"{"level":"info","msg":"request done","status":200}"#;
+            let dat: Vec<u8> = line.repeat(100);
+            let c: Vec<u8> = roundtrip(&dat);
+            assert!(c.len() * 10 < dat.len());
+        }
+
+        #[test]
+        fn test_overlap() {
+            roundtrip(&[0x42; 1000]);
+        }
+
+        #[test]
+        fn test_random() {
+            let dat: Vec<u8> = (0..70000u32)
+                .map(|i: u32| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+                .collect();
+            roundtrip(&dat);
+        }
+
+        #[test]
+        fn test_broken() {
+            let c: Vec<u8> = Lz77.encode(b"abcabcabcabc");
+            assert!(codec::lz77_decompress(&c[..c.len() - 1]).is_err());
+            assert!(codec::lz77_decompress(b"\0\0\0\0\0\0\0\x04\x80\x00\x01").is_err());
+        }
+    }
+
+    mod decode_by_id {
+        use crate::codec::{self, Codec, Identity, Lz77, ID_LZ77};
+
+        #[test]
+        fn test_builtin() {
+            let c: Vec<u8> = Lz77.encode(b"abc");
+            assert_eq!(
+                codec::decode_by_id(ID_LZ77, &c, &Identity),
+                Ok(b"abc".to_vec())
+            );
+            assert!(codec::decode_by_id(0x42, &c, &Identity).is_err());
+            assert!(!codec::is_known(0x42, &Identity));
+            assert!(codec::is_known(ID_LZ77, &Identity));
+        }
+    }
+}
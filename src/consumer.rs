@@ -0,0 +1,148 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use crate::batch;
+use crate::clock::Clock;
+use crate::evt::Event;
+use crate::item::{Item, Name};
+use crate::lease::Leases;
+use crate::order::{Order, Page};
+use crate::request::Request;
+use crate::RingBuffer;
+
+/// Ring buffer wrapper which lets many consumers take items(`Request::Claim`).
+///
+/// A claimed item will be hidden from other consumers until `Request::Ack`(removed),
+/// `Request::Nack`(released) or the visibility timeout of the lease.
+/// Candidates will be tried oldest first(`order::Order::Created`) by their leases; only the
+/// claimed item will be read.
+/// Leases of removed items(`Request::Del`, `Request::Vacuum`, `Request::Expire`, overwritten
+/// items) will be removed as well; leases left by other processes will be removed by the orphan
+/// cleaner(see `lease::remove_stale`).
+pub struct ConsumerRingBuffer<R, B, C> {
+    pub inner: R,
+    pub leases: Leases<B, C>,
+}
+
+impl<R, B, C> ConsumerRingBuffer<R, B, C>
+where
+    R: RingBuffer,
+    B: Fn(Name) -> PathBuf,
+    C: Clock,
+{
+    /// Takes the lease first(header only), then reads the item once.
+    fn claim_named(&mut self, name: Name) -> Option<Event> {
+        match self.leases.acquire(&name) {
+            Ok(true) => {}
+            Ok(false) => return None,
+            Err(e) => return Some(e),
+        }
+        // removed, broken or expired items can not be claimed
+        match self.inner.handle(Request::Get(name.clone())) {
+            Event::ItemGot(named) => Some(Event::ItemGot(named)),
+            _ => self.leases.remove(&name).err(),
+        }
+    }
+
+    /// Lists names oldest first(slot order if the inner buffer can not order names).
+    fn list(&mut self) -> Result<Vec<Name>, Event> {
+        let oldest = Request::ListPage(Page::new(Order::Created));
+        let listed: Event = match self.inner.handle(oldest) {
+            Event::BadRequest => self.inner.handle(Request::List),
+            e => e,
+        };
+        match listed {
+            Event::NamesGot(names) => Ok(names),
+            e => Err(e),
+        }
+    }
+
+    fn handle_claim(&mut self) -> Event {
+        let names: Vec<Name> = match self.list() {
+            Ok(names) => names,
+            Err(e) => return e,
+        };
+        names
+            .into_iter()
+            .find_map(|name: Name| self.claim_named(name))
+            .unwrap_or(Event::NoClaimableItem)
+    }
+
+    fn handle_ack(&mut self, name: Name) -> Event {
+        let inner: &mut R = &mut self.inner;
+        self.leases
+            .release(&name, || inner.handle(Request::Del(name.clone())))
+    }
+
+    fn handle_nack(&mut self, name: Name) -> Event {
+        self.leases.release(&name, || Event::Success)
+    }
+
+    fn handle_push(&mut self, item: Item) -> Event {
+        match self.inner.handle(Request::Push(item)) {
+            Event::ItemOverwrote(n) => match self.leases.remove(&n) {
+                Ok(_) => Event::ItemOverwrote(n),
+                Err(e) => e,
+            },
+            e => e,
+        }
+    }
+
+    /// Removes leases of items removed by the request(`Request::Vacuum`, `Request::Expire`).
+    fn handle_removal(&mut self, req: Request) -> Event {
+        let before: Vec<Name> = match self.inner.handle(Request::List) {
+            Event::NamesGot(names) => names,
+            e => return e,
+        };
+        let removed: Event = self.inner.handle(req);
+        let after: HashSet<Name> = match self.inner.handle(Request::List) {
+            Event::NamesGot(names) => names.into_iter().collect(),
+            e => return e,
+        };
+        let left: Result<(), Event> = before
+            .iter()
+            .filter(|n: &&Name| !after.contains(*n))
+            .try_for_each(|n: &Name| self.leases.remove(n));
+        match left {
+            Ok(_) => removed,
+            Err(e) => e,
+        }
+    }
+
+    fn handle_del(&mut self, name: Name) -> Event {
+        match self.inner.handle(Request::Del(name.clone())) {
+            Event::Success => self
+                .leases
+                .remove(&name)
+                .map(|_| Event::Success)
+                .unwrap_or_else(|e| e),
+            e => e,
+        }
+    }
+}
+
+impl<R, B, C> RingBuffer for ConsumerRingBuffer<R, B, C>
+where
+    R: RingBuffer,
+    B: Fn(Name) -> PathBuf,
+    C: Clock,
+{
+    fn handle(&mut self, req: Request) -> Event {
+        match req {
+            Request::Claim => self.handle_claim(),
+            Request::Ack(name) => self.handle_ack(name),
+            Request::Nack(name) => self.handle_nack(name),
+            Request::Push(item) => self.handle_push(item),
+            Request::PushBatch(items) => {
+                batch::handle_many(self, items.into_iter().map(Request::Push).collect())
+            }
+            Request::Vacuum => self.handle_removal(Request::Vacuum),
+            Request::Expire => self.handle_removal(Request::Expire),
+            Request::Del(name) => self.handle_del(name),
+            Request::DelMany(names) => {
+                batch::handle_many(self, names.into_iter().map(Request::Del).collect())
+            }
+            q => self.inner.handle(q),
+        }
+    }
+}
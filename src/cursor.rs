@@ -0,0 +1,188 @@
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::evt::Event;
+use crate::write;
+
+/// File name to store the cursor in the buffer directory.
+pub const CURSOR_NAME: &str = ".cursor";
+
+/// Sequence numbers of the oldest item(head) and the next item to write(tail).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Cursor {
+    head: u64,
+    tail: u64,
+}
+
+impl Cursor {
+    /// Creates new cursor(`Event::BadRequest` if head > tail).
+    pub fn new(head: u64, tail: u64) -> Result<Self, Event> {
+        (head <= tail)
+            .then_some(Self { head, tail })
+            .ok_or(Event::BadRequest)
+    }
+
+    pub fn head(&self) -> u64 {
+        self.head
+    }
+
+    pub fn tail(&self) -> u64 {
+        self.tail
+    }
+
+    /// Number of items between head and tail.
+    pub fn len(&self) -> u64 {
+        self.tail - self.head
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head == self.tail
+    }
+
+    /// Creates new cursor which has an extra item.
+    pub fn pushed(&self) -> Self {
+        Self {
+            head: self.head,
+            tail: self.tail.saturating_add(1),
+        }
+    }
+
+    /// Creates new cursor without the oldest item.
+    pub fn popped(&self) -> Self {
+        Self {
+            head: self.tail.min(self.head + 1),
+            tail: self.tail,
+        }
+    }
+}
+
+impl From<&Cursor> for Vec<u8> {
+    fn from(c: &Cursor) -> Self {
+        format!("{:016x}\n{:016x}\n", c.head, c.tail).into_bytes()
+    }
+}
+
+fn hex2u64(s: Option<&str>) -> Result<u64, Event> {
+    s.and_then(|hex: &str| u64::from_str_radix(hex, 16).ok())
+        .ok_or_else(|| Event::UnexpectedError("Invalid cursor".into()))
+}
+
+impl TryFrom<&[u8]> for Cursor {
+    type Error = Event;
+    fn try_from(b: &[u8]) -> Result<Self, Self::Error> {
+        let s: &str = std::str::from_utf8(b)
+            .map_err(|e| Event::UnexpectedError(format!("Invalid cursor: {}", e)))?;
+        let mut lines = s.lines();
+        let head: u64 = hex2u64(lines.next())?;
+        let tail: u64 = hex2u64(lines.next())?;
+        Cursor::new(head, tail)
+            .map_err(|_| Event::UnexpectedError(format!("Invalid cursor: {}..{}", head, tail)))
+    }
+}
+
+/// Creates a path to store the cursor.
+pub fn cursor_path_new<P>(dirname: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    dirname.as_ref().join(CURSOR_NAME)
+}
+
+fn path2cursor<P>(p: P) -> Result<Cursor, Event>
+where
+    P: AsRef<Path>,
+{
+    match std::fs::read(p) {
+        Ok(raw) => Cursor::try_from(raw.as_slice()),
+        Err(e) => match e.kind() {
+            ErrorKind::NotFound => Ok(Cursor::default()),
+            _ => Err(Event::UnexpectedError(format!(
+                "Unable to read cursor: {}",
+                e
+            ))),
+        },
+    }
+}
+
+/// Creates new cursor loader which uses dirname to find the cursor file.
+///
+/// A missing cursor file will be converted to an empty cursor.
+pub fn cursor_loader_new_default<P>(dirname: P) -> impl Fn() -> Result<Cursor, Event>
+where
+    P: AsRef<Path>,
+{
+    let p: PathBuf = cursor_path_new(dirname);
+    move || path2cursor(&p)
+}
+
+/// Creates new cursor saver which uses dirname to write the cursor file atomically.
+pub fn cursor_saver_new_default<P>(dirname: P) -> impl Fn(&Cursor) -> Result<(), Event>
+where
+    P: AsRef<Path>,
+{
+    let p: PathBuf = cursor_path_new(dirname);
+    move |c: &Cursor| {
+        let dat: Vec<u8> = c.into();
+        write::write_atomic(&dat, &p)
+    }
+}
+
+#[cfg(test)]
+mod test_cursor {
+
+    mod cursor {
+        use crate::cursor::Cursor;
+        use crate::evt::Event;
+
+        #[test]
+        fn test_invalid() {
+            assert_eq!(Cursor::new(1, 0), Err(Event::BadRequest));
+        }
+
+        #[test]
+        fn test_push_pop() {
+            let c: Cursor = Cursor::default().pushed().pushed();
+            assert_eq!(c.len(), 2);
+            let c: Cursor = c.popped().popped().popped();
+            assert!(c.is_empty());
+            assert_eq!(c.head(), 2);
+            assert_eq!(c.tail(), 2);
+        }
+
+        #[test]
+        fn test_bytes() {
+            let c: Cursor = Cursor::new(0x42, 0x142).unwrap();
+            let b: Vec<u8> = (&c).into();
+            assert_eq!(b, b"0000000000000042\n0000000000000142\n");
+            let d: Cursor = Cursor::try_from(b.as_slice()).unwrap();
+            assert_eq!(c, d);
+        }
+
+        #[test]
+        fn test_broken() {
+            let r = Cursor::try_from(b"0000000000000042\n".as_slice());
+            assert!(r.is_err());
+        }
+    }
+
+    mod cursor_saver_new_default {
+        use std::path::Path;
+
+        use crate::cursor::{self, Cursor};
+
+        #[test]
+        #[ignore]
+        fn test_save_load() {
+            let dirname = Path::new("./test.d/cursor/cursor_saver_new_default/save_load.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let load = cursor::cursor_loader_new_default(dirname);
+            assert_eq!(load(), Ok(Cursor::default()));
+
+            let save = cursor::cursor_saver_new_default(dirname);
+            save(&Cursor::new(3, 5).unwrap()).unwrap();
+            assert_eq!(load(), Ok(Cursor::new(3, 5).unwrap()));
+        }
+    }
+}
@@ -2,6 +2,7 @@ use std::fs::File;
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 
+use crate::batch;
 use crate::evt::Event;
 use crate::full;
 use crate::item::Name;
@@ -10,7 +11,10 @@ fn truncate_as_del<P>(p: P) -> Result<(), ErrorKind>
 where
     P: AsRef<Path>,
 {
-    File::create(p).map(|_| ()).map_err(|e| e.kind())
+    let f: File = File::create(p.as_ref()).map_err(|e| e.kind())?;
+    // truncates in a batch will be synced by the batch(see `batch::durable`)
+    batch::defer_sync(f, p);
+    Ok(())
 }
 
 fn del_new<B>(path_builder: B) -> impl Fn(Name) -> Result<(), ErrorKind>
@@ -24,12 +28,17 @@ where
 }
 
 /// Creates new delete handler which uses a closure to build path from `Name`.
+///
+/// Names escaping the directory will be rejected(`Event::BadRequest`; see `full::name_check`).
 pub fn del_handler_new<B>(path_builder: B) -> impl Fn(Name) -> Event
 where
     B: Fn(Name) -> PathBuf,
 {
     let f = del_new(path_builder);
     move |n: Name| {
+        if let Err(e) = full::name_check(&n) {
+            return e;
+        }
         f(n).map(|_| Event::Success).unwrap_or_else(|e| match e {
             ErrorKind::NotFound => Event::Success,
             _ => Event::UnexpectedError(format!("Unable to truncate: {}", e)),
@@ -0,0 +1,285 @@
+#[cfg(feature = "chacha20poly1305")]
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+#[cfg(feature = "chacha20poly1305")]
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+
+use crate::evt::Event;
+use crate::item::Name;
+
+/// Seals(encrypts and authenticates) payloads before writing and opens them after reading.
+///
+/// A sealed payload starts with the key id so that items sealed by an old key can be opened
+/// after the key rotation.
+/// The additional data(e.g, the item header and the name) will be authenticated, not sealed.
+pub trait Envelope {
+    /// Key id used to seal new payloads(`None`: payloads will be stored as is).
+    fn key_id(&self) -> Option<u8>;
+
+    /// Checks if payloads sealed by the key id can be opened.
+    fn has_key(&self, key_id: u8) -> bool;
+
+    /// Seals the payload(key id + sealed bytes).
+    fn seal(&self, aad: &[u8], dat: &[u8]) -> Result<Vec<u8>, Event>;
+
+    /// Opens the sealed payload(`Err` on unknown key id or authentication failure).
+    fn open(&self, aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>, Event>;
+}
+
+impl<E> Envelope for &E
+where
+    E: Envelope + ?Sized,
+{
+    fn key_id(&self) -> Option<u8> {
+        (**self).key_id()
+    }
+
+    fn has_key(&self, key_id: u8) -> bool {
+        (**self).has_key(key_id)
+    }
+
+    fn seal(&self, aad: &[u8], dat: &[u8]) -> Result<Vec<u8>, Event> {
+        (**self).seal(aad, dat)
+    }
+
+    fn open(&self, aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>, Event> {
+        (**self).open(aad, sealed)
+    }
+}
+
+impl<E> Envelope for Arc<E>
+where
+    E: Envelope + ?Sized,
+{
+    fn key_id(&self) -> Option<u8> {
+        (**self).key_id()
+    }
+
+    fn has_key(&self, key_id: u8) -> bool {
+        (**self).has_key(key_id)
+    }
+
+    fn seal(&self, aad: &[u8], dat: &[u8]) -> Result<Vec<u8>, Event> {
+        (**self).seal(aad, dat)
+    }
+
+    fn open(&self, aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>, Event> {
+        (**self).open(aad, sealed)
+    }
+}
+
+/// Stores payloads as is(sealed payloads can not be opened).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Plain;
+
+impl Envelope for Plain {
+    fn key_id(&self) -> Option<u8> {
+        None
+    }
+
+    fn has_key(&self, _: u8) -> bool {
+        false
+    }
+
+    fn seal(&self, _: &[u8], dat: &[u8]) -> Result<Vec<u8>, Event> {
+        Ok(dat.to_vec())
+    }
+
+    fn open(&self, _: &[u8], _: &[u8]) -> Result<Vec<u8>, Event> {
+        Err(Event::UnexpectedError(
+            "No key to open sealed payload".into(),
+        ))
+    }
+}
+
+/// Opens the sealed payload of the named item.
+///
+/// A payload sealed by an unknown key will be reported as `Event::UnsupportedFormat`(the key may
+/// be configured later); an empty payload or an authentication failure as `Event::Broken`.
+pub fn open_named<E>(envelope: &E, n: &Name, aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>, Event>
+where
+    E: Envelope,
+{
+    let key_id: u8 = *sealed.first().ok_or_else(|| Event::Broken(n.clone()))?;
+    envelope
+        .has_key(key_id)
+        .then_some(())
+        .ok_or_else(|| Event::UnsupportedFormat(n.clone()))?;
+    envelope
+        .open(aad, sealed)
+        .map_err(|_| Event::Broken(n.clone()))
+}
+
+#[cfg(feature = "chacha20poly1305")]
+const NONCE_SIZE: usize = 12;
+
+#[cfg(feature = "chacha20poly1305")]
+const TAG_SIZE: usize = 16;
+
+/// ChaCha20-Poly1305 envelope with a key ring(requires the `chacha20poly1305` feature).
+///
+/// Sealed payload: key id(1 byte) + nonce(12 bytes, random) + ciphertext + tag(16 bytes).
+/// The key id and the additional data will be authenticated.
+#[cfg(feature = "chacha20poly1305")]
+#[derive(Clone)]
+pub struct ChaCha20Poly1305 {
+    current: u8,
+    keys: BTreeMap<u8, chacha20poly1305::ChaCha20Poly1305>,
+}
+
+#[cfg(feature = "chacha20poly1305")]
+impl ChaCha20Poly1305 {
+    /// Creates new envelope which seals payloads by the key.
+    pub fn new(key_id: u8, key: [u8; 32]) -> Self {
+        Self {
+            current: key_id,
+            keys: BTreeMap::from([(key_id, chacha20poly1305::ChaCha20Poly1305::new(&key.into()))]),
+        }
+    }
+
+    /// Creates new envelope which can also open payloads sealed by the key(e.g, a retired key).
+    pub fn with_key(mut self, key_id: u8, key: [u8; 32]) -> Self {
+        self.keys
+            .entry(key_id)
+            .or_insert_with(|| chacha20poly1305::ChaCha20Poly1305::new(&key.into()));
+        self
+    }
+
+    /// Key ids to open payloads.
+    pub fn key_ids(&self) -> impl Iterator<Item = u8> + '_ {
+        self.keys.keys().copied()
+    }
+}
+
+#[cfg(feature = "chacha20poly1305")]
+impl std::fmt::Debug for ChaCha20Poly1305 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChaCha20Poly1305")
+            .field("current", &self.current)
+            .field("key_ids", &self.key_ids().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[cfg(feature = "chacha20poly1305")]
+impl Envelope for ChaCha20Poly1305 {
+    fn key_id(&self) -> Option<u8> {
+        Some(self.current)
+    }
+
+    fn has_key(&self, key_id: u8) -> bool {
+        self.keys.contains_key(&key_id)
+    }
+
+    fn seal(&self, aad: &[u8], dat: &[u8]) -> Result<Vec<u8>, Event> {
+        let cipher = self
+            .keys
+            .get(&self.current)
+            .ok_or_else(|| Event::UnexpectedError("No current key".into()))?;
+        let nonce = chacha20poly1305::ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ad: Vec<u8> = [&[self.current], aad].concat();
+        let ct: Vec<u8> = cipher
+            .encrypt(&nonce, Payload { msg: dat, aad: &ad })
+            .map_err(|_| Event::UnexpectedError("Unable to seal payload".into()))?;
+        let mut sealed: Vec<u8> = Vec::with_capacity(1 + NONCE_SIZE + ct.len());
+        sealed.push(self.current);
+        sealed.extend(nonce);
+        sealed.extend(ct);
+        Ok(sealed)
+    }
+
+    fn open(&self, aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>, Event> {
+        let (key_id, rest) = sealed
+            .split_first()
+            .filter(|(_, rest)| NONCE_SIZE + TAG_SIZE <= rest.len())
+            .ok_or_else(|| Event::UnexpectedError("Truncated sealed payload".into()))?;
+        let cipher = self
+            .keys
+            .get(key_id)
+            .ok_or_else(|| Event::UnexpectedError(format!("Unknown key id: {}", key_id)))?;
+        let (nonce, ct) = rest.split_at(NONCE_SIZE);
+        let ad: Vec<u8> = [&[*key_id], aad].concat();
+        cipher
+            .decrypt(nonce.into(), Payload { msg: ct, aad: &ad })
+            .map_err(|_| Event::UnexpectedError("Authentication failed".into()))
+    }
+}
+
+#[cfg(test)]
+mod test_envelope {
+
+    mod open_named {
+        use crate::envelope::{self, Plain};
+        use crate::evt::Event;
+        use crate::item::Name;
+
+        #[test]
+        fn test_plain() {
+            let n = Name::from("42");
+            assert_eq!(
+                envelope::open_named(&Plain, &n, b"", &[1, 2, 3]),
+                Err(Event::UnsupportedFormat(n.clone()))
+            );
+            assert_eq!(
+                envelope::open_named(&Plain, &n, b"", &[]),
+                Err(Event::Broken(n))
+            );
+        }
+    }
+
+    #[cfg(feature = "chacha20poly1305")]
+    mod chacha20_poly1305_envelope {
+        use crate::envelope::{self, ChaCha20Poly1305, Envelope};
+        use crate::evt::Event;
+        use crate::item::Name;
+
+        fn key(start: u8) -> [u8; 32] {
+            let mut k: [u8; 32] = [0; 32];
+            k.iter_mut()
+                .enumerate()
+                .for_each(|(i, b)| *b = start + i as u8);
+            k
+        }
+
+        #[test]
+        fn test_rotation() {
+            let old = ChaCha20Poly1305::new(1, key(1));
+            let sealed: Vec<u8> = old.seal(b"42", b"pii").unwrap();
+            assert_eq!(sealed[0], 1);
+            assert_ne!(&sealed[13..16], b"pii");
+
+            let new = ChaCha20Poly1305::new(2, key(2)).with_key(1, key(1));
+            assert_eq!(new.open(b"42", &sealed).unwrap(), b"pii");
+            assert_eq!(new.seal(b"42", b"pii").unwrap()[0], 2);
+
+            let other = ChaCha20Poly1305::new(2, key(2));
+            let n = Name::from("42");
+            assert_eq!(
+                envelope::open_named(&other, &n, b"42", &sealed),
+                Err(Event::UnsupportedFormat(n))
+            );
+        }
+
+        #[test]
+        fn test_tampered() {
+            let e = ChaCha20Poly1305::new(1, key(1));
+            let n = Name::from("42");
+            let mut sealed: Vec<u8> = e.seal(b"42", b"pii").unwrap();
+            assert_eq!(
+                envelope::open_named(&e, &n, b"43", &sealed),
+                Err(Event::Broken(n.clone()))
+            );
+            let last: usize = sealed.len() - 1;
+            sealed[last] ^= 0x01;
+            assert_eq!(
+                envelope::open_named(&e, &n, b"42", &sealed),
+                Err(Event::Broken(n.clone()))
+            );
+            assert_eq!(
+                envelope::open_named(&e, &n, b"42", &sealed[..8]),
+                Err(Event::Broken(n))
+            );
+        }
+    }
+}
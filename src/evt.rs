@@ -1,7 +1,8 @@
 use crate::item::{Item, Name, NamedItem};
+use crate::stat::Stat;
 
 /// A list of request handler results.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum Event {
     Success,
@@ -24,6 +25,12 @@ pub enum Event {
     /// Unable to push an item(storage or buffer full).
     TooManyItemsAlready,
 
+    /// The pushed item overwrote the oldest item(the named item evicted).
+    ItemOverwrote(Name),
+
+    /// The pushed item dropped(buffer full).
+    ItemDropped,
+
     /// Unexpected error(unable to check item existence, ...).
     NoPerm(String),
 
@@ -39,9 +46,34 @@ pub enum Event {
     /// Item got, but its contents broken(power failure?).
     Broken(Name),
 
+    /// Item got, but written in a format this reader does not support(newer version, other
+    /// checksum, ...; not removed by `Request::Vacuum`).
+    UnsupportedFormat(Name),
+
     /// Broken items removed.
     BrokenItemsRemoved(u64),
 
+    /// Item got, but expired(use `Request::Expire` to remove).
+    Expired(Name),
+
+    /// Expired items removed.
+    ExpiredItemsRemoved(u64),
+
+    /// No item can be claimed(empty, or all items claimed by other consumers).
+    NoClaimableItem,
+
+    /// Results of a batch request(same order as the request).
+    Batch(Vec<Event>),
+
+    /// Occupancy and byte usage got.
+    Stat(Stat),
+
+    /// The item rejected(total bytes of the buffer would exceed the quota).
+    QuotaExceeded,
+
+    /// The item(or the item file to read) exceeds the max item size(contains the byte length).
+    ItemTooLarge(u64),
+
     /// Item got, but unreadable(bit rot?).
     InvalidItem(String),
 
@@ -58,6 +90,26 @@ impl TryFrom<Event> for Vec<Name> {
     }
 }
 
+impl TryFrom<Event> for Vec<Event> {
+    type Error = Event;
+    fn try_from(v: Event) -> Result<Self, Self::Error> {
+        match v {
+            Event::Batch(results) => Ok(results),
+            _ => Err(Event::BadRequest),
+        }
+    }
+}
+
+impl TryFrom<Event> for Stat {
+    type Error = Event;
+    fn try_from(v: Event) -> Result<Self, Self::Error> {
+        match v {
+            Event::Stat(s) => Ok(s),
+            _ => Err(Event::BadRequest),
+        }
+    }
+}
+
 impl TryFrom<Event> for Item {
     type Error = Event;
     fn try_from(v: Event) -> Result<Self, Self::Error> {
@@ -75,8 +127,10 @@ impl TryFrom<Event> for Name {
             Event::Empty(name) => Ok(name),
             Event::Used(name) => Ok(name),
             Event::ItemWrote(name) => Ok(name),
+            Event::ItemOverwrote(name) => Ok(name),
             Event::NoEntry(name) => Ok(name),
             Event::Broken(name) => Ok(name),
+            Event::UnsupportedFormat(name) => Ok(name),
             Event::ItemGot(named) => Ok(Name::from(named)),
             _ => Err(Event::BadRequest),
         }
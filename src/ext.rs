@@ -0,0 +1,209 @@
+use std::fmt;
+
+use crate::evt::Event;
+use crate::item::{Item, Name, NamedItem};
+use crate::order::Page;
+use crate::request::Request;
+use crate::stat::Stat;
+use crate::RingBuffer;
+
+/// An unexpected event of a request(see `RingBufferExt`).
+///
+/// Only the event is kept: the cause of an error event(e.g, an `io::Error`) is available as the
+/// message of the event(`Event::NoPerm`, `Event::UnexpectedError`, ...) and `Error::source`
+/// is `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RingError {
+    op: &'static str,
+    event: Event,
+}
+
+impl RingError {
+    /// Creates new error of the operation(`get`, `push`, ...).
+    pub fn new(op: &'static str, event: Event) -> Self {
+        Self { op, event }
+    }
+
+    /// The operation which got the event.
+    pub fn op(&self) -> &'static str {
+        self.op
+    }
+
+    /// The original event.
+    pub fn event(&self) -> &Event {
+        &self.event
+    }
+
+    pub fn into_event(self) -> Event {
+        self.event
+    }
+
+    /// Checks if the request may succeed later(`Event::Again`).
+    pub fn is_again(&self) -> bool {
+        self.event == Event::Again
+    }
+}
+
+impl fmt::Display for RingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.event {
+            Event::NoEntry(n) => write!(f, "{}: no entry: {}", self.op, n.as_str()),
+            Event::Broken(n) => write!(f, "{}: broken item: {}", self.op, n.as_str()),
+            Event::UnsupportedFormat(n) => {
+                write!(f, "{}: unsupported item format: {}", self.op, n.as_str())
+            }
+            Event::Expired(n) => write!(f, "{}: expired item: {}", self.op, n.as_str()),
+            Event::NoPerm(s) => write!(f, "{}: no permission: {}", self.op, s),
+            Event::InvalidItem(s) => write!(f, "{}: invalid item: {}", self.op, s),
+            Event::UnexpectedError(s) => write!(f, "{}: {}", self.op, s),
+            e => write!(f, "{}: unexpected event: {:?}", self.op, e),
+        }
+    }
+}
+
+impl std::error::Error for RingError {}
+
+impl From<RingError> for Event {
+    fn from(e: RingError) -> Self {
+        e.event
+    }
+}
+
+/// Typed requests over `RingBuffer`(unexpected events will be returned as `RingError`).
+pub trait RingBufferExt: RingBuffer {
+    /// Gets a named item.
+    fn get(&mut self, name: Name) -> Result<NamedItem, RingError> {
+        match self.handle(Request::Get(name)) {
+            Event::ItemGot(named) => Ok(named),
+            e => Err(RingError::new("get", e)),
+        }
+    }
+
+    /// Pushes an item(`Event::ItemDropped` will be an error).
+    fn push(&mut self, item: Item) -> Result<Name, RingError> {
+        match self.handle(Request::Push(item)) {
+            Event::ItemWrote(name) => Ok(name),
+            Event::ItemOverwrote(name) => Ok(name),
+            e => Err(RingError::new("push", e)),
+        }
+    }
+
+    /// Removes a named item.
+    fn del(&mut self, name: Name) -> Result<(), RingError> {
+        match self.handle(Request::Del(name)) {
+            Event::Success => Ok(()),
+            e => Err(RingError::new("del", e)),
+        }
+    }
+
+    /// Lists names.
+    fn list(&mut self) -> Result<Vec<Name>, RingError> {
+        match self.handle(Request::List) {
+            Event::NamesGot(names) => Ok(names),
+            e => Err(RingError::new("list", e)),
+        }
+    }
+
+    /// Lists a page of names in the order.
+    fn list_page(&mut self, page: Page) -> Result<Vec<Name>, RingError> {
+        match self.handle(Request::ListPage(page)) {
+            Event::NamesGot(names) => Ok(names),
+            e => Err(RingError::new("list_page", e)),
+        }
+    }
+
+    /// Removes broken items(returns the number of removed items).
+    fn vacuum(&mut self) -> Result<u64, RingError> {
+        match self.handle(Request::Vacuum) {
+            Event::BrokenItemsRemoved(cnt) => Ok(cnt),
+            e => Err(RingError::new("vacuum", e)),
+        }
+    }
+
+    /// Removes expired items(returns the number of removed items).
+    fn expire(&mut self) -> Result<u64, RingError> {
+        match self.handle(Request::Expire) {
+            Event::ExpiredItemsRemoved(cnt) => Ok(cnt),
+            e => Err(RingError::new("expire", e)),
+        }
+    }
+
+    /// Gets and removes the oldest item(sequential buffer only).
+    fn pop(&mut self) -> Result<NamedItem, RingError> {
+        match self.handle(Request::Pop) {
+            Event::ItemGot(named) => Ok(named),
+            e => Err(RingError::new("pop", e)),
+        }
+    }
+
+    /// Gets occupancy and byte usage.
+    fn stat(&mut self) -> Result<Stat, RingError> {
+        match self.handle(Request::Stat) {
+            Event::Stat(s) => Ok(s),
+            e => Err(RingError::new("stat", e)),
+        }
+    }
+}
+
+impl<R> RingBufferExt for R where R: RingBuffer + ?Sized {}
+
+/// Request handler closure(see `ring_buffer_new`) as a `RingBuffer`.
+pub struct Handler<F>(pub F);
+
+impl<F> RingBuffer for Handler<F>
+where
+    F: FnMut(Request) -> Event,
+{
+    fn handle(&mut self, req: Request) -> Event {
+        (self.0)(req)
+    }
+}
+
+#[cfg(test)]
+mod test_ext {
+
+    mod ring_buffer_ext {
+        use crate::evt::Event;
+        use crate::ext::{Handler, RingBufferExt, RingError};
+        use crate::item::{Item, Name, NamedItem};
+        use crate::request::Request;
+
+        fn handler(req: Request) -> Event {
+            match req {
+                Request::Get(n) if n.as_str() == "00" => {
+                    Event::ItemGot(NamedItem::new(Item::from(b"item".as_slice()), n))
+                }
+                Request::Get(n) => Event::NoEntry(n),
+                Request::Push(_) => Event::ItemDropped,
+                Request::List => Event::NamesGot(vec![Name::from("00")]),
+                Request::Vacuum => Event::BrokenItemsRemoved(3),
+                _ => Event::BadRequest,
+            }
+        }
+
+        #[test]
+        fn test_ok() {
+            let mut rb = Handler(handler);
+            let got: NamedItem = rb.get(Name::from("00")).unwrap();
+            assert_eq!(got.into_item(), Item::from(b"item".as_slice()));
+            assert_eq!(rb.list().unwrap(), vec![Name::from("00")]);
+            assert_eq!(rb.vacuum().unwrap(), 3);
+        }
+
+        #[test]
+        fn test_err() {
+            let mut rb = Handler(handler);
+            let e: RingError = rb.get(Name::from("01")).unwrap_err();
+            assert_eq!(e.event(), &Event::NoEntry(Name::from("01")));
+            assert_eq!(e.to_string(), "get: no entry: 01");
+
+            let e: RingError = rb.push(Item::from(b"item".as_slice())).unwrap_err();
+            assert_eq!(e.op(), "push");
+            assert_eq!(e.into_event(), Event::ItemDropped);
+
+            let e: Box<dyn std::error::Error> = Box::new(rb.del(Name::from("00")).unwrap_err());
+            assert_eq!(e.to_string(), "del: unexpected event: BadRequest");
+            assert!(e.source().is_none());
+        }
+    }
+}
@@ -1,8 +1,26 @@
-use std::path::{Path, PathBuf};
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
 
+use crate::evt::Event;
 use crate::item::Name;
 
+/// Checks if the name is a plain file name(`Event::BadRequest` if not).
+///
+/// Empty names, separators, `.`, `..` and absolute paths will be rejected.
+pub fn name_check(name: &Name) -> Result<(), Event> {
+    let s: &str = name.as_str();
+    let mut components = Path::new(s).components();
+    let plain: bool = !s.contains(['/', '\\'])
+        && matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        );
+    plain.then_some(()).ok_or(Event::BadRequest)
+}
+
 /// Creates fullpath builder which converts name into Pathbuf.
+///
+/// Names will not be checked(see `name_check` and `fullpath_builder_new_checked`).
 pub fn fullpath_builder_new<P>(dirname: P) -> impl Fn(Name) -> PathBuf
 where
     P: AsRef<Path>,
@@ -10,6 +28,144 @@ where
     move |name: Name| dirname.as_ref().join(name.as_str())
 }
 
+/// Creates fullpath builder which rejects names escaping the directory(`Event::BadRequest`).
+pub fn fullpath_builder_new_checked<P>(dirname: P) -> impl Fn(Name) -> Result<PathBuf, Event>
+where
+    P: AsRef<Path>,
+{
+    move |name: Name| name_check(&name).map(|_| dirname.as_ref().join(name.as_str()))
+}
+
+/// Directory layout of buffer files(flat or sharded).
+///
+/// A sharded layout uses leading characters of a name as nested directories.
+///
+/// * flat:                     abcd1234 -> dirname/abcd1234
+/// * sharded(depth=2,width=2): abcd1234 -> dirname/ab/cd/abcd1234
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    dirname: PathBuf,
+    depth: usize,
+    width: usize,
+}
+
+impl Layout {
+    /// Creates new flat layout(all buffer files in the directory).
+    pub fn flat<P>(dirname: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            dirname: dirname.as_ref().to_path_buf(),
+            depth: 0,
+            width: 0,
+        }
+    }
+
+    /// Creates new sharded layout(`Event::BadRequest` if width is zero).
+    ///
+    /// # Arguments
+    /// - dirname: Path to store shard directories.
+    /// - depth: Number of nested shard directories.
+    /// - width: Number of name characters for each shard directory.
+    pub fn sharded<P>(dirname: P, depth: usize, width: usize) -> Result<Self, Event>
+    where
+        P: AsRef<Path>,
+    {
+        (0 < width || 0 == depth)
+            .then(|| Self {
+                dirname: dirname.as_ref().to_path_buf(),
+                depth,
+                width,
+            })
+            .ok_or(Event::BadRequest)
+    }
+
+    pub fn dirname(&self) -> &Path {
+        &self.dirname
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Gets shard directory names(short names use fewer shards).
+    fn shards<'a>(&self, name: &'a Name) -> impl Iterator<Item = &'a str> {
+        let s: &str = name.as_str();
+        let width: usize = self.width;
+        (0..self.depth)
+            .map(move |i: usize| s.get(i * width..(i + 1) * width))
+            .take_while(|o: &Option<&str>| o.is_some())
+            .flatten()
+    }
+
+    /// Builds a path to the directory which contains the named item.
+    pub fn shard_dir(&self, name: &Name) -> PathBuf {
+        self.shards(name)
+            .fold(self.dirname.clone(), |p: PathBuf, shard: &str| {
+                p.join(shard)
+            })
+    }
+
+    /// Builds a path to the named item.
+    pub fn path(&self, name: &Name) -> PathBuf {
+        self.shard_dir(name).join(name.as_str())
+    }
+
+    /// Checks if the name and its shard directories are plain file names(see `name_check`).
+    pub fn check(&self, name: &Name) -> Result<(), Event> {
+        name_check(name)?;
+        self.shards(name)
+            .try_for_each(|shard: &str| name_check(&Name::from(shard)))
+    }
+
+    /// Builds a path to the named item(`Event::BadRequest` if the path escapes the directory).
+    pub fn path_checked(&self, name: &Name) -> Result<PathBuf, Event> {
+        self.check(name).map(|_| self.path(name))
+    }
+
+    /// Creates missing shard directories for the named item.
+    ///
+    /// The buffer directory itself will not be created.
+    pub fn create_shard_dir(&self, name: &Name) -> Result<(), Event> {
+        self.shards(name)
+            .try_fold(self.dirname.clone(), |p: PathBuf, shard: &str| {
+                let d: PathBuf = p.join(shard);
+                match std::fs::create_dir(&d) {
+                    Ok(_) => Ok(d),
+                    Err(e) => match e.kind() {
+                        ErrorKind::AlreadyExists => Ok(d),
+                        _ => Err(Event::UnexpectedError(format!(
+                            "Unable to create shard dir({:#?}): {}",
+                            d, e
+                        ))),
+                    },
+                }
+            })
+            .map(|_| ())
+    }
+
+    /// Creates path builder which uses this layout.
+    pub fn path_builder(&self) -> impl Fn(Name) -> PathBuf {
+        let layout: Layout = self.clone();
+        move |name: Name| layout.path(&name)
+    }
+}
+
+/// Creates sharded path builder which uses leading characters of names as directories.
+///
+/// * depth=2, width=2: abcd1234 -> dirname/ab/cd/abcd1234
+pub fn sharded_path_builder_new<P>(
+    dirname: P,
+    depth: usize,
+    width: usize,
+) -> Result<impl Fn(Name) -> PathBuf, Event>
+where
+    P: AsRef<Path>,
+{
+    Layout::sharded(dirname, depth, width).map(|l: Layout| l.path_builder())
+}
+
 #[cfg(test)]
 mod test_full {
 
@@ -27,4 +183,119 @@ mod test_full {
             assert_eq!(pb, Path::new("./test.d/42"));
         }
     }
+
+    mod name_check {
+        use crate::evt::Event;
+        use crate::full;
+        use crate::item::Name;
+
+        #[test]
+        fn test_plain() {
+            assert_eq!(full::name_check(&Name::from("42")), Ok(()));
+            assert_eq!(full::name_check(&Name::from(".42.lease")), Ok(()));
+        }
+
+        #[test]
+        fn test_invalid() {
+            let invalid = ["", ".", "..", "../42", "a/b", "a\\b", "/etc/passwd"];
+            for s in invalid {
+                assert_eq!(
+                    full::name_check(&Name::from(s)),
+                    Err(Event::BadRequest),
+                    "{}",
+                    s
+                );
+            }
+        }
+    }
+
+    mod fullpath_builder_new_checked {
+        use std::path::Path;
+
+        use crate::evt::Event;
+        use crate::full;
+        use crate::item::Name;
+
+        #[test]
+        fn test_checked() {
+            let f = full::fullpath_builder_new_checked("./test.d");
+            assert_eq!(f(Name::from("42")).unwrap(), Path::new("./test.d/42"));
+            assert_eq!(f(Name::from("../42")), Err(Event::BadRequest));
+        }
+    }
+
+    mod sharded_path_builder_new {
+        use std::path::{Path, PathBuf};
+
+        use crate::full;
+        use crate::item::Name;
+
+        #[test]
+        fn test_u32() {
+            let f = full::sharded_path_builder_new("./test.d", 2, 2).unwrap();
+            let pb: PathBuf = f(Name::from("abcd1234"));
+            assert_eq!(pb, Path::new("./test.d/ab/cd/abcd1234"));
+        }
+
+        #[test]
+        fn test_short() {
+            let f = full::sharded_path_builder_new("./test.d", 2, 2).unwrap();
+            let pb: PathBuf = f(Name::from("abc"));
+            assert_eq!(pb, Path::new("./test.d/ab/abc"));
+        }
+
+        #[test]
+        fn test_flat() {
+            let f = full::sharded_path_builder_new("./test.d", 0, 0).unwrap();
+            let pb: PathBuf = f(Name::from("abcd1234"));
+            assert_eq!(pb, Path::new("./test.d/abcd1234"));
+        }
+
+        #[test]
+        fn test_invalid() {
+            let r = full::sharded_path_builder_new("./test.d", 2, 0);
+            assert!(r.is_err());
+        }
+    }
+
+    mod layout {
+        use std::path::Path;
+
+        use crate::evt::Event;
+        use crate::full::Layout;
+        use crate::item::Name;
+
+        #[test]
+        fn test_path_checked() {
+            let l: Layout = Layout::sharded("./test.d", 1, 2).unwrap();
+            let p = l.path_checked(&Name::from("abcd")).unwrap();
+            assert_eq!(p, Path::new("./test.d/ab/abcd"));
+            assert_eq!(l.path_checked(&Name::from("..cd")), Err(Event::BadRequest));
+        }
+
+        #[test]
+        #[ignore]
+        fn test_create_shard_dir() {
+            let dirname = Path::new("./test.d/full/layout/create_shard_dir.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let l: Layout = Layout::sharded(dirname, 2, 2).unwrap();
+            let n: Name = Name::from("abcd1234");
+            l.create_shard_dir(&n).unwrap();
+            l.create_shard_dir(&n).unwrap();
+            assert!(dirname.join("ab/cd").is_dir());
+        }
+
+        #[test]
+        #[ignore]
+        fn test_dir_noent() {
+            let dirname = Path::new("./test.d/full/layout/dir_noent.d");
+            std::fs::remove_dir_all(dirname).ok();
+
+            let l: Layout = Layout::sharded(dirname, 2, 2).unwrap();
+            let r = l.create_shard_dir(&Name::from("abcd1234"));
+            assert!(r.is_err());
+        }
+    }
 }
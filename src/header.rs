@@ -0,0 +1,795 @@
+use std::time::{Duration, SystemTime};
+
+use crate::checksum::Checksum;
+use crate::codec::{self, Codec, Identity};
+use crate::envelope::{self, Envelope, Plain};
+use crate::evt::Event;
+use crate::item::{Item, Name};
+use crate::meta::Meta;
+
+/// Magic bytes at the start of an item file.
+pub const MAGIC: [u8; 4] = *b"FSRG";
+
+/// Current item format version.
+pub const VERSION: u8 = 1;
+
+/// Byte length of an encoded header(without extensions).
+pub const HEADER_SIZE: usize = 24;
+
+/// Max byte length of an encoded header(deadline + metadata length + codec id).
+pub const HEADER_SIZE_MAX: usize = HEADER_SIZE + 8 + 4 + 1;
+
+/// The header has a deadline extension(8 bytes, unix time, us, BE).
+pub const FLAG_DEADLINE: u8 = 0x01;
+
+/// The header has a metadata extension(4 bytes, metadata length, BE).
+///
+/// The metadata(see `meta::Meta`) follows the header.
+pub const FLAG_META: u8 = 0x02;
+
+/// The header has a codec extension(1 byte, codec id; the payload was encoded by the codec).
+pub const FLAG_CODEC: u8 = 0x04;
+
+/// The payload was sealed by an envelope(see `envelope::Envelope`; no extension).
+pub const FLAG_ENVELOPE: u8 = 0x08;
+
+/// Flags known by this version(unknown flags will be rejected on read).
+pub const FLAGS_KNOWN: u8 = FLAG_DEADLINE | FLAG_META | FLAG_CODEC | FLAG_ENVELOPE;
+
+/// Header of an item file.
+///
+/// An item file = header + metadata + payload + checksum(header + metadata + payload).
+///
+/// | offset | size | field                      |
+/// |--------|------|----------------------------|
+/// |      0 |    4 | magic(`FSRG`)              |
+/// |      4 |    1 | format version             |
+/// |      5 |    1 | flags                      |
+/// |      6 |    1 | checksum algorithm id      |
+/// |      7 |    1 | checksum size              |
+/// |      8 |    8 | payload length(big endian) |
+/// |     16 |    8 | created(unix time, us, BE) |
+/// |     24 |    8 | deadline(`FLAG_DEADLINE`)  |
+/// |      * |    4 | metadata length(`FLAG_META`) |
+/// |      * |    1 | codec id(`FLAG_CODEC`)     |
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    version: u8,
+    flags: u8,
+    checksum_id: u8,
+    checksum_size: u8,
+    length: u64,
+    created: u64,
+    deadline: Option<u64>,
+    meta_size: Option<u32>,
+    codec: Option<u8>,
+}
+
+/// A list of item formats to accept on read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compat {
+    /// Accepts items with a valid header only.
+    #[default]
+    Strict,
+
+    /// Also accepts headerless legacy items(payload + checksum(payload)).
+    Legacy,
+}
+
+fn time2micros(t: SystemTime) -> u64 {
+    t.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d: Duration| d.as_micros().try_into().unwrap_or(u64::MAX))
+        .unwrap_or(0)
+}
+
+fn micros2time(micros: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_micros(micros)
+}
+
+impl Header {
+    /// Creates new header for the payload(`Event::BadRequest` if the checksum is too long).
+    pub fn new<K>(length: u64, created: SystemTime, checker: &K) -> Result<Self, Event>
+    where
+        K: Checksum,
+    {
+        let checksum_size: u8 = checker.size().try_into().map_err(|_| Event::BadRequest)?;
+        Ok(Self {
+            version: VERSION,
+            flags: 0,
+            checksum_id: checker.id(),
+            checksum_size,
+            length,
+            created: time2micros(created),
+            deadline: None,
+            meta_size: None,
+            codec: None,
+        })
+    }
+
+    /// Creates new header which has the deadline.
+    pub fn with_deadline(self, deadline: SystemTime) -> Self {
+        Self {
+            flags: self.flags | FLAG_DEADLINE,
+            deadline: Some(time2micros(deadline)),
+            ..self
+        }
+    }
+
+    /// Creates new header which has the metadata length.
+    pub fn with_meta_size(self, meta_size: u32) -> Self {
+        Self {
+            flags: self.flags | FLAG_META,
+            meta_size: Some(meta_size),
+            ..self
+        }
+    }
+
+    /// Creates new header which has the codec id.
+    pub fn with_codec(self, codec: u8) -> Self {
+        Self {
+            flags: self.flags | FLAG_CODEC,
+            codec: Some(codec),
+            ..self
+        }
+    }
+
+    /// Creates new header which marks the payload as sealed.
+    pub fn with_envelope(self) -> Self {
+        Self {
+            flags: self.flags | FLAG_ENVELOPE,
+            ..self
+        }
+    }
+
+    /// Checks if the payload was sealed by an envelope.
+    pub fn is_sealed(&self) -> bool {
+        self.flags & FLAG_ENVELOPE != 0
+    }
+
+    /// Byte length of this header(with extensions).
+    pub fn size(&self) -> usize {
+        HEADER_SIZE
+            + self.deadline.map(|_| 8).unwrap_or(0)
+            + self.meta_size.map(|_| 4).unwrap_or(0)
+            + self.codec.map(|_| 1).unwrap_or(0)
+    }
+
+    /// Codec id of the payload(`codec::ID_IDENTITY` if no codec extension).
+    pub fn codec_id(&self) -> u8 {
+        self.codec.unwrap_or(codec::ID_IDENTITY)
+    }
+
+    /// Byte length of the metadata which follows this header.
+    pub fn meta_size(&self) -> usize {
+        self.meta_size.unwrap_or(0) as usize
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn flags(&self) -> u8 {
+        self.flags
+    }
+
+    pub fn checksum_id(&self) -> u8 {
+        self.checksum_id
+    }
+
+    pub fn checksum_size(&self) -> usize {
+        self.checksum_size.into()
+    }
+
+    /// Payload byte length.
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    pub fn created(&self) -> SystemTime {
+        micros2time(self.created)
+    }
+
+    /// The time when the item expires(per item).
+    pub fn deadline(&self) -> Option<SystemTime> {
+        self.deadline.map(micros2time)
+    }
+
+    /// Checks if the checksum of the item can be verified by the checker.
+    pub(crate) fn is_checked_by<K>(&self, checker: &K) -> bool
+    where
+        K: Checksum,
+    {
+        self.checksum_id == checker.id() && self.checksum_size() == checker.size()
+    }
+
+    /// Checks if the header was written by the checker and known flags.
+    pub(crate) fn validate<K>(&self, checker: &K) -> Result<(), Event>
+    where
+        K: Checksum,
+    {
+        let valid: bool = self.version == VERSION
+            && self.flags & !FLAGS_KNOWN == 0
+            && self.is_checked_by(checker);
+        valid
+            .then_some(())
+            .ok_or_else(|| Event::UnexpectedError(format!("Unsupported header: {:?}", self)))
+    }
+}
+
+impl From<&Header> for Vec<u8> {
+    fn from(h: &Header) -> Self {
+        let mut v: Vec<u8> = Vec::with_capacity(HEADER_SIZE);
+        v.extend(MAGIC);
+        v.extend([h.version, h.flags, h.checksum_id, h.checksum_size]);
+        v.extend(h.length.to_be_bytes());
+        v.extend(h.created.to_be_bytes());
+        v.extend(h.deadline.iter().flat_map(|d: &u64| d.to_be_bytes()));
+        v.extend(h.meta_size.iter().flat_map(|m: &u32| m.to_be_bytes()));
+        v.extend(h.codec.iter());
+        v
+    }
+}
+
+fn be64(b: &[u8]) -> u64 {
+    b.iter()
+        .fold(0, |u: u64, byte: &u8| (u << 8) | u64::from(*byte))
+}
+
+impl TryFrom<&[u8]> for Header {
+    type Error = Event;
+    fn try_from(b: &[u8]) -> Result<Self, Self::Error> {
+        let h: &[u8] = b
+            .get(..HEADER_SIZE)
+            .filter(|h: &&[u8]| h.starts_with(&MAGIC))
+            .ok_or_else(|| Event::UnexpectedError("Invalid header".into()))?;
+        let flags: u8 = h[5];
+        let ext = |offset: usize, size: usize| {
+            b.get(offset..offset + size)
+                .map(be64)
+                .ok_or_else(|| Event::UnexpectedError("Invalid header extension".into()))
+        };
+        let deadline: Option<u64> = match flags & FLAG_DEADLINE {
+            0 => None,
+            _ => Some(ext(HEADER_SIZE, 8)?),
+        };
+        let offset: usize = HEADER_SIZE + deadline.map(|_| 8).unwrap_or(0);
+        let meta_size: Option<u32> = match flags & FLAG_META {
+            0 => None,
+            _ => Some(ext(offset, 4)? as u32),
+        };
+        let offset: usize = offset + meta_size.map(|_| 4).unwrap_or(0);
+        let codec: Option<u8> = match flags & FLAG_CODEC {
+            0 => None,
+            _ => Some(ext(offset, 1)? as u8),
+        };
+        Ok(Self {
+            version: h[4],
+            flags,
+            checksum_id: h[6],
+            checksum_size: h[7],
+            length: be64(&h[8..16]),
+            created: be64(&h[16..24]),
+            deadline,
+            meta_size,
+            codec,
+        })
+    }
+}
+
+/// Checks if the bytes start with the magic.
+pub fn has_magic(b: &[u8]) -> bool {
+    b.starts_with(&MAGIC)
+}
+
+/// Checks if the checksum of an item(starts with the header) can be verified by the checker.
+///
+/// An item written with another checksum algorithm will be reported as `Event::UnsupportedFormat`.
+/// Items without magic or a complete header will be left to the checksum verification.
+pub fn check_checksum_id<K>(n: &Name, head: &[u8], checker: &K) -> Result<(), Event>
+where
+    K: Checksum,
+{
+    match Header::try_from(head) {
+        Ok(h) if !h.is_checked_by(checker) => Err(Event::UnsupportedFormat(n.clone())),
+        _ => Ok(()),
+    }
+}
+
+/// On-disk item format options.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ItemFormat<K, D, E> {
+    /// Computes checksum(header + metadata + payload).
+    pub checker: K,
+
+    /// Encodes payloads on write(payloads are decoded by the codec id in the header).
+    pub codec: D,
+
+    /// Seals encoded payloads on write and opens them on read.
+    pub envelope: E,
+
+    /// Item formats to accept on read.
+    pub compat: Compat,
+
+    /// Max byte length of an item file to read(`None`: unlimited; see `quota::Quota::max_file`).
+    pub max_file: Option<u64>,
+}
+
+impl<K> ItemFormat<K, Identity, Plain> {
+    /// Creates new format which uses the checker(no codec, no envelope, strict).
+    pub fn new(checker: K) -> Self {
+        Self {
+            checker,
+            codec: Identity,
+            envelope: Plain,
+            compat: Compat::Strict,
+            max_file: None,
+        }
+    }
+}
+
+impl<K, D, E> ItemFormat<K, D, E> {
+    /// Creates new format which uses the codec to encode payloads.
+    pub fn with_codec<F>(self, codec: F) -> ItemFormat<K, F, E> {
+        ItemFormat {
+            checker: self.checker,
+            codec,
+            envelope: self.envelope,
+            compat: self.compat,
+            max_file: self.max_file,
+        }
+    }
+
+    /// Creates new format which uses the envelope to seal payloads.
+    pub fn with_envelope<F>(self, envelope: F) -> ItemFormat<K, D, F> {
+        ItemFormat {
+            checker: self.checker,
+            codec: self.codec,
+            envelope,
+            compat: self.compat,
+            max_file: self.max_file,
+        }
+    }
+
+    /// Creates new format which accepts the formats on read.
+    pub fn with_compat(self, compat: Compat) -> Self {
+        Self { compat, ..self }
+    }
+
+    /// Creates new format which refuses item files over the max byte length on read.
+    pub fn with_max_file(self, max_file: u64) -> Self {
+        Self {
+            max_file: Some(max_file),
+            ..self
+        }
+    }
+}
+
+/// Gets the additional data authenticated by an envelope(see `envelope::Envelope`).
+///
+/// The header(without the payload length, which is known after sealing), the metadata and the
+/// name will be bound to the sealed payload.
+pub fn aad(h: &Header, meta: &[u8], n: &Name) -> Vec<u8> {
+    let mut ad: Vec<u8> = Vec::from(&Header { length: 0, ..*h });
+    ad.extend(meta);
+    ad.extend(n.as_str().as_bytes());
+    ad
+}
+
+/// Prepends a header and the metadata to the encoded payload.
+///
+/// The deadline will be stored in the header.
+/// The payload will be stored as is if the codec does not shrink it.
+/// The encoded payload will be sealed if the envelope has a key; the metadata will be stored in
+/// plain text(authenticated with the header and the name, see `aad`).
+/// The checksum(header + metadata + payload) will be appended by a writer.
+pub fn frame<K, D, E>(
+    n: &Name,
+    payload: Item,
+    created: SystemTime,
+    checker: &K,
+    codec: &D,
+    envelope: &E,
+) -> Result<Item, Event>
+where
+    K: Checksum,
+    D: Codec,
+    E: Envelope,
+{
+    let deadline: Option<SystemTime> = payload.deadline();
+    let meta: Vec<u8> = Vec::try_from(payload.meta())?;
+    let raw: Vec<u8> = payload.into();
+    let encoded: Option<Vec<u8>> = match codec.id() {
+        codec::ID_IDENTITY => None,
+        _ => Some(codec.encode(&raw)).filter(|e: &Vec<u8>| e.len() < raw.len()),
+    };
+    let (payload, codec_id): (Vec<u8>, Option<u8>) = match encoded {
+        None => (raw, None),
+        Some(e) => (e, Some(codec.id())),
+    };
+    let h: Header = Header::new(0, created, checker)?;
+    let h: Header = match deadline {
+        None => h,
+        Some(d) => h.with_deadline(d),
+    };
+    let h: Header = match meta.len() {
+        0 => h,
+        len => h.with_meta_size(len.try_into().map_err(|_| Event::BadRequest)?),
+    };
+    let h: Header = match codec_id {
+        None => h,
+        Some(id) => h.with_codec(id),
+    };
+    let (h, payload): (Header, Vec<u8>) = match envelope.key_id() {
+        None => (h, payload),
+        Some(_) => {
+            let h: Header = h.with_envelope();
+            (h, envelope.seal(&aad(&h, &meta, n), &payload)?)
+        }
+    };
+    let h: Header = Header {
+        length: payload.len() as u64,
+        ..h
+    };
+    let mut framed: Vec<u8> = Vec::from(&h);
+    framed.extend(meta);
+    framed.extend(payload);
+    Ok(Item::from(framed))
+}
+
+/// Splits a checksum verified item(header + metadata + payload) into the header and the payload.
+///
+/// A sealed payload will be opened by the envelope(see `envelope::open_named`).
+/// The payload will be decoded by the codec id(built-in codecs or the codec).
+/// Headers of another version, unknown flags, another checksum or an unknown codec will be
+/// reported as `Event::UnsupportedFormat`; truncated or inconsistent items as `Event::Broken`.
+pub fn unframe<K, D, E>(
+    n: Name,
+    framed: Item,
+    checker: &K,
+    codec: &D,
+    envelope: &E,
+) -> Result<(Header, Item), Event>
+where
+    K: Checksum,
+    D: Codec,
+    E: Envelope,
+{
+    let broken = |_| Event::Broken(n.clone());
+    let mut raw: Vec<u8> = framed.into();
+    let h: Header = Header::try_from(raw.as_slice()).map_err(broken)?;
+    let supported: bool = h.validate(checker).is_ok() && codec::is_known(h.codec_id(), codec);
+    supported
+        .then_some(())
+        .ok_or_else(|| Event::UnsupportedFormat(n.clone()))?;
+    let offset: usize = h.size() + h.meta_size();
+    let length: Option<u64> = raw.len().checked_sub(offset).map(|l: usize| l as u64);
+    (length == Some(h.length))
+        .then_some(())
+        .ok_or_else(|| Event::Broken(n.clone()))?;
+    let payload: Vec<u8> = raw.split_off(offset);
+    let payload: Vec<u8> = match h.is_sealed() {
+        false => payload,
+        true => envelope::open_named(envelope, &n, &aad(&h, &raw[h.size()..], &n), &payload)?,
+    };
+    let payload: Vec<u8> = match h.codec_id() {
+        codec::ID_IDENTITY => payload,
+        id => codec::decode_by_id(id, &payload, codec).map_err(broken)?,
+    };
+    let meta: Meta = Meta::try_from(&raw[h.size()..]).map_err(broken)?;
+    let payload: Item = Item::from(payload).with_meta(meta);
+    let payload: Item = match h.deadline() {
+        None => payload,
+        Some(d) => payload.with_deadline(d),
+    };
+    Ok((h, payload))
+}
+
+/// Gets the header and the payload of a checksum verified item(see `unframe`).
+///
+/// A `Compat::Legacy` item without magic will be returned as is(without a header).
+pub fn unframe_compat<K, D, E>(
+    n: Name,
+    framed: Item,
+    format: &ItemFormat<K, D, E>,
+) -> Result<(Option<Header>, Item), Event>
+where
+    K: Checksum,
+    D: Codec,
+    E: Envelope,
+{
+    let legacy: bool = format.compat == Compat::Legacy && !has_magic(framed.as_slice());
+    match legacy {
+        true => Ok((None, framed)),
+        false => unframe(n, framed, &format.checker, &format.codec, &format.envelope)
+            .map(|(h, payload)| (Some(h), payload)),
+    }
+}
+
+#[cfg(test)]
+mod test_header {
+
+    mod header {
+        use std::time::{Duration, SystemTime};
+
+        use crate::checksum::{Crc32c, Crc64};
+        use crate::codec::{Identity, Lz77};
+        #[cfg(feature = "chacha20poly1305")]
+        use crate::envelope::ChaCha20Poly1305;
+        use crate::envelope::Plain;
+        use crate::evt::Event;
+        #[cfg(feature = "chacha20poly1305")]
+        use crate::header::FLAG_ENVELOPE;
+        use crate::header::{self, Header, FLAG_CODEC, FLAG_DEADLINE, FLAG_META, HEADER_SIZE};
+        use crate::item::{Item, Name};
+        use crate::meta::Meta;
+
+        #[test]
+        fn test_bytes() {
+            let created = SystemTime::UNIX_EPOCH + Duration::from_micros(0x42);
+            let h: Header = Header::new(3, created, &Crc32c).unwrap();
+            let b: Vec<u8> = (&h).into();
+            assert_eq!(b.len(), HEADER_SIZE);
+            assert_eq!(&b[..8], b"FSRG\x01\x00\x01\x04");
+            let parsed: Header = Header::try_from(b.as_slice()).unwrap();
+            assert_eq!(parsed, h);
+            assert_eq!(parsed.created(), created);
+            assert_eq!(parsed.length(), 3);
+        }
+
+        #[test]
+        fn test_frame() {
+            let now = SystemTime::now();
+            let framed: Item = header::frame(
+                &Name::from("42"),
+                Item::from(b"abc".as_slice()),
+                now,
+                &Crc32c,
+                &Identity,
+                &Plain,
+            )
+            .unwrap();
+            let (h, payload) =
+                header::unframe(Name::from("42"), framed, &Crc32c, &Identity, &Plain).unwrap();
+            assert_eq!(payload, Item::from(b"abc".as_slice()));
+            assert_eq!(h.checksum_size(), 4);
+        }
+
+        #[test]
+        fn test_deadline() {
+            let now = SystemTime::UNIX_EPOCH + Duration::from_secs(42);
+            let deadline = now + Duration::from_secs(1);
+            let item = Item::from(b"abc".as_slice()).with_deadline(deadline);
+            let framed: Item = header::frame(
+                &Name::from("42"),
+                item.clone(),
+                now,
+                &Crc32c,
+                &Identity,
+                &Plain,
+            )
+            .unwrap();
+            assert_eq!(framed.as_slice().len(), HEADER_SIZE + 8 + 3);
+            let (h, payload) =
+                header::unframe(Name::from("42"), framed, &Crc32c, &Identity, &Plain).unwrap();
+            assert_eq!(h.deadline(), Some(deadline));
+            assert_eq!(payload, item);
+        }
+
+        #[test]
+        fn test_meta() {
+            let now = SystemTime::UNIX_EPOCH + Duration::from_secs(42);
+            let meta = Meta::new().with("content-type", "text/plain");
+            let item = Item::from(b"abc".as_slice())
+                .with_meta(meta.clone())
+                .with_deadline(now);
+            let framed: Item = header::frame(
+                &Name::from("42"),
+                item.clone(),
+                now,
+                &Crc32c,
+                &Identity,
+                &Plain,
+            )
+            .unwrap();
+            let (h, payload) =
+                header::unframe(Name::from("42"), framed, &Crc32c, &Identity, &Plain).unwrap();
+            assert_eq!(h.flags(), FLAG_DEADLINE | FLAG_META);
+            assert_eq!(payload.meta(), &meta);
+            assert_eq!(payload, item);
+        }
+
+        #[test]
+        fn test_codec() {
+            let now = SystemTime::now();
+            let dat: Vec<u8> = b"abcdefgh".repeat(64);
+            let framed: Item = header::frame(
+                &Name::from("42"),
+                Item::from(dat.clone()),
+                now,
+                &Crc32c,
+                &Lz77,
+                &Plain,
+            )
+            .unwrap();
+            assert!(framed.as_slice().len() < dat.len());
+            let (h, payload) =
+                header::unframe(Name::from("42"), framed, &Crc32c, &Identity, &Plain).unwrap();
+            assert_eq!(h.flags(), FLAG_CODEC);
+            assert_eq!(payload, Item::from(dat));
+        }
+
+        #[test]
+        fn test_codec_skip() {
+            let now = SystemTime::now();
+            let framed: Item = header::frame(
+                &Name::from("42"),
+                Item::from(b"a".as_slice()),
+                now,
+                &Crc32c,
+                &Lz77,
+                &Plain,
+            )
+            .unwrap();
+            let (h, payload) =
+                header::unframe(Name::from("42"), framed, &Crc32c, &Lz77, &Plain).unwrap();
+            assert_eq!(h.flags(), 0);
+            assert_eq!(payload, Item::from(b"a".as_slice()));
+        }
+
+        #[cfg(feature = "chacha20poly1305")]
+        #[test]
+        fn test_envelope() {
+            let now = SystemTime::now();
+            let dat: Vec<u8> = b"abcdefgh".repeat(64);
+            let n = Name::from("42");
+            let e = ChaCha20Poly1305::new(1, [0x42; 32]);
+            let item = Item::from(dat.clone())
+                .with_meta(Meta::new().with("k", "v"))
+                .with_deadline(now + Duration::from_secs(60));
+            let framed: Item = header::frame(&n, item.clone(), now, &Crc32c, &Lz77, &e).unwrap();
+            let raw: Vec<u8> = framed.clone().into();
+            assert!(!raw.windows(8).any(|w: &[u8]| w == b"abcdefgh"));
+            let (h, payload) =
+                header::unframe(n.clone(), framed.clone(), &Crc32c, &Identity, &e).unwrap();
+            assert_eq!(
+                h.flags(),
+                FLAG_DEADLINE | FLAG_META | FLAG_CODEC | FLAG_ENVELOPE
+            );
+            assert_eq!(payload.as_slice(), dat.as_slice());
+            assert_eq!(payload.meta(), item.meta());
+
+            // no key to open
+            let unframe = |framed: Vec<u8>, e: &ChaCha20Poly1305| {
+                header::unframe(n.clone(), Item::from(framed), &Crc32c, &Identity, e)
+            };
+            let plain = header::unframe(n.clone(), framed.clone(), &Crc32c, &Identity, &Plain);
+            assert_eq!(plain, Err(Event::UnsupportedFormat(n.clone())));
+            let unknown = ChaCha20Poly1305::new(2, [0x42; 32]);
+            assert_eq!(
+                unframe(raw.clone(), &unknown),
+                Err(Event::UnsupportedFormat(n.clone()))
+            );
+
+            // authentication failures
+            let other = ChaCha20Poly1305::new(1, [0x43; 32]);
+            assert_eq!(unframe(raw.clone(), &other), Err(Event::Broken(n.clone())));
+            let moved = header::unframe(Name::from("43"), framed, &Crc32c, &Identity, &e);
+            assert_eq!(moved, Err(Event::Broken(Name::from("43"))));
+            let mut deadline: Vec<u8> = raw.clone();
+            deadline[HEADER_SIZE + 7] ^= 0x01;
+            assert_eq!(unframe(deadline, &e), Err(Event::Broken(n.clone())));
+            let mut meta: Vec<u8> = raw.clone();
+            meta[h.size() + h.meta_size() - 1] ^= 0x01;
+            assert_eq!(unframe(meta, &e), Err(Event::Broken(n.clone())));
+        }
+
+        #[test]
+        fn test_algorithm_mismatch() {
+            let now = SystemTime::now();
+            let framed: Item = header::frame(
+                &Name::from("42"),
+                Item::from(vec![]),
+                now,
+                &Crc32c,
+                &Identity,
+                &Plain,
+            )
+            .unwrap();
+            assert_eq!(
+                header::unframe(Name::from("42"), framed, &Crc64, &Identity, &Plain),
+                Err(Event::UnsupportedFormat(Name::from("42")))
+            );
+        }
+
+        #[test]
+        fn test_unknown_codec() {
+            let now = SystemTime::now();
+            let dat: Vec<u8> = b"abcdefgh".repeat(64);
+            let mut framed: Vec<u8> = header::frame(
+                &Name::from("42"),
+                Item::from(dat),
+                now,
+                &Crc32c,
+                &Lz77,
+                &Plain,
+            )
+            .unwrap()
+            .into();
+            assert_eq!(framed[HEADER_SIZE], crate::codec::ID_LZ77);
+            framed[HEADER_SIZE] = 0x42;
+            assert_eq!(
+                header::unframe(Name::from("42"), Item::from(framed), &Crc32c, &Lz77, &Plain),
+                Err(Event::UnsupportedFormat(Name::from("42")))
+            );
+        }
+
+        #[test]
+        fn test_unsupported() {
+            let now = SystemTime::now();
+            let framed: Vec<u8> = header::frame(
+                &Name::from("42"),
+                Item::from(vec![]),
+                now,
+                &Crc32c,
+                &Identity,
+                &Plain,
+            )
+            .unwrap()
+            .into();
+            // a newer version, an unknown flag
+            for (offset, byte) in [(4, 2), (5, 0x80)] {
+                let mut newer: Vec<u8> = framed.clone();
+                newer[offset] = byte;
+                assert_eq!(
+                    header::unframe(
+                        Name::from("42"),
+                        Item::from(newer),
+                        &Crc32c,
+                        &Identity,
+                        &Plain
+                    ),
+                    Err(Event::UnsupportedFormat(Name::from("42")))
+                );
+            }
+        }
+
+        #[test]
+        fn test_truncated() {
+            let now = SystemTime::now();
+            let framed: Vec<u8> = header::frame(
+                &Name::from("42"),
+                Item::from(b"abc".as_slice()),
+                now,
+                &Crc32c,
+                &Identity,
+                &Plain,
+            )
+            .unwrap()
+            .into();
+            let cut: Item = Item::from(&framed[..framed.len() - 1]);
+            assert_eq!(
+                header::unframe(Name::from("42"), cut, &Crc32c, &Identity, &Plain),
+                Err(Event::Broken(Name::from("42")))
+            );
+        }
+    }
+
+    mod unframe_compat {
+        use crate::checksum::Crc32c;
+        use crate::evt::Event;
+        use crate::header::{self, Compat, ItemFormat};
+        use crate::item::{Item, Name};
+
+        #[test]
+        fn test_legacy() {
+            let legacy = Item::from(b"abc".as_slice());
+            let n = Name::from("00");
+            let strict =
+                header::unframe_compat(n.clone(), legacy.clone(), &ItemFormat::new(Crc32c));
+            assert_eq!(strict, Err(Event::Broken(n.clone())));
+            let format = ItemFormat::new(Crc32c).with_compat(Compat::Legacy);
+            let compat = header::unframe_compat(n, legacy.clone(), &format);
+            assert_eq!(compat, Ok((None, legacy)));
+        }
+    }
+}
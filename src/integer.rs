@@ -1 +1,3 @@
 pub mod u;
+pub mod u16;
+pub mod u32;
@@ -0,0 +1,119 @@
+use crate::compose::compose_err;
+
+use crate::evt::Event;
+use crate::item::Name;
+
+/// Try converts `Name`(hex string expected) to `u16`.
+///
+/// * 0000 => 0
+/// * 0001 => 1
+/// * ...
+/// * ffff => 65535
+/// * zz => `Event::BadRequest`
+pub fn n2u16_hex(n: &Name) -> Result<u16, Event> {
+    let s: &str = n.as_str();
+    u16::from_str_radix(s, 16).map_err(|_| Event::BadRequest)
+}
+
+/// Converts `u16` to `Name`(zero padded hex string).
+///
+/// * 0 -> 0000
+/// * 1 -> 0001
+/// * ...
+/// * 65535 -> ffff
+pub fn u2n16_hex(u: u16) -> Name {
+    Name::from(format!("{:04x}", u))
+}
+
+/// Creates new name checker which rejects non-u16 names.
+///
+/// * 0000 -> 0000
+/// * 0001 -> 0001
+/// * ...
+/// * ffff -> ffff
+/// * zz -> `Event::BadRequest`
+pub fn name_checker_u16_new() -> impl Fn(Name) -> Result<Name, Event> {
+    move |name: Name| n2u16_hex(&name).map(|_: u16| name)
+}
+
+/// Creates new name generator which uses converters to convert name to u16 or vice versa.
+pub fn next_gen_by_prev_u16_new<F, G>(n2u: F, u2n: G) -> impl Fn(Name) -> Result<Name, Event>
+where
+    F: Fn(Name) -> Result<u16, Event>,
+    G: Fn(u16) -> Result<Name, Event>,
+{
+    let next = move |prev: Name| {
+        let pu: u16 = n2u(prev)?;
+        Ok(pu.checked_add(1).unwrap_or(0))
+    };
+    compose_err(next, u2n)
+}
+
+/// Creates new name generator which uses hex converters(`n2u16_hex` and `u2n16_hex`).
+///
+/// * 0000 => 0001
+/// * ffff => 0000
+pub fn next_gen_by_prev_u16_hex() -> impl Fn(Name) -> Result<Name, Event> {
+    next_gen_by_prev_u16_new(|n: Name| n2u16_hex(&n), |u: u16| Ok(u2n16_hex(u)))
+}
+
+#[cfg(test)]
+mod test_u16 {
+
+    mod name_checker_u16_new {
+        use crate::integer::u16;
+
+        use crate::evt::Event;
+        use crate::item::Name;
+
+        #[test]
+        fn test_zero() {
+            let chk = u16::name_checker_u16_new();
+            let nam = chk(Name::from("0000")).unwrap();
+            assert_eq!(nam, Name::from("0000"));
+        }
+
+        #[test]
+        fn test_invalid() {
+            let chk = u16::name_checker_u16_new();
+            let r = chk(Name::from("invalid"));
+            assert_eq!(r, Err(Event::BadRequest));
+        }
+    }
+
+    mod next_gen_by_prev_u16_hex {
+        use crate::integer::u16;
+        use crate::item::Name;
+
+        #[test]
+        fn test_wrap() {
+            let f = u16::next_gen_by_prev_u16_hex();
+            let n: Name = f(Name::from("ffff")).unwrap();
+            assert_eq!(n, Name::from("0000"));
+        }
+
+        #[test]
+        fn test_next() {
+            let f = u16::next_gen_by_prev_u16_hex();
+            let n: Name = f(Name::from("0041")).unwrap();
+            assert_eq!(n, Name::from("0042"));
+        }
+    }
+
+    mod u2n16_hex {
+        use crate::integer::u16;
+        use crate::item::Name;
+
+        #[test]
+        fn test_zero() {
+            let n: Name = u16::u2n16_hex(0);
+            assert_eq!(n, Name::from("0000"));
+        }
+
+        #[test]
+        fn test_max() {
+            let n: Name = u16::u2n16_hex(0xffff);
+            assert_eq!(n, Name::from("ffff"));
+        }
+    }
+}
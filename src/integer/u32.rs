@@ -0,0 +1,119 @@
+use crate::compose::compose_err;
+
+use crate::evt::Event;
+use crate::item::Name;
+
+/// Try converts `Name`(hex string expected) to `u32`.
+///
+/// * 00000000 => 0
+/// * 00000001 => 1
+/// * ...
+/// * ffffffff => 4294967295
+/// * zz => `Event::BadRequest`
+pub fn n2u32_hex(n: &Name) -> Result<u32, Event> {
+    let s: &str = n.as_str();
+    u32::from_str_radix(s, 16).map_err(|_| Event::BadRequest)
+}
+
+/// Converts `u32` to `Name`(zero padded hex string).
+///
+/// * 0 -> 00000000
+/// * 1 -> 00000001
+/// * ...
+/// * 4294967295 -> ffffffff
+pub fn u2n32_hex(u: u32) -> Name {
+    Name::from(format!("{:08x}", u))
+}
+
+/// Creates new name checker which rejects non-u32 names.
+///
+/// * 00000000 -> 00000000
+/// * 00000001 -> 00000001
+/// * ...
+/// * ffffffff -> ffffffff
+/// * zz -> `Event::BadRequest`
+pub fn name_checker_u32_new() -> impl Fn(Name) -> Result<Name, Event> {
+    move |name: Name| n2u32_hex(&name).map(|_: u32| name)
+}
+
+/// Creates new name generator which uses converters to convert name to u32 or vice versa.
+pub fn next_gen_by_prev_u32_new<F, G>(n2u: F, u2n: G) -> impl Fn(Name) -> Result<Name, Event>
+where
+    F: Fn(Name) -> Result<u32, Event>,
+    G: Fn(u32) -> Result<Name, Event>,
+{
+    let next = move |prev: Name| {
+        let pu: u32 = n2u(prev)?;
+        Ok(pu.checked_add(1).unwrap_or(0))
+    };
+    compose_err(next, u2n)
+}
+
+/// Creates new name generator which uses hex converters(`n2u32_hex` and `u2n32_hex`).
+///
+/// * 00000000 => 00000001
+/// * ffffffff => 00000000
+pub fn next_gen_by_prev_u32_hex() -> impl Fn(Name) -> Result<Name, Event> {
+    next_gen_by_prev_u32_new(|n: Name| n2u32_hex(&n), |u: u32| Ok(u2n32_hex(u)))
+}
+
+#[cfg(test)]
+mod test_u32 {
+
+    mod name_checker_u32_new {
+        use crate::integer::u32;
+
+        use crate::evt::Event;
+        use crate::item::Name;
+
+        #[test]
+        fn test_zero() {
+            let chk = u32::name_checker_u32_new();
+            let nam = chk(Name::from("00000000")).unwrap();
+            assert_eq!(nam, Name::from("00000000"));
+        }
+
+        #[test]
+        fn test_invalid() {
+            let chk = u32::name_checker_u32_new();
+            let r = chk(Name::from("invalid"));
+            assert_eq!(r, Err(Event::BadRequest));
+        }
+    }
+
+    mod next_gen_by_prev_u32_hex {
+        use crate::integer::u32;
+        use crate::item::Name;
+
+        #[test]
+        fn test_wrap() {
+            let f = u32::next_gen_by_prev_u32_hex();
+            let n: Name = f(Name::from("ffffffff")).unwrap();
+            assert_eq!(n, Name::from("00000000"));
+        }
+
+        #[test]
+        fn test_next() {
+            let f = u32::next_gen_by_prev_u32_hex();
+            let n: Name = f(Name::from("00000041")).unwrap();
+            assert_eq!(n, Name::from("00000042"));
+        }
+    }
+
+    mod u2n32_hex {
+        use crate::integer::u32;
+        use crate::item::Name;
+
+        #[test]
+        fn test_zero() {
+            let n: Name = u32::u2n32_hex(0);
+            assert_eq!(n, Name::from("00000000"));
+        }
+
+        #[test]
+        fn test_max() {
+            let n: Name = u32::u2n32_hex(0xffff_ffff);
+            assert_eq!(n, Name::from("ffffffff"));
+        }
+    }
+}
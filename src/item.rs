@@ -1,9 +1,46 @@
+use std::time::SystemTime;
+
 use crate::evt::Event;
+use crate::meta::Meta;
 
-/// Contains raw bytes.
+/// Contains raw bytes(and an optional deadline and metadata).
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Item {
     raw: Vec<u8>,
+    deadline: Option<SystemTime>,
+    meta: Meta,
+}
+
+impl Item {
+    pub fn as_slice(&self) -> &[u8] {
+        self.raw.as_slice()
+    }
+
+    /// Sets the time when the item expires.
+    pub fn with_deadline(self, deadline: SystemTime) -> Self {
+        Self {
+            deadline: Some(deadline),
+            ..self
+        }
+    }
+
+    pub fn deadline(&self) -> Option<SystemTime> {
+        self.deadline
+    }
+
+    /// Sets the metadata(stored with the payload).
+    pub fn with_meta(self, meta: Meta) -> Self {
+        Self { meta, ..self }
+    }
+
+    pub fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    /// Replaces the raw bytes(the deadline and the metadata will be kept).
+    pub fn with_raw(self, raw: Vec<u8>) -> Self {
+        Self { raw, ..self }
+    }
 }
 
 impl From<Item> for Vec<u8> {
@@ -14,7 +51,11 @@ impl From<Item> for Vec<u8> {
 
 impl From<Vec<u8>> for Item {
     fn from(raw: Vec<u8>) -> Self {
-        Self { raw }
+        Self {
+            raw,
+            deadline: None,
+            meta: Meta::default(),
+        }
     }
 }
 
@@ -25,7 +66,7 @@ impl From<&[u8]> for Item {
 }
 
 /// Contains name string.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Name {
     name: String,
 }
@@ -64,6 +105,35 @@ impl TryFrom<&Name> for u8 {
     }
 }
 
+impl From<u16> for Name {
+    fn from(raw: u16) -> Self {
+        let name: String = format!("{:04x}", raw);
+        Self { name }
+    }
+}
+impl TryFrom<&Name> for u16 {
+    type Error = Event;
+    fn try_from(n: &Name) -> Result<Self, Self::Error> {
+        let s: &str = n.name.as_str();
+        u16::from_str_radix(s, 16)
+            .map_err(|e| Event::UnexpectedError(format!("Invalid name: {}", e)))
+    }
+}
+impl From<u32> for Name {
+    fn from(raw: u32) -> Self {
+        let name: String = format!("{:08x}", raw);
+        Self { name }
+    }
+}
+impl TryFrom<&Name> for u32 {
+    type Error = Event;
+    fn try_from(n: &Name) -> Result<Self, Self::Error> {
+        let s: &str = n.name.as_str();
+        u32::from_str_radix(s, 16)
+            .map_err(|e| Event::UnexpectedError(format!("Invalid name: {}", e)))
+    }
+}
+
 /// A named `Item` with `Name`.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NamedItem {
@@ -87,6 +157,11 @@ impl NamedItem {
     pub fn into_item(self) -> Item {
         self.item
     }
+
+    /// Gets the metadata of the item.
+    pub fn meta(&self) -> &Meta {
+        self.item.meta()
+    }
 }
 
 impl From<NamedItem> for Item {
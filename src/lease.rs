@@ -0,0 +1,476 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs::DirEntry;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, SystemTime};
+
+use crate::claim::Claim;
+use crate::clock::Clock;
+use crate::evt::Event;
+use crate::item::Name;
+use crate::order;
+use crate::write;
+
+const LEASE_PREFIX: &str = ".";
+const LEASE_SUFFIX: &str = ".lease";
+
+/// Byte length of a lease file.
+pub const LEASE_SIZE: usize = 28;
+
+/// Creates a lease path for the item path(same directory, hidden, survives restarts).
+///
+/// * ./ring.d/42 -> ./ring.d/.42.lease
+pub fn lease_path_new<P>(p: P) -> Result<PathBuf, Event>
+where
+    P: AsRef<Path>,
+{
+    let p: &Path = p.as_ref();
+    p.file_name()
+        .and_then(OsStr::to_str)
+        .map(|name: &str| p.with_file_name(format!("{}{}{}", LEASE_PREFIX, name, LEASE_SUFFIX)))
+        .ok_or_else(|| Event::UnexpectedError(format!("Invalid item path: {:#?}", p)))
+}
+
+/// Gets the item file name of the lease file name created by `lease_path_new`.
+///
+/// * .42.lease -> 42
+/// * 42        -> None
+pub fn lease2item(s: &str) -> Option<&str> {
+    s.strip_prefix(LEASE_PREFIX)
+        .and_then(|s: &str| s.strip_suffix(LEASE_SUFFIX))
+        .filter(|item: &&str| !item.is_empty())
+}
+
+static LEASE_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+static OWNER_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+/// Creates new owner id unique in this process(process id + per process counter).
+pub fn owner_new() -> u64 {
+    let cnt: u32 = OWNER_COUNTER.fetch_add(1, Ordering::Relaxed);
+    (u64::from(std::process::id()) << 32) | u64::from(cnt)
+}
+
+fn time2micros(t: SystemTime) -> u64 {
+    t.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d: Duration| d.as_micros().try_into().unwrap_or(u64::MAX))
+        .unwrap_or(0)
+}
+
+fn be64(b: &[u8]) -> u64 {
+    b.iter()
+        .fold(0, |u: u64, byte: &u8| (u << 8) | u64::from(*byte))
+}
+
+/// A consumer lease of an item.
+///
+/// The lease is tied to the item(name + creation time); an item written after the lease was
+/// taken(e.g, overwritten) is not leased.
+///
+/// | size | field                                   |
+/// |------|-----------------------------------------|
+/// |    8 | deadline(unix time, us, BE)             |
+/// |    8 | creation time of the item(unix time, us, BE) |
+/// |    8 | owner id(BE)                            |
+/// |    4 | per process lease counter(BE)           |
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lease {
+    deadline: u64,
+    created: u64,
+    owner: u64,
+    seq: u32,
+}
+
+impl Lease {
+    /// Creates new lease which expires at the deadline(owned by this process).
+    pub fn new(deadline: SystemTime) -> Self {
+        Self {
+            deadline: time2micros(deadline),
+            created: 0,
+            owner: u64::from(std::process::id()) << 32,
+            seq: LEASE_COUNTER.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    /// Creates new lease of the item created at the time.
+    pub fn with_created(self, created: SystemTime) -> Self {
+        Self {
+            created: time2micros(created),
+            ..self
+        }
+    }
+
+    /// Creates new lease owned by the owner(see `Leases::with_owner`).
+    pub fn with_owner(self, owner: u64) -> Self {
+        Self { owner, ..self }
+    }
+
+    pub fn deadline(&self) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_micros(self.deadline)
+    }
+
+    pub fn owner(&self) -> u64 {
+        self.owner
+    }
+
+    /// Checks if the lease expired(the item is visible to other consumers again).
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.deadline() <= now
+    }
+
+    /// Checks if the lease was taken for the item created at the time.
+    pub fn is_for(&self, created: SystemTime) -> bool {
+        self.created == time2micros(created)
+    }
+}
+
+impl From<&Lease> for Vec<u8> {
+    fn from(l: &Lease) -> Self {
+        let mut v: Vec<u8> = Vec::with_capacity(LEASE_SIZE);
+        v.extend(l.deadline.to_be_bytes());
+        v.extend(l.created.to_be_bytes());
+        v.extend(l.owner.to_be_bytes());
+        v.extend(l.seq.to_be_bytes());
+        v
+    }
+}
+
+impl TryFrom<&[u8]> for Lease {
+    type Error = Event;
+    fn try_from(b: &[u8]) -> Result<Self, Self::Error> {
+        let b: [u8; LEASE_SIZE] = b
+            .try_into()
+            .map_err(|_| Event::UnexpectedError("Invalid lease".into()))?;
+        Ok(Self {
+            deadline: be64(&b[..8]),
+            created: be64(&b[8..16]),
+            owner: be64(&b[16..24]),
+            seq: u32::from_be_bytes([b[24], b[25], b[26], b[27]]),
+        })
+    }
+}
+
+/// Reads the lease(an invalid lease file will be ignored and replaced by a new lease).
+fn read_lease(p: &Path) -> Result<Option<Lease>, Event> {
+    match std::fs::read(p) {
+        Ok(b) => Ok(Lease::try_from(b.as_slice()).ok()),
+        Err(e) => match e.kind() {
+            ErrorKind::NotFound => Ok(None),
+            _ => Err(Event::UnexpectedError(format!(
+                "Unable to read lease: {}",
+                e
+            ))),
+        },
+    }
+}
+
+fn remove_lease(p: &Path) -> Result<(), Event> {
+    match std::fs::remove_file(p) {
+        Ok(_) => Ok(()),
+        Err(e) => match e.kind() {
+            ErrorKind::NotFound => Ok(()),
+            _ => Err(Event::UnexpectedError(format!(
+                "Unable to remove lease: {}",
+                e
+            ))),
+        },
+    }
+}
+
+/// Checks if the lease file is left behind(the item was removed or replaced).
+fn is_stale(lease: &Path, item: PathBuf) -> Result<bool, Event> {
+    let created: Option<SystemTime> = order::path2created(item)
+        .map_err(|e| Event::UnexpectedError(format!("Unable to get creation time: {}", e)))?;
+    let leased: Option<Lease> = read_lease(lease)?;
+    Ok(match (created, leased) {
+        (Some(t), Some(l)) => !l.is_for(t),
+        _ => true,
+    })
+}
+
+fn remove_left(d: DirEntry) -> Result<u64, Event> {
+    let is_dir: bool = d.file_type().map(|t| t.is_dir()).unwrap_or(false);
+    if is_dir {
+        return remove_stale(d.path());
+    }
+    let p: PathBuf = d.path();
+    let item: PathBuf = match d.file_name().to_str().and_then(lease2item) {
+        Some(item) => p.with_file_name(item),
+        None => return Ok(0),
+    };
+    // a busy lease is being updated by a consumer
+    let _claim: Claim = match Claim::acquire(&p) {
+        Ok(c) => c,
+        Err(Event::Again) => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    match is_stale(&p, item)? {
+        false => Ok(0),
+        true => remove_lease(&p).map(|_| 1),
+    }
+}
+
+/// Removes lease files left behind by removed or replaced items(expired, vacuumed, ...).
+///
+/// Shard directories will be scanned recursively.
+/// Returns the number of removed files.
+pub fn remove_stale<P>(dirname: P) -> Result<u64, Event>
+where
+    P: AsRef<Path>,
+{
+    let dirents = std::fs::read_dir(dirname)
+        .map_err(|e| Event::UnexpectedError(format!("Unable to read dir: {}", e)))?;
+    dirents.into_iter().try_fold(0, |tot, r| {
+        let d: DirEntry =
+            r.map_err(|e| Event::UnexpectedError(format!("Unable to read dir entry: {}", e)))?;
+        remove_left(d).map(|cnt: u64| cnt + tot)
+    })
+}
+
+/// Consumer leases persisted beside item files(see `lease_path_new`).
+///
+/// Lease files will be updated while holding a slot claim(see `claim::Claim`).
+/// A lease is tied to the item(name + creation time) and to the owner id of the consumer.
+/// Leases taken by this instance will be remembered to check `Ack`/`Nack`; leases found in files
+/// with the same owner id(e.g, taken before a restart) will be accepted as well.
+pub struct Leases<B, C> {
+    path_builder: B,
+    clock: C,
+    visibility: Duration,
+    owner: u64,
+    held: HashMap<Name, Lease>,
+}
+
+impl<B, C> Leases<B, C>
+where
+    B: Fn(Name) -> PathBuf,
+    C: Clock,
+{
+    /// Creates new leases(owned by a new owner id; see `owner_new`).
+    ///
+    /// # Arguments
+    /// - path_builder: Builds a path for a named item.
+    /// - clock:        Gets the current time.
+    /// - visibility:   Claimed items will be visible again after this timeout.
+    pub fn new(path_builder: B, clock: C, visibility: Duration) -> Self {
+        Self {
+            path_builder,
+            clock,
+            visibility,
+            owner: owner_new(),
+            held: HashMap::new(),
+        }
+    }
+
+    /// Creates new leases owned by the consumer id(persistent across restarts).
+    pub fn with_owner(self, owner: u64) -> Self {
+        Self { owner, ..self }
+    }
+
+    pub fn owner(&self) -> u64 {
+        self.owner
+    }
+
+    fn lease_path(&self, name: &Name) -> Result<PathBuf, Event> {
+        lease_path_new((self.path_builder)(name.clone()))
+    }
+
+    /// Gets the creation time of the item(`None` if removed).
+    fn created(&self, name: &Name) -> Result<Option<SystemTime>, Event> {
+        order::path2created((self.path_builder)(name.clone()))
+            .map_err(|e| Event::UnexpectedError(format!("Unable to get creation time: {}", e)))
+    }
+
+    /// Takes the lease of the item(`Ok(false)` if another consumer holds an unexpired lease).
+    ///
+    /// A lease of a replaced item will be ignored; a missing item can not be leased.
+    pub fn acquire(&mut self, name: &Name) -> Result<bool, Event> {
+        let p: PathBuf = self.lease_path(name)?;
+        let _claim: Claim = match Claim::acquire(&p) {
+            Ok(c) => c,
+            Err(Event::Again) => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        let created: SystemTime = match self.created(name)? {
+            Some(t) => t,
+            None => return Ok(false),
+        };
+        let now: SystemTime = self.clock.now();
+        let busy: bool = read_lease(&p)?
+            .map(|l: Lease| !l.is_expired(now) && l.is_for(created))
+            .unwrap_or(false);
+        if busy {
+            return Ok(false);
+        }
+        let lease = Lease::new(now + self.visibility)
+            .with_created(created)
+            .with_owner(self.owner);
+        write::write_atomic(&Vec::from(&lease), &p)?;
+        self.held.insert(name.clone(), lease);
+        Ok(true)
+    }
+
+    /// Checks the lease of the item under the slot claim.
+    fn check(&self, name: &Name, p: &Path) -> Result<(), Event> {
+        let ours: Option<Lease> = read_lease(p)?.filter(|l: &Lease| l.owner() == self.owner);
+        let lease: Lease = match (self.held.get(name), ours) {
+            (None, None) => return Err(Event::NoEntry(name.clone())),
+            (Some(h), Some(l)) if *h == l => l,
+            (None, Some(l)) => l,
+            _ => return Err(Event::Expired(name.clone())),
+        };
+        let now: SystemTime = self.clock.now();
+        let same: bool = self
+            .created(name)?
+            .is_some_and(|t: SystemTime| lease.is_for(t));
+        match !lease.is_expired(now) && same {
+            true => Ok(()),
+            false => Err(Event::Expired(name.clone())),
+        }
+    }
+
+    /// Runs the closure if this consumer holds the unexpired lease, then removes the lease.
+    ///
+    /// * `Event::NoEntry`: the lease was not taken by this consumer.
+    /// * `Event::Expired`: the lease expired or the item was replaced(the item may be claimed by
+    ///   another consumer).
+    /// * `Event::Again`:   the lease file is busy(the lease is still held).
+    pub fn release<F>(&mut self, name: &Name, f: F) -> Event
+    where
+        F: FnOnce() -> Event,
+    {
+        let released = || {
+            let p: PathBuf = self.lease_path(name)?;
+            let _claim: Claim = Claim::acquire(&p)?;
+            self.check(name, &p)?;
+            match f() {
+                Event::Success => remove_lease(&p).map(|_| Event::Success),
+                e => Err(e),
+            }
+        };
+        match released() {
+            Ok(evt) => {
+                self.held.remove(name);
+                evt
+            }
+            Err(e @ (Event::Expired(_) | Event::NoEntry(_))) => {
+                self.held.remove(name);
+                e
+            }
+            // the lease is still held(busy lease file or failed closure)
+            Err(e) => e,
+        }
+    }
+
+    /// Removes the lease of the removed item(any owner).
+    pub fn remove(&mut self, name: &Name) -> Result<(), Event> {
+        let p: PathBuf = self.lease_path(name)?;
+        let _claim: Claim = Claim::acquire(&p)?;
+        remove_lease(&p)?;
+        self.held.remove(name);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_lease {
+
+    mod remove_stale {
+        use std::path::Path;
+        use std::time::{Duration, SystemTime};
+
+        use crate::checksum::Crc32c;
+        use crate::clock::ManualClock;
+        use crate::full::Layout;
+        use crate::header::ItemFormat;
+        use crate::item::{Item, Name, NamedItem};
+        use crate::lease::{self, Leases};
+        use crate::write::{self, WriteMode};
+
+        #[test]
+        #[ignore]
+        fn test_left() {
+            let dirname = Path::new("./test.d/lease/remove_stale/left.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let clock = ManualClock::new(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000));
+            let layout = Layout::flat(dirname);
+            let w = write::writer_unchecked_new_layout_with_format_clock(
+                layout.clone(),
+                ItemFormat::new(Crc32c),
+                WriteMode::Atomic,
+                clock.clone(),
+            );
+            let write = |n: &str| {
+                w(NamedItem::new(Item::from(b"job".as_slice()), Name::from(n))).unwrap();
+            };
+            ["00", "01", "02"].into_iter().for_each(write);
+            let mut leases = Leases::new(
+                layout.path_builder(),
+                clock.clone(),
+                Duration::from_secs(30),
+            );
+            for n in ["00", "01", "02"] {
+                assert_eq!(leases.acquire(&Name::from(n)), Ok(true));
+            }
+            assert_eq!(lease::remove_stale(dirname), Ok(0));
+
+            // removed and replaced items
+            std::fs::remove_file(dirname.join("00")).unwrap();
+            clock.advance(Duration::from_secs(1));
+            write("01");
+            assert_eq!(lease::remove_stale(dirname), Ok(2));
+            assert!(!dirname.join(".00.lease").exists());
+            assert!(!dirname.join(".01.lease").exists());
+            assert!(dirname.join(".02.lease").exists());
+        }
+    }
+
+    mod lease_path_new {
+        use std::path::{Path, PathBuf};
+
+        use crate::lease;
+        use crate::tmp;
+
+        #[test]
+        fn test_hidden() {
+            let p: PathBuf = lease::lease_path_new("./test.d/42").unwrap();
+            assert_eq!(p, Path::new("./test.d/.42.lease"));
+            let name: &str = p.file_name().unwrap().to_str().unwrap();
+            assert!(!tmp::is_tmp_name(name));
+            assert_eq!(lease::lease2item(name), Some("42"));
+            assert_eq!(lease::lease2item("42"), None);
+            assert_eq!(lease::lease2item(".lease"), None);
+        }
+    }
+
+    mod lease {
+        use std::time::{Duration, SystemTime};
+
+        use crate::lease::{Lease, LEASE_SIZE};
+
+        #[test]
+        fn test_bytes() {
+            let deadline = SystemTime::UNIX_EPOCH + Duration::from_secs(42);
+            let l = Lease::new(deadline);
+            let b: Vec<u8> = Vec::from(&l);
+            assert_eq!(b.len(), LEASE_SIZE);
+            assert_eq!(Lease::try_from(b.as_slice()), Ok(l));
+            assert!(l.is_expired(deadline));
+            assert!(!l.is_expired(deadline - Duration::from_micros(1)));
+            assert_ne!(Lease::new(deadline), l);
+
+            let created = SystemTime::UNIX_EPOCH + Duration::from_secs(7);
+            let l = Lease::new(deadline).with_created(created).with_owner(42);
+            let b: Vec<u8> = Vec::from(&l);
+            assert_eq!(Lease::try_from(b.as_slice()), Ok(l));
+            assert_eq!(l.owner(), 42);
+            assert!(l.is_for(created));
+            assert!(!l.is_for(created + Duration::from_micros(1)));
+            assert!(Lease::try_from(&b[..16]).is_err());
+        }
+    }
+}
@@ -1,20 +1,45 @@
+pub mod batch;
+pub mod builder;
+pub mod checksum;
+pub mod claim;
+pub mod clock;
+pub mod codec;
 pub mod compose;
+pub mod consumer;
+pub mod cursor;
 pub mod del;
 pub mod empty;
+pub mod envelope;
 pub mod evt;
+pub mod ext;
 pub mod full;
+pub mod header;
 pub mod integer;
 pub mod item;
+pub mod lease;
 pub mod list;
+pub mod mem;
+pub mod meta;
+pub mod naming;
 pub mod next;
+pub mod order;
 pub mod push;
+pub mod quota;
 pub mod read;
 pub mod request;
+pub mod seq;
+pub mod shared;
+pub mod stat;
+pub mod stream;
+pub mod tmp;
+pub mod ttl;
 pub mod u;
+pub mod vacuum;
 pub mod write;
 
 use crate::evt::Event;
 use crate::request::Request;
+use crate::stat::{Stat, StatRingBuffer};
 
 use crate::item::{Item, Name};
 
@@ -74,6 +99,44 @@ where
         })
 }
 
+fn remove_expired_item<R>(buf: &mut R, n: Name) -> Result<u64, Event>
+where
+    R: RingBuffer,
+{
+    let q: Request = Request::Get(n);
+    match buf.handle(q) {
+        Event::Expired(expired_name) => remove_item(buf, expired_name),
+        _ => Ok(0),
+    }
+}
+
+fn remove_expired_items_from_list<R>(buf: &mut R) -> Result<u64, Event>
+where
+    R: RingBuffer,
+{
+    let q: Request = Request::List;
+    match buf.handle(q) {
+        Event::NamesGot(names) => names.into_iter().try_fold(0, |tot, name| {
+            remove_expired_item(buf, name).map(|cnt: u64| cnt + tot)
+        }),
+        e => Err(e),
+    }
+}
+
+/// Removes expired buffer items.
+///
+/// The buffer with item headers required(see `ttl::Ttl`).
+pub fn remove_expired_buffers<R>(buf: &mut R) -> Event
+where
+    R: RingBuffer,
+{
+    remove_expired_items_from_list(buf)
+        .map(Event::ExpiredItemsRemoved)
+        .unwrap_or_else(|e| {
+            Event::UnexpectedError(format!("Unable to remove expired items: {:#?}", e))
+        })
+}
+
 /// An interface for creating request handler.
 pub trait RingBuffer {
     fn handle(&mut self, req: Request) -> Event;
@@ -87,7 +150,10 @@ where
     move |req: Request| r.handle(req)
 }
 
-/// Helper struct for creating request handler.
+/// Helper struct for creating request handler(see `builder::FsRingBufferBuilder`).
+///
+/// `Request::Stat` will be rejected(`Event::BadRequest`); use `with_stat` to report the
+/// occupancy, byte usage and broken items.
 pub struct FsRingBuffer<G, D, P, L> {
     pub get: G,
     pub del: D,
@@ -102,6 +168,32 @@ where
     P: FnMut(Item) -> Event,
     L: Fn() -> Event,
 {
+    /// Creates new request handler.
+    ///
+    /// # Arguments
+    /// - get:  Gets a named item.
+    /// - del:  Removes a named item.
+    /// - push: Pushes an item.
+    /// - list: Lists names of items(`Event::NamesGot`).
+    pub fn new(get: G, del: D, push: P, list: L) -> Self {
+        Self {
+            get,
+            del,
+            push,
+            list,
+        }
+    }
+
+    /// Creates new request handler which reports the stat(see `stat::stat_new`).
+    ///
+    /// Broken items found by `Request::Get` will be counted until `Request::Vacuum`.
+    pub fn with_stat<S>(self, stat: S) -> StatRingBuffer<Self, S>
+    where
+        S: Fn() -> Result<Stat, Event>,
+    {
+        StatRingBuffer::new(self, stat)
+    }
+
     fn handle_get(&mut self, name: Name) -> Event {
         (self.get)(name)
     }
@@ -132,7 +224,18 @@ where
             Request::Del(name) => self.handle_del(name),
             Request::Push(item) => self.handle_push(item),
             Request::List => self.handle_list(),
+            Request::PushBatch(items) => {
+                batch::handle_many(self, items.into_iter().map(Request::Push).collect())
+            }
+            Request::GetMany(names) => {
+                batch::handle_many(self, names.into_iter().map(Request::Get).collect())
+            }
+            Request::DelMany(names) => {
+                batch::handle_many(self, names.into_iter().map(Request::Del).collect())
+            }
             Request::Vacuum => remove_broken_buffers(self),
+            Request::Expire => remove_expired_buffers(self),
+            _ => Event::BadRequest,
         }
     }
 }
@@ -140,6 +243,59 @@ where
 #[cfg(test)]
 mod test_lib {
 
+    mod fs_ring_buffer {
+        use crate::evt::Event;
+        use crate::item::{Item, Name};
+        use crate::request::Request;
+        use crate::stat::Stat;
+        use crate::{FsRingBuffer, RingBuffer};
+
+        fn list() -> Event {
+            Event::NamesGot(vec![Name::from("00"), Name::from("01")])
+        }
+
+        #[test]
+        fn test_literal() {
+            let mut rb = FsRingBuffer {
+                get: |n: Name| Event::NoEntry(n),
+                del: |_: Name| Event::Success,
+                push: |_: Item| Event::ItemDropped,
+                list,
+            };
+            assert_eq!(rb.handle(Request::List), list());
+            assert_eq!(rb.handle(Request::Stat), Event::BadRequest);
+        }
+
+        #[test]
+        fn test_with_stat() {
+            let stat = || {
+                Ok(Stat {
+                    used: 2,
+                    capacity: 256,
+                    ..Stat::default()
+                })
+            };
+            let mut rb = FsRingBuffer::new(
+                |n: Name| Event::Broken(n),
+                |_: Name| Event::Success,
+                |_: Item| Event::ItemOverwrote(Name::from("01")),
+                list,
+            )
+            .with_stat(stat);
+            let broken = |rb: &mut dyn RingBuffer| -> u64 {
+                let s: Stat = rb.handle(Request::Stat).try_into().unwrap();
+                s.broken
+            };
+            let names = vec![Name::from("00"), Name::from("01"), Name::from("00")];
+            rb.handle(Request::GetMany(names));
+            assert_eq!(broken(&mut rb), 2);
+            rb.handle(Request::Del(Name::from("00")));
+            assert_eq!(broken(&mut rb), 1);
+            rb.handle(Request::Push(Item::from(b"item".as_slice())));
+            assert_eq!(broken(&mut rb), 0);
+        }
+    }
+
     mod remove_broken_buffers {
         use std::fs;
         use std::path::Path;
@@ -1,8 +1,13 @@
-use std::path::Path;
+use std::fs::{DirEntry, FileType, Metadata};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 use crate::empty;
 use crate::evt::Event;
+use crate::full::{self, Layout};
 use crate::item::Name;
+use crate::naming::HexNames;
 
 pub mod u;
 
@@ -46,6 +51,43 @@ where
     }
 }
 
+/// Creates checked list getter which uses a closure to build path for (non-)empty check.
+pub fn list_checked_new<L, B>(list: L, path_builder: B) -> impl Fn() -> Result<Vec<Name>, Event>
+where
+    L: Fn() -> Result<Vec<Name>, Event>,
+    B: Fn(Name) -> PathBuf,
+{
+    let empty_checker = empty::name2empty_fs_new(path_builder);
+    let non_empty_checker = empty::nonempty_checker_new(empty_checker);
+    let filter = move |n: &Name| non_empty_checker(n.clone());
+    list_filtered_new(list, filter)
+}
+
+/// Creates checked list getter which uses default (non-)empty checker.
+pub fn list_checked_new_default<L, P>(list: L, dirname: P) -> impl Fn() -> Result<Vec<Name>, Event>
+where
+    L: Fn() -> Result<Vec<Name>, Event>,
+    P: AsRef<Path>,
+{
+    let path_builder = full::fullpath_builder_new(dirname);
+    list_checked_new(list, path_builder)
+}
+
+/// Creates checked list handler which uses a closure to build path for (non-)empty check.
+pub fn list_request_handler_new_with_path_builder<L, B>(
+    list: L,
+    path_builder: B,
+) -> impl Fn() -> Event
+where
+    L: Fn() -> Result<Vec<Name>, Event>,
+    B: Fn(Name) -> PathBuf,
+{
+    let empty_checker = empty::name2empty_fs_new(path_builder);
+    let non_empty_checker = empty::nonempty_checker_new(empty_checker);
+    let filter = move |n: &Name| non_empty_checker(n.clone());
+    list_request_handler_new(list, filter)
+}
+
 /// Creates checked list handler which uses default (non-)empty checker.
 pub fn list_request_handler_new_default<L, P>(list: L, dirname: P) -> impl Fn() -> Event
 where
@@ -58,6 +100,149 @@ where
     list_request_handler_new(list, filter)
 }
 
+fn read_dir_entries(dir: &Path) -> Result<Vec<DirEntry>, Event> {
+    match std::fs::read_dir(dir) {
+        Ok(rd) => rd
+            .collect::<Result<_, _>>()
+            .map_err(|e| Event::UnexpectedError(format!("Unable to read dir entry: {}", e))),
+        // no items yet(missing shard dir)
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(Event::UnexpectedError(format!(
+            "Unable to read dir({:#?}): {}",
+            dir, e
+        ))),
+    }
+}
+
+fn entry2sized(
+    layout: &Layout,
+    names: &HexNames,
+    d: &DirEntry,
+) -> Result<Option<(Name, u64)>, Event> {
+    let name: Name = match d.file_name().to_str() {
+        Some(s) => Name::from(s),
+        None => return Ok(None),
+    };
+    // temporary files, cursors, leases and misplaced items
+    if !names.contains(&name) || layout.path(&name) != d.path() {
+        return Ok(None);
+    }
+    match d.metadata() {
+        Ok(m) => Ok(Some((name, m.len())).filter(|pair| 0 < pair.1)),
+        // removed after the scan
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Event::UnexpectedError(format!(
+            "Unable to get metadata: {}",
+            e
+        ))),
+    }
+}
+
+fn scan_dir(
+    layout: &Layout,
+    names: &HexNames,
+    dir: &Path,
+    depth: usize,
+) -> Result<Vec<(Name, u64)>, Event> {
+    read_dir_entries(dir)?.into_iter().try_fold(
+        vec![],
+        |mut found: Vec<(Name, u64)>, d: DirEntry| {
+            let ft: Option<FileType> = d.file_type().ok();
+            match ft {
+                Some(t) if t.is_dir() && 0 < depth => {
+                    found.extend(scan_dir(layout, names, &d.path(), depth - 1)?)
+                }
+                Some(t) if t.is_file() => found.extend(entry2sized(layout, names, &d)?),
+                _ => {}
+            }
+            Ok(found)
+        },
+    )
+}
+
+/// Creates new directory scanner which gets non-empty slots with their sizes(sorted by name).
+///
+/// The directories will be read once; no metadata call per possible slot.
+/// Names out of the naming scheme(temporary files, cursors, leases, ...) and symlinks will be skipped.
+///
+/// # Arguments
+/// - layout: Directory layout of buffer files(flat or sharded).
+/// - names: Naming scheme(capacity and name width).
+pub fn scanner_new_layout(
+    layout: Layout,
+    names: HexNames,
+) -> impl Fn() -> Result<Vec<(Name, u64)>, Event> {
+    move || {
+        let mut found = scan_dir(&layout, &names, layout.dirname(), layout.depth())?;
+        found.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+        Ok(found)
+    }
+}
+
+/// Creates new list getter which scans the directory once(see `scanner_new_layout`).
+pub fn list_scanned_new(layout: Layout, names: HexNames) -> impl Fn() -> Result<Vec<Name>, Event> {
+    let scan = scanner_new_layout(layout, names);
+    move || scan().map(|found: Vec<(Name, u64)>| found.into_iter().map(|pair| pair.0).collect())
+}
+
+/// Creates list handler which scans the directory once(scanned names are non-empty already).
+pub fn list_request_handler_new_scanned(layout: Layout, names: HexNames) -> impl Fn() -> Event {
+    list_request_handler_new(list_scanned_new(layout, names), |_: &Name| Ok(true))
+}
+
+fn names2oldest<M>(names: Vec<Name>, modified: &M) -> Result<Name, Event>
+where
+    M: Fn(&Name) -> Result<SystemTime, Event>,
+{
+    let timed: Vec<(SystemTime, Name)> = names
+        .into_iter()
+        .map(|n: Name| modified(&n).map(|t: SystemTime| (t, n)))
+        .collect::<Result<_, _>>()?;
+    timed
+        .into_iter()
+        .min_by_key(|pair| pair.0)
+        .map(|pair| pair.1)
+        .ok_or_else(|| Event::UnexpectedError("No item found".into()))
+}
+
+/// Creates new oldest item finder which uses closures to get names and modified time.
+pub fn oldest_finder_new<L, M>(list: L, modified: M) -> impl Fn() -> Result<Name, Event>
+where
+    L: Fn() -> Result<Vec<Name>, Event>,
+    M: Fn(&Name) -> Result<SystemTime, Event>,
+{
+    move || {
+        let names: Vec<Name> = list()?;
+        names2oldest(names, &modified)
+    }
+}
+
+/// Creates new modified time getter which uses a closure to build path from `Name`.
+pub fn modified_getter_new<B>(path_builder: B) -> impl Fn(&Name) -> Result<SystemTime, Event>
+where
+    B: Fn(Name) -> PathBuf,
+{
+    move |n: &Name| {
+        std::fs::metadata(path_builder(n.clone()))
+            .and_then(|m: Metadata| m.modified())
+            .map_err(|e| Event::UnexpectedError(format!("Unable to get modified time: {}", e)))
+    }
+}
+
+/// Creates new oldest item finder which uses default path builder to get modified time.
+///
+/// # Arguments
+/// - list: Gets non-empty names.
+/// - dirname: Path to buffer files.
+pub fn oldest_finder_new_default<L, P>(list: L, dirname: P) -> impl Fn() -> Result<Name, Event>
+where
+    L: Fn() -> Result<Vec<Name>, Event>,
+    P: AsRef<Path>,
+{
+    let path_builder = full::fullpath_builder_new(dirname);
+    oldest_finder_new(list, modified_getter_new(path_builder))
+}
+
 #[cfg(test)]
 mod test_list {
 
@@ -115,6 +300,36 @@ mod test_list {
         }
     }
 
+    mod oldest_finder_new {
+        use std::time::{Duration, SystemTime};
+
+        use crate::evt::Event;
+        use crate::item::Name;
+        use crate::list;
+
+        #[test]
+        fn test_oldest() {
+            let flist = || Ok(vec![Name::from("00"), Name::from("01"), Name::from("02")]);
+            let modified = |n: &Name| {
+                let secs: u64 = match n.as_str() {
+                    "01" => 1,
+                    _ => 2,
+                };
+                Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            };
+            let f = list::oldest_finder_new(flist, modified);
+            assert_eq!(f(), Ok(Name::from("01")));
+        }
+
+        #[test]
+        fn test_empty() {
+            let flist = || Ok(vec![]);
+            let modified = |_: &Name| Err(Event::BadRequest);
+            let f = list::oldest_finder_new(flist, modified);
+            assert!(f().is_err());
+        }
+    }
+
     mod list_request_handler_new_default {
         use std::path::Path;
 
@@ -134,4 +349,56 @@ mod test_list {
             assert_eq!(evt, Event::NamesGot(vec![]));
         }
     }
+
+    mod scanner_new_layout {
+        use std::path::Path;
+
+        use crate::evt::Event;
+        use crate::full::Layout;
+        use crate::item::Name;
+        use crate::list;
+        use crate::naming::HexNames;
+
+        #[test]
+        #[ignore]
+        fn test_flat() {
+            let dirname = Path::new("./test.d/list/scanner_new_layout/flat.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname.join("0f")).unwrap();
+            std::fs::write(dirname.join("42"), b"item").unwrap();
+            std::fs::write(dirname.join("01"), b"12").unwrap();
+            std::fs::write(dirname.join("02"), b"").unwrap();
+            std::fs::write(dirname.join(".42.1234.tmp"), b"orphan").unwrap();
+            std::fs::write(dirname.join("foreign"), b"foreign").unwrap();
+
+            let names: HexNames = HexNames::u8().with_capacity(0x40).unwrap();
+            let f = list::scanner_new_layout(Layout::flat(dirname), names);
+            assert_eq!(f().unwrap(), vec![(Name::from("01"), 2)]);
+
+            let f = list::list_request_handler_new_scanned(Layout::flat(dirname), HexNames::u8());
+            let expected = vec![Name::from("01"), Name::from("42")];
+            assert_eq!(f(), Event::NamesGot(expected));
+        }
+
+        #[test]
+        #[ignore]
+        fn test_sharded() {
+            let dirname = Path::new("./test.d/list/scanner_new_layout/sharded.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname.join("ab")).unwrap();
+            std::fs::create_dir_all(dirname.join("00")).unwrap();
+            std::fs::write(dirname.join("ab/abcd"), b"item").unwrap();
+            std::fs::write(dirname.join("00/0001"), b"item1").unwrap();
+            std::fs::write(dirname.join("00/abce"), b"misplaced").unwrap();
+
+            let layout: Layout = Layout::sharded(dirname, 1, 2).unwrap();
+            let f = list::scanner_new_layout(layout, HexNames::u16());
+            let expected = vec![(Name::from("0001"), 5), (Name::from("abcd"), 4)];
+            assert_eq!(f().unwrap(), expected);
+
+            let missing: Layout = Layout::flat(dirname.join("missing.d"));
+            let f = list::scanner_new_layout(missing, HexNames::u16());
+            assert_eq!(f().unwrap(), vec![]);
+        }
+    }
 }
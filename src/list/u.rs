@@ -1,5 +1,6 @@
 use crate::evt::Event;
 use crate::item::Name;
+use crate::naming::HexNames;
 
 /// Creates new trivial list getter.
 pub fn list_names_u8_all_new() -> impl Fn() -> Result<Vec<Name>, Event> {
@@ -10,6 +11,30 @@ pub fn list_names_u8_all_new() -> impl Fn() -> Result<Vec<Name>, Event> {
     }
 }
 
+/// Creates new list getter which uses the naming scheme to enumerate all names.
+pub fn list_names_hex_new(names: HexNames) -> impl Fn() -> Result<Vec<Name>, Event> {
+    move || Ok(names.names().collect())
+}
+
+/// Creates new trivial list getter which uses u16 names(0000 ... ffff).
+pub fn list_names_u16_all_new() -> impl Fn() -> Result<Vec<Name>, Event> {
+    list_names_hex_new(HexNames::u16())
+}
+
+/// Creates new list getter which uses u16 names(0000 ... capacity-1).
+pub fn list_names_u16_new(capacity: u64) -> Result<impl Fn() -> Result<Vec<Name>, Event>, Event> {
+    HexNames::u16()
+        .with_capacity(capacity)
+        .map(list_names_hex_new)
+}
+
+/// Creates new list getter which uses u32 names(00000000 ... capacity-1).
+pub fn list_names_u32_new(capacity: u64) -> Result<impl Fn() -> Result<Vec<Name>, Event>, Event> {
+    HexNames::u32()
+        .with_capacity(capacity)
+        .map(list_names_hex_new)
+}
+
 #[cfg(test)]
 mod test_u {
 
@@ -23,4 +48,42 @@ mod test_u {
             assert_eq!(v.len(), 256);
         }
     }
+
+    mod list_names_u16_new {
+        use crate::item::Name;
+        use crate::list;
+
+        #[test]
+        fn test_len() {
+            let f = list::u::list_names_u16_new(1000).unwrap();
+            let v: Vec<_> = f().unwrap();
+            assert_eq!(v.len(), 1000);
+            assert_eq!(v[999], Name::from("03e7"));
+        }
+
+        #[test]
+        fn test_too_large() {
+            let r = list::u::list_names_u16_new(65537);
+            assert!(r.is_err());
+        }
+    }
+
+    mod list_names_u32_new {
+        use crate::item::Name;
+        use crate::list;
+
+        #[test]
+        fn test_len() {
+            let f = list::u::list_names_u32_new(3).unwrap();
+            let v: Vec<_> = f().unwrap();
+            assert_eq!(
+                v,
+                vec![
+                    Name::from("00000000"),
+                    Name::from("00000001"),
+                    Name::from("00000002"),
+                ]
+            );
+        }
+    }
 }
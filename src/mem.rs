@@ -0,0 +1,406 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::SystemTime;
+
+use crate::checksum::Checksum;
+use crate::clock::{Clock, SystemClock};
+use crate::evt::Event;
+use crate::item::{Item, Name, NamedItem};
+use crate::list;
+use crate::naming::HexNames;
+use crate::next;
+use crate::order::OrderedRingBuffer;
+use crate::push::{self, FullPolicy};
+use crate::read;
+use crate::request::Request;
+use crate::stat::Stat;
+use crate::u::buf;
+use crate::write;
+use crate::{FsRingBuffer, RingBuffer};
+
+struct Slot {
+    raw: Vec<u8>,
+    created: SystemTime,
+}
+
+/// In-memory slots of ring buffers(cloned handles share the same slots).
+///
+/// Stored bytes(payload + checksum) can be inspected or replaced to simulate broken items.
+#[derive(Clone, Default)]
+pub struct MemSlots {
+    slots: Arc<Mutex<BTreeMap<String, Slot>>>,
+}
+
+impl MemSlots {
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, Slot>> {
+        self.slots.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Gets stored bytes of the named slot(`None` if never written).
+    pub fn raw(&self, name: &Name) -> Option<Vec<u8>> {
+        self.lock().get(name.as_str()).map(|s: &Slot| s.raw.clone())
+    }
+
+    /// Replaces stored bytes of the named slot(empty bytes make the slot empty).
+    pub fn set_raw(&self, name: &Name, raw: Vec<u8>, created: SystemTime) {
+        self.lock()
+            .insert(name.as_str().into(), Slot { raw, created });
+    }
+
+    pub(crate) fn truncate(&self, name: &Name) {
+        if let Some(s) = self.lock().get_mut(name.as_str()) {
+            s.raw.clear();
+        }
+    }
+
+    pub(crate) fn is_empty(&self, name: &Name) -> bool {
+        self.lock()
+            .get(name.as_str())
+            .is_none_or(|s: &Slot| s.raw.is_empty())
+    }
+
+    fn created(&self, name: &Name) -> Option<SystemTime> {
+        self.lock()
+            .get(name.as_str())
+            .filter(|s: &&Slot| !s.raw.is_empty())
+            .map(|s: &Slot| s.created)
+    }
+
+    /// Gets non-empty slots in the naming scheme(sorted by name).
+    pub(crate) fn used(&self, names: &HexNames) -> Vec<(Name, u64, SystemTime)> {
+        self.lock()
+            .iter()
+            .filter(|(_, s)| !s.raw.is_empty())
+            .map(|(n, s)| (Name::from(n.as_str()), s.raw.len() as u64, s.created))
+            .filter(|t| names.contains(&t.0))
+            .collect()
+    }
+}
+
+fn writer_new<K, T>(
+    slots: MemSlots,
+    checker: Arc<K>,
+    clock: T,
+) -> impl Fn(NamedItem) -> Result<Name, Event>
+where
+    K: Checksum,
+    T: Clock,
+{
+    move |named: NamedItem| {
+        let (name, item) = named.into_pair();
+        let mut raw: Vec<u8> = item.into();
+        let chk: Vec<u8> = checker.compute(&raw);
+        raw.extend(chk);
+        slots.set_raw(&name, raw, clock.now());
+        Ok(name)
+    }
+}
+
+fn stat_new(slots: MemSlots, names: HexNames) -> impl Fn() -> Result<Stat, Event> {
+    move || {
+        let capacity: u64 = names.capacity();
+        let empty = Stat {
+            free: capacity,
+            capacity,
+            ..Stat::default()
+        };
+        let used = slots.used(&names).into_iter();
+        Ok(used.fold(empty, |s: Stat, t| s.with_item(t.1, t.2)))
+    }
+}
+
+/// Creates in-memory random ring buffer impl which behaves like a headerless `FsRingBuffer`.
+///
+/// - Names out of the naming scheme will be rejected(`Event::BadRequest`).
+/// - Empty slots(never written or deleted) will be reported as `Event::NoEntry` and not listed.
+/// - Checksum mismatches will be reported as `Event::Broken`(see `MemSlots::set_raw`).
+/// - Pushes retry with next names on used slots(`Event::Again` after `push::RETRY_LIMIT`
+///   unless the buffer is full).
+///
+/// # Arguments
+/// - slots: Storage of items(keep a clone to inspect items).
+/// - names: Naming scheme(capacity and name width).
+/// - checker: Computes checksum.
+/// - get_name: Gets next random name.
+/// - policy: Strategy to push an item into a full buffer.
+/// - clock: Stamps the creation time of items(`Order::Created`, `Stat`).
+pub fn ring_buffer_impl_mem_new<K, N, T>(
+    slots: MemSlots,
+    names: HexNames,
+    checker: K,
+    get_name: N,
+    policy: FullPolicy,
+    clock: T,
+) -> impl RingBuffer
+where
+    K: Checksum,
+    N: FnMut() -> Result<Name, Event>,
+    T: Clock + Clone,
+{
+    let checker = Arc::new(checker);
+    let checksize: usize = checker.size();
+
+    let (gs, gk) = (slots.clone(), checker.clone());
+    let get = move |n: Name| match gs.raw(&n).filter(|raw| !raw.is_empty()) {
+        None => Event::NoEntry(n),
+        Some(raw) => {
+            let chk = |dat: &[u8]| gk.compute(dat);
+            match read::raw2item_with_checksum(n.clone(), raw, checksize, &chk) {
+                Ok(item) => Event::ItemGot(NamedItem::new(item, n)),
+                Err(e) => e,
+            }
+        }
+    };
+    let ds = slots.clone();
+    let del = move |n: Name| {
+        ds.truncate(&n);
+        Event::Success
+    };
+
+    let es = slots.clone();
+    let wtr = write::writer_checked_new(
+        writer_new(slots.clone(), checker.clone(), clock.clone()),
+        move |n: &Name| Ok(es.is_empty(n)),
+    );
+    let push = push::push_handler_new_unmanaged_retry(get_name, wtr, push::RETRY_LIMIT);
+
+    let us = slots.clone();
+    let used = move || {
+        let used = us.used(&names).into_iter();
+        Ok(used.map(|t| t.0).collect::<Vec<Name>>())
+    };
+    let fs = slots.clone();
+    let is_full = move || Ok(names.capacity() <= fs.used(&names).len() as u64);
+    let ms = slots.clone();
+    let oldest = list::oldest_finder_new(used.clone(), move |n: &Name| {
+        ms.created(n).ok_or_else(|| Event::NoEntry(n.clone()))
+    });
+    let overwriter = writer_new(slots.clone(), checker, clock);
+    let overwrite = move |item: Item| {
+        let name: Name = oldest()?;
+        overwriter(NamedItem::new(item, name))
+    };
+    let push = push::push_handler_new_with_policy(push, is_full, overwrite, policy);
+
+    let cs = slots.clone();
+    OrderedRingBuffer {
+        inner: FsRingBuffer {
+            get: buf::name_checked_new(names, get),
+            del: buf::name_checked_new(names, del),
+            push,
+            list: list::list_request_handler_new(used, |_: &Name| Ok(true)),
+        }
+        .with_stat(stat_new(slots, names)),
+        created: move |n: &Name| Ok(cs.created(n)),
+    }
+}
+
+/// Raw slots without checksum for tests of ring buffer wrappers(u8 names).
+///
+/// - Items stored as `expired` will be reported as `Event::Expired`.
+/// - Pushes write the slot after the last used one.
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub(crate) struct RawSlots {
+    pub slots: MemSlots,
+}
+
+#[cfg(test)]
+impl RawSlots {
+    pub(crate) fn get(&self, n: Name) -> Event {
+        match self.slots.raw(&n).filter(|raw| !raw.is_empty()) {
+            None => Event::NoEntry(n),
+            Some(raw) if raw == b"expired" => Event::Expired(n),
+            Some(raw) => Event::ItemGot(NamedItem::new(Item::from(raw), n)),
+        }
+    }
+
+    pub(crate) fn del(&self, n: Name) -> Event {
+        self.slots.truncate(&n);
+        Event::Success
+    }
+
+    /// Writes the item(overwrites a used slot).
+    pub(crate) fn write(&self, named: NamedItem) -> Result<Name, Event> {
+        let (name, item) = named.into_pair();
+        self.slots.set_raw(&name, item.into(), SystemTime::now());
+        Ok(name)
+    }
+
+    pub(crate) fn names(&self) -> Vec<Name> {
+        let used = self.slots.used(&HexNames::u8()).into_iter();
+        used.map(|t| t.0).collect()
+    }
+
+    fn push(&self, item: Item) -> Event {
+        let next: u8 = self
+            .names()
+            .last()
+            .map(|n: &Name| u8::from_str_radix(n.as_str(), 16).unwrap() + 1)
+            .unwrap_or(0);
+        match self.write(NamedItem::new(item, Name::from(next))) {
+            Ok(n) => Event::ItemWrote(n),
+            Err(e) => e,
+        }
+    }
+}
+
+#[cfg(test)]
+impl RingBuffer for RawSlots {
+    fn handle(&mut self, req: Request) -> Event {
+        match req {
+            Request::Get(n) => self.get(n),
+            Request::Del(n) => self.del(n),
+            Request::Push(item) => self.push(item),
+            Request::List => Event::NamesGot(self.names()),
+            _ => Event::BadRequest,
+        }
+    }
+}
+
+/// Creates deterministic random name generator(splitmix64).
+pub fn next_seeded_new(names: HexNames, seed: u64) -> impl FnMut() -> Result<Name, Event> {
+    let mut state: u64 = seed;
+    let random = move || {
+        state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let z: u64 = (state ^ (state >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        let z: u64 = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        Ok(z ^ (z >> 31))
+    };
+    next::u::next_random_hex_new(names, random)
+}
+
+/// Creates in-memory random ring buffer which picks names from the seed(see `ring_buffer_impl_mem_new`).
+///
+/// # Arguments
+/// - slots: Storage of items(keep a clone to inspect items).
+/// - names: Naming scheme(capacity and name width).
+/// - checker: Computes checksum.
+/// - policy: Strategy to push an item into a full buffer.
+/// - seed: Seed of the name generator(same seeds, same names).
+pub fn ring_buffer_mem_new_seeded<K>(
+    slots: MemSlots,
+    names: HexNames,
+    checker: K,
+    policy: FullPolicy,
+    seed: u64,
+) -> impl FnMut(Request) -> Event
+where
+    K: Checksum,
+{
+    let get_name = next_seeded_new(names, seed);
+    let rb = ring_buffer_impl_mem_new(slots, names, checker, get_name, policy, SystemClock);
+    crate::ring_buffer_new(rb)
+}
+
+#[cfg(test)]
+mod test_mem {
+
+    mod ring_buffer_mem_new_seeded {
+        use std::time::{Duration, SystemTime};
+
+        use crate::checksum::{Crc32c, Nop};
+        use crate::evt::Event;
+        use crate::item::{Item, Name};
+        use crate::mem::{self, MemSlots};
+        use crate::naming::HexNames;
+        use crate::order::{Order, Page};
+        use crate::push::FullPolicy;
+        use crate::request::Request;
+        use crate::stat::Stat;
+
+        fn item(b: &[u8]) -> Item {
+            Item::from(b)
+        }
+
+        #[test]
+        fn test_push_get_del() {
+            let slots = MemSlots::default();
+            let names: HexNames = HexNames::u8().with_capacity(16).unwrap();
+            let mut f = mem::ring_buffer_mem_new_seeded(
+                slots.clone(),
+                names,
+                Crc32c,
+                FullPolicy::Reject,
+                42,
+            );
+            let n: Name = f(Request::Push(item(b"item"))).try_into().unwrap();
+            assert!(names.contains(&n));
+            assert_eq!(slots.raw(&n).unwrap().len(), 8);
+
+            let got: Item = f(Request::Get(n.clone())).try_into().unwrap();
+            assert_eq!(got, item(b"item"));
+            assert_eq!(f(Request::List), Event::NamesGot(vec![n.clone()]));
+
+            assert_eq!(f(Request::Del(n.clone())), Event::Success);
+            assert_eq!(f(Request::Get(n.clone())), Event::NoEntry(n.clone()));
+            assert_eq!(f(Request::List), Event::NamesGot(vec![]));
+            assert_eq!(f(Request::Del(n)), Event::Success);
+
+            assert_eq!(f(Request::Get(Name::from("10"))), Event::BadRequest);
+            assert_eq!(f(Request::Get(Name::from("../10"))), Event::BadRequest);
+        }
+
+        #[test]
+        fn test_broken() {
+            let slots = MemSlots::default();
+            let mut f = mem::ring_buffer_mem_new_seeded(
+                slots.clone(),
+                HexNames::u8(),
+                Crc32c,
+                FullPolicy::Reject,
+                1,
+            );
+            let n: Name = f(Request::Push(item(b"item"))).try_into().unwrap();
+            slots.set_raw(&n, b"itemXXXX".to_vec(), SystemTime::now());
+            assert_eq!(f(Request::Get(n.clone())), Event::Broken(n.clone()));
+
+            let s: Stat = f(Request::Stat).try_into().unwrap();
+            assert_eq!((s.used, s.broken), (1, 1));
+            assert_eq!(f(Request::Vacuum), Event::BrokenItemsRemoved(1));
+            assert_eq!(f(Request::List), Event::NamesGot(vec![]));
+        }
+
+        #[test]
+        fn test_full() {
+            let names: HexNames = HexNames::u8().with_capacity(2).unwrap();
+            let slots = MemSlots::default();
+            let mut f =
+                mem::ring_buffer_mem_new_seeded(slots.clone(), names, Nop, FullPolicy::Reject, 7);
+            assert!(matches!(f(Request::Push(item(b"a"))), Event::ItemWrote(_)));
+            assert!(matches!(f(Request::Push(item(b"b"))), Event::ItemWrote(_)));
+            assert_eq!(f(Request::Push(item(b"c"))), Event::TooManyItemsAlready);
+
+            let mut f = mem::ring_buffer_mem_new_seeded(
+                slots.clone(),
+                names,
+                Nop,
+                FullPolicy::DropIncoming,
+                7,
+            );
+            assert_eq!(f(Request::Push(item(b"c"))), Event::ItemDropped);
+
+            let oldest = Name::from("01");
+            let t0: SystemTime = SystemTime::now() - Duration::from_secs(60);
+            slots.set_raw(&oldest, b"old".to_vec(), t0);
+            let mut f = mem::ring_buffer_mem_new_seeded(
+                slots.clone(),
+                names,
+                Nop,
+                FullPolicy::OverwriteOldest,
+                7,
+            );
+            assert_eq!(
+                f(Request::Push(item(b"c"))),
+                Event::ItemOverwrote(oldest.clone())
+            );
+            assert_eq!(slots.raw(&oldest).unwrap(), b"c");
+
+            let page = Page::new(Order::Created).with_limit(1);
+            assert_eq!(
+                f(Request::ListPage(page)),
+                Event::NamesGot(vec![Name::from("00")])
+            );
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::BTreeMap;
+
+use crate::evt::Event;
+
+/// A typed metadata value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Bytes(Vec<u8>),
+    Str(String),
+    U64(u64),
+    I64(i64),
+    Bool(bool),
+}
+
+const TAG_BYTES: u8 = 0;
+const TAG_STR: u8 = 1;
+const TAG_U64: u8 = 2;
+const TAG_I64: u8 = 3;
+const TAG_BOOL: u8 = 4;
+
+impl Value {
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::U64(u) => Some(*u),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::I64(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    fn tag(&self) -> u8 {
+        match self {
+            Value::Bytes(_) => TAG_BYTES,
+            Value::Str(_) => TAG_STR,
+            Value::U64(_) => TAG_U64,
+            Value::I64(_) => TAG_I64,
+            Value::Bool(_) => TAG_BOOL,
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Value::Bytes(b) => b.clone(),
+            Value::Str(s) => s.as_bytes().to_vec(),
+            Value::U64(u) => u.to_be_bytes().to_vec(),
+            Value::I64(i) => i.to_be_bytes().to_vec(),
+            Value::Bool(b) => vec![u8::from(*b)],
+        }
+    }
+
+    fn from_tagged(tag: u8, b: &[u8]) -> Result<Self, Event> {
+        let invalid = || Event::UnexpectedError(format!("Invalid metadata value(tag={})", tag));
+        match tag {
+            TAG_BYTES => Ok(Value::Bytes(b.to_vec())),
+            TAG_STR => String::from_utf8(b.to_vec())
+                .map(Value::Str)
+                .map_err(|_| invalid()),
+            TAG_U64 => b
+                .try_into()
+                .map(|a: [u8; 8]| Value::U64(u64::from_be_bytes(a)))
+                .map_err(|_| invalid()),
+            TAG_I64 => b
+                .try_into()
+                .map(|a: [u8; 8]| Value::I64(i64::from_be_bytes(a)))
+                .map_err(|_| invalid()),
+            TAG_BOOL => match b {
+                [0] => Ok(Value::Bool(false)),
+                [1] => Ok(Value::Bool(true)),
+                _ => Err(invalid()),
+            },
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(b: Vec<u8>) -> Self {
+        Value::Bytes(b)
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Str(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Str(s.into())
+    }
+}
+
+impl From<u64> for Value {
+    fn from(u: u64) -> Self {
+        Value::U64(u)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(i: i64) -> Self {
+        Value::I64(i)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+/// Typed key/value metadata of an item(content type, producer id, trace id, ...).
+///
+/// Encoded entries(sorted by key):
+///
+/// | size | field                  |
+/// |------|------------------------|
+/// |    2 | key length(big endian) |
+/// |    * | key(utf-8)             |
+/// |    1 | value type tag         |
+/// |    4 | value length(BE)       |
+/// |    * | value                  |
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Meta {
+    map: BTreeMap<String, Value>,
+}
+
+impl Meta {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a value(returns the previous value).
+    pub fn insert<K, V>(&mut self, key: K, val: V) -> Option<Value>
+    where
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        self.map.insert(key.into(), val.into())
+    }
+
+    /// Creates new metadata which has the value.
+    pub fn with<K, V>(mut self, key: K, val: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        self.insert(key, val);
+        self
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.map.get(key)
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        self.map.remove(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Iterates entries in key order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.map.iter().map(|(k, v)| (k.as_str(), v))
+    }
+}
+
+fn entry2bytes(key: &str, val: &Value, buf: &mut Vec<u8>) -> Result<(), Event> {
+    let klen: u16 = key.len().try_into().map_err(|_| Event::BadRequest)?;
+    let v: Vec<u8> = val.to_bytes();
+    let vlen: u32 = v.len().try_into().map_err(|_| Event::BadRequest)?;
+    buf.extend(klen.to_be_bytes());
+    buf.extend(key.as_bytes());
+    buf.push(val.tag());
+    buf.extend(vlen.to_be_bytes());
+    buf.extend(v);
+    Ok(())
+}
+
+impl TryFrom<&Meta> for Vec<u8> {
+    type Error = Event;
+
+    /// Encodes the metadata(`Event::BadRequest` if a key or a value is too long).
+    fn try_from(m: &Meta) -> Result<Self, Self::Error> {
+        m.iter().try_fold(Vec::new(), |mut buf: Vec<u8>, (k, v)| {
+            entry2bytes(k, v, &mut buf).map(|_| buf)
+        })
+    }
+}
+
+/// Splits bytes at the length(`Event::UnexpectedError` if too short).
+fn take(b: &[u8], len: usize) -> Result<(&[u8], &[u8]), Event> {
+    (len <= b.len())
+        .then(|| b.split_at(len))
+        .ok_or_else(|| Event::UnexpectedError("Truncated metadata".into()))
+}
+
+fn bytes2entry(b: &[u8]) -> Result<(String, Value, &[u8]), Event> {
+    let (klen, rest) = take(b, 2)?;
+    let (key, rest) = take(rest, usize::from(u16::from_be_bytes([klen[0], klen[1]])))?;
+    let key: String = String::from_utf8(key.to_vec())
+        .map_err(|_| Event::UnexpectedError("Invalid metadata key".into()))?;
+    let (tag, rest) = take(rest, 1)?;
+    let (vlen, rest) = take(rest, 4)?;
+    let vlen: u32 = u32::from_be_bytes([vlen[0], vlen[1], vlen[2], vlen[3]]);
+    let (val, rest) = take(rest, vlen as usize)?;
+    Value::from_tagged(tag[0], val).map(|v: Value| (key, v, rest))
+}
+
+impl TryFrom<&[u8]> for Meta {
+    type Error = Event;
+    fn try_from(mut b: &[u8]) -> Result<Self, Self::Error> {
+        let mut m: Meta = Meta::new();
+        while !b.is_empty() {
+            let (key, val, rest) = bytes2entry(b)?;
+            m.insert(key, val);
+            b = rest;
+        }
+        Ok(m)
+    }
+}
+
+#[cfg(test)]
+mod test_meta {
+
+    mod meta {
+        use crate::meta::{Meta, Value};
+
+        #[test]
+        fn test_bytes() {
+            let m: Meta = Meta::new()
+                .with("content-type", "application/json")
+                .with("producer", 42u64)
+                .with("offset", -1i64)
+                .with("sampled", true)
+                .with("trace", vec![0xca, 0xfe]);
+            let b: Vec<u8> = Vec::try_from(&m).unwrap();
+            let parsed: Meta = Meta::try_from(b.as_slice()).unwrap();
+            assert_eq!(parsed, m);
+            assert_eq!(
+                parsed.get("content-type").and_then(Value::as_str),
+                Some("application/json")
+            );
+            assert_eq!(parsed.get("producer").and_then(Value::as_u64), Some(42));
+        }
+
+        #[test]
+        fn test_empty() {
+            let b: Vec<u8> = Vec::try_from(&Meta::new()).unwrap();
+            assert!(b.is_empty());
+            assert_eq!(Meta::try_from(b.as_slice()), Ok(Meta::new()));
+        }
+
+        #[test]
+        fn test_truncated() {
+            let m: Meta = Meta::new().with("k", "v");
+            let b: Vec<u8> = Vec::try_from(&m).unwrap();
+            assert!(Meta::try_from(&b[..b.len() - 1]).is_err());
+        }
+
+        #[test]
+        fn test_invalid_tag() {
+            let b: &[u8] = b"\x00\x01k\xff\x00\x00\x00\x00";
+            assert!(Meta::try_from(b).is_err());
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use crate::evt::Event;
+use crate::item::Name;
+
+/// Fixed-width hex naming scheme for slots(0..capacity).
+///
+/// Names are zero padded lowercase hex strings; lexical order matches numeric order.
+///
+/// * width=2, slot=0x42 -> 42
+/// * width=4, slot=0x42 -> 0042
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HexNames {
+    width: usize,
+    capacity: u64,
+}
+
+// 16 digits are not supported: 16^16 slots do not fit the u64 capacity.
+fn width2max(width: usize) -> Option<u64> {
+    match width {
+        1..=15 => Some(1 << (4 * width)),
+        _ => None,
+    }
+}
+
+impl HexNames {
+    /// Creates new naming scheme(`Event::BadRequest` if the capacity does not fit the width).
+    ///
+    /// # Arguments
+    /// - width: Number of hex digits(1..=15).
+    /// - capacity: Number of slots(1..=16^width).
+    pub fn new(width: usize, capacity: u64) -> Result<Self, Event> {
+        let max: u64 = width2max(width).ok_or(Event::BadRequest)?;
+        (0 < capacity && capacity <= max)
+            .then_some(Self { width, capacity })
+            .ok_or(Event::BadRequest)
+    }
+
+    /// 256 slots: 00 ... ff
+    pub fn u8() -> Self {
+        Self {
+            width: 2,
+            capacity: 256,
+        }
+    }
+
+    /// 65536 slots: 0000 ... ffff
+    pub fn u16() -> Self {
+        Self {
+            width: 4,
+            capacity: 65536,
+        }
+    }
+
+    /// 2^32 slots: 00000000 ... ffffffff
+    pub fn u32() -> Self {
+        Self {
+            width: 8,
+            capacity: 1 << 32,
+        }
+    }
+
+    /// Creates new naming scheme which uses same width and the capacity.
+    pub fn with_capacity(&self, capacity: u64) -> Result<Self, Event> {
+        Self::new(self.width, capacity)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    /// Converts a slot number to `Name`(the slot number wraps around the capacity).
+    pub fn slot2name(&self, slot: u64) -> Name {
+        let s: u64 = slot % self.capacity;
+        Name::from(format!("{:0w$x}", s, w = self.width))
+    }
+
+    /// Converts `Name` to a slot number(`Event::BadRequest` if the name is not in this scheme).
+    pub fn name2slot(&self, n: &Name) -> Result<u64, Event> {
+        let s: &str = n.as_str();
+        let valid: bool = s.len() == self.width
+            && s.bytes()
+                .all(|b: u8| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+        valid
+            .then(|| u64::from_str_radix(s, 16).ok())
+            .flatten()
+            .filter(|slot: &u64| *slot < self.capacity)
+            .ok_or(Event::BadRequest)
+    }
+
+    /// Checks if `Name` is in this scheme.
+    pub fn contains(&self, n: &Name) -> bool {
+        self.name2slot(n).is_ok()
+    }
+
+    /// Gets the next name(wraps around the capacity).
+    pub fn next(&self, prev: &Name) -> Result<Name, Event> {
+        self.name2slot(prev)
+            .map(|slot: u64| self.slot2name(slot.wrapping_add(1) % self.capacity))
+    }
+
+    /// Creates new iterator which generates all names in numeric order.
+    pub fn names(&self) -> impl Iterator<Item = Name> {
+        let names: HexNames = *self;
+        (0..self.capacity).map(move |slot: u64| names.slot2name(slot))
+    }
+}
+
+#[cfg(test)]
+mod test_naming {
+
+    mod hex_names {
+        use crate::evt::Event;
+        use crate::item::Name;
+        use crate::naming::HexNames;
+
+        #[test]
+        fn test_invalid_capacity() {
+            assert_eq!(HexNames::new(2, 257), Err(Event::BadRequest));
+            assert_eq!(HexNames::new(2, 0), Err(Event::BadRequest));
+            assert_eq!(HexNames::new(16, 1), Err(Event::BadRequest));
+            assert_eq!(HexNames::new(0, 1), Err(Event::BadRequest));
+            assert_eq!(
+                HexNames::new(15, 1 << 60).map(|h| h.capacity()),
+                Ok(1 << 60)
+            );
+        }
+
+        #[test]
+        fn test_u8() {
+            let h: HexNames = HexNames::u8();
+            assert_eq!(h.slot2name(0x42), Name::from("42"));
+            assert_eq!(h.name2slot(&Name::from("ff")), Ok(255));
+            assert_eq!(h, HexNames::new(2, 256).unwrap());
+        }
+
+        #[test]
+        fn test_u16() {
+            let h: HexNames = HexNames::u16();
+            assert_eq!(h.slot2name(0x42), Name::from("0042"));
+            assert_eq!(h.name2slot(&Name::from("0042")), Ok(0x42));
+            assert_eq!(h.name2slot(&Name::from("42")), Err(Event::BadRequest));
+        }
+
+        #[test]
+        fn test_u32() {
+            let h: HexNames = HexNames::u32();
+            assert_eq!(h.slot2name(0xcafef00d), Name::from("cafef00d"));
+            assert_eq!(h.capacity(), 1 << 32);
+        }
+
+        #[test]
+        fn test_capacity() {
+            let h: HexNames = HexNames::u16().with_capacity(1000).unwrap();
+            assert_eq!(h.slot2name(1000), Name::from("0000"));
+            assert_eq!(h.name2slot(&Name::from("03e7")), Ok(999));
+            assert_eq!(h.name2slot(&Name::from("03e8")), Err(Event::BadRequest));
+            assert_eq!(h.next(&Name::from("03e7")), Ok(Name::from("0000")));
+        }
+
+        #[test]
+        fn test_reject_foreign() {
+            let h: HexNames = HexNames::u8();
+            assert!(!h.contains(&Name::from("FF")));
+            assert!(!h.contains(&Name::from("+f")));
+            assert!(!h.contains(&Name::from(".cursor")));
+            assert!(!h.contains(&Name::from("../42")));
+        }
+
+        #[test]
+        fn test_names() {
+            let h: HexNames = HexNames::u16().with_capacity(300).unwrap();
+            let v: Vec<Name> = h.names().collect();
+            assert_eq!(v.len(), 300);
+            let mut sorted: Vec<Name> = v.clone();
+            sorted.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+            assert_eq!(v, sorted);
+        }
+    }
+}
@@ -4,6 +4,7 @@ use std::path::Path;
 
 use crate::evt::Event;
 use crate::item::Name;
+use crate::naming::HexNames;
 
 fn get_next_u8(prev: u8) -> u8 {
     prev.checked_add(1).unwrap_or(0)
@@ -57,6 +58,58 @@ pub fn next_random_u8_new_from_path_default() -> Result<impl FnMut() -> Result<N
     next_random_u8_new_from_path("/dev/urandom")
 }
 
+/// Creates new name iterator which uses sequential u16 numbers.
+pub fn next_u16_iter_new(init: u16) -> impl Iterator<Item = Name> {
+    next_hex_iter_new(HexNames::u16(), init.into())
+}
+
+/// Creates new name iterator which uses sequential slot numbers of the naming scheme.
+pub fn next_hex_iter_new(names: HexNames, init: u64) -> impl Iterator<Item = Name> {
+    let cap: u64 = names.capacity();
+    (0..cap).map(move |i: u64| names.slot2name(init.wrapping_add(i) % cap))
+}
+
+/// Creates new next generator which uses random number source to create `Name`.
+///
+/// Random numbers will be wrapped around the capacity of the naming scheme.
+pub fn next_random_hex_new<R>(
+    names: HexNames,
+    mut random_source: R,
+) -> impl FnMut() -> Result<Name, Event>
+where
+    R: FnMut() -> Result<u64, Event>,
+{
+    move || random_source().map(|u: u64| names.slot2name(u))
+}
+
+/// Creates new next generator which uses `Read` to get next random bytes.
+pub fn next_random_hex_new_from_read<R>(
+    names: HexNames,
+    mut r: R,
+) -> impl FnMut() -> Result<Name, Event>
+where
+    R: Read,
+{
+    let mut buf: [u8; 8] = [0; 8];
+    let rs = move || {
+        r.read_exact(&mut buf)
+            .map(|_| u64::from_be_bytes(buf))
+            .map_err(|e| {
+                Event::UnexpectedError(format!("Unable to read next u64 from read: {}", e))
+            })
+    };
+    next_random_hex_new(names, rs)
+}
+
+/// Creates new next generator which tries to use /dev/urandom as random bytes source.
+pub fn next_random_hex_new_from_path_default(
+    names: HexNames,
+) -> Result<impl FnMut() -> Result<Name, Event>, Event> {
+    let f: File = File::open("/dev/urandom")
+        .map_err(|e| Event::UnexpectedError(format!("Unable to open random source file: {}", e)))?;
+    Ok(next_random_hex_new_from_read(names, f))
+}
+
 #[cfg(test)]
 mod test_u {
 
@@ -93,6 +146,52 @@ mod test_u {
         }
     }
 
+    mod next_u16_iter_new {
+        use crate::item::Name;
+        use crate::next;
+
+        #[test]
+        fn test_65536() {
+            let i = next::u::next_u16_iter_new(0x43);
+            let v: Vec<Name> = i.collect();
+            assert_eq!(v.len(), 65536);
+            assert_eq!(v[0], Name::from("0043"));
+            assert_eq!(v[65535], Name::from("0042"));
+        }
+    }
+
+    mod next_hex_iter_new {
+        use crate::item::Name;
+        use crate::naming::HexNames;
+        use crate::next;
+
+        #[test]
+        fn test_capacity() {
+            let names = HexNames::u16().with_capacity(3).unwrap();
+            let v: Vec<Name> = next::u::next_hex_iter_new(names, 2).collect();
+            assert_eq!(
+                v,
+                vec![Name::from("0002"), Name::from("0000"), Name::from("0001")]
+            );
+        }
+    }
+
+    mod next_random_hex_new_from_read {
+        use crate::item::Name;
+        use crate::naming::HexNames;
+        use crate::next;
+
+        #[test]
+        fn test_number() {
+            let names = HexNames::u32().with_capacity(1000).unwrap();
+            let rs: &[u8] = &[0, 0, 0, 0, 0, 0, 0x03, 0xe9, 0, 0, 0, 0, 0, 0, 0, 0x42];
+            let mut f = next::u::next_random_hex_new_from_read(names, rs);
+            assert_eq!(f(), Ok(Name::from("00000001")));
+            assert_eq!(f(), Ok(Name::from("00000042")));
+            assert!(f().is_err());
+        }
+    }
+
     mod next_u8_iter_new {
         use crate::item::Name;
         use crate::next;
@@ -0,0 +1,278 @@
+use std::fs::File;
+use std::io::{ErrorKind, Read};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::evt::Event;
+use crate::header::{self, Header};
+use crate::item::Name;
+use crate::request::Request;
+use crate::RingBuffer;
+
+/// A list of orders to list names(see `Request::ListPage`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Order {
+    /// Slot number order(same as `Request::List`).
+    #[default]
+    Slot,
+
+    /// Oldest first(creation time in the item header; modified time for headerless items).
+    Created,
+
+    /// Push order from the head(sequential buffer only).
+    Sequence,
+}
+
+/// A page of ordered names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Page {
+    pub order: Order,
+
+    /// Number of names to skip.
+    pub offset: usize,
+
+    /// Max number of names(`None`: unlimited).
+    pub limit: Option<usize>,
+}
+
+impl Page {
+    /// Creates new page which contains all names in the order.
+    pub fn new(order: Order) -> Self {
+        Self {
+            order,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_offset(self, offset: usize) -> Self {
+        Self { offset, ..self }
+    }
+
+    pub fn with_limit(self, limit: usize) -> Self {
+        Self {
+            limit: Some(limit),
+            ..self
+        }
+    }
+
+    /// Gets names of this page from the ordered names.
+    pub fn apply<I>(&self, ordered: I) -> Vec<Name>
+    where
+        I: Iterator<Item = Name>,
+    {
+        ordered
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+}
+
+/// Gets the creation time of the item file(`None` if missing).
+pub(crate) fn path2created(p: PathBuf) -> Result<Option<SystemTime>, ErrorKind> {
+    let mut f: File = match File::open(&p) {
+        Ok(f) => f,
+        // removed after the list
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.kind()),
+    };
+    let mut buf: Vec<u8> = Vec::with_capacity(header::HEADER_SIZE_MAX);
+    Read::by_ref(&mut f)
+        .take(header::HEADER_SIZE_MAX as u64)
+        .read_to_end(&mut buf)
+        .map_err(|e| e.kind())?;
+    match Header::try_from(buf.as_slice()) {
+        Ok(h) => Ok(Some(h.created())),
+        Err(_) => f
+            .metadata()
+            .and_then(|m| m.modified())
+            .map(Some)
+            .map_err(|e| e.kind()),
+    }
+}
+
+/// Creates new creation time getter which uses a closure to build path from `Name`.
+///
+/// Only the header(with extensions) will be read; the modified time will be used for headerless
+/// items.
+/// A missing file will be reported as `None`.
+pub fn created_getter_new<B>(path_builder: B) -> impl Fn(&Name) -> Result<Option<SystemTime>, Event>
+where
+    B: Fn(Name) -> PathBuf,
+{
+    move |n: &Name| {
+        path2created(path_builder(n.clone()))
+            .map_err(|e| Event::UnexpectedError(format!("Unable to get creation time: {}", e)))
+    }
+}
+
+/// Ring buffer wrapper which lists names ordered by creation time(`Request::ListPage`).
+///
+/// `Order::Sequence` will be rejected(`Event::BadRequest`); see `seq::SeqRingBuffer`.
+pub struct OrderedRingBuffer<R, C> {
+    pub inner: R,
+
+    /// Gets the creation time of a named item(`None` if removed).
+    pub created: C,
+}
+
+impl<R, C> OrderedRingBuffer<R, C>
+where
+    R: RingBuffer,
+    C: Fn(&Name) -> Result<Option<SystemTime>, Event>,
+{
+    fn list(&mut self) -> Result<Vec<Name>, Event> {
+        match self.inner.handle(Request::List) {
+            Event::NamesGot(names) => Ok(names),
+            e => Err(e),
+        }
+    }
+
+    fn list_page(&mut self, page: Page) -> Result<Vec<Name>, Event> {
+        match page.order {
+            Order::Slot => self.list().map(|names| page.apply(names.into_iter())),
+            Order::Created => {
+                let mut timed: Vec<(SystemTime, Name)> = vec![];
+                for n in self.list()? {
+                    if let Some(t) = (self.created)(&n)? {
+                        timed.push((t, n));
+                    }
+                }
+                // names are in slot order; ties keep it
+                timed.sort_by_key(|pair| pair.0);
+                Ok(page.apply(timed.into_iter().map(|pair| pair.1)))
+            }
+            Order::Sequence => Err(Event::BadRequest),
+        }
+    }
+}
+
+impl<R, C> RingBuffer for OrderedRingBuffer<R, C>
+where
+    R: RingBuffer,
+    C: Fn(&Name) -> Result<Option<SystemTime>, Event>,
+{
+    fn handle(&mut self, req: Request) -> Event {
+        match req {
+            Request::ListPage(page) => self
+                .list_page(page)
+                .map(Event::NamesGot)
+                .unwrap_or_else(|e| e),
+            q => self.inner.handle(q),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_order {
+
+    mod ordered_ring_buffer {
+        use std::time::{Duration, SystemTime};
+
+        use crate::evt::Event;
+        use crate::item::Name;
+        use crate::order::{Order, OrderedRingBuffer, Page};
+        use crate::request::Request;
+        use crate::RingBuffer;
+
+        struct Names {}
+        impl RingBuffer for Names {
+            fn handle(&mut self, req: Request) -> Event {
+                match req {
+                    Request::List => Event::NamesGot(
+                        ["00", "01", "02", "03"]
+                            .into_iter()
+                            .map(Name::from)
+                            .collect(),
+                    ),
+                    _ => Event::BadRequest,
+                }
+            }
+        }
+
+        fn created(n: &Name) -> Result<Option<SystemTime>, Event> {
+            let secs: Option<u64> = match n.as_str() {
+                "00" => Some(30),
+                "01" => Some(10),
+                "02" => None,
+                _ => Some(20),
+            };
+            Ok(secs.map(|s: u64| SystemTime::UNIX_EPOCH + Duration::from_secs(s)))
+        }
+
+        #[test]
+        fn test_created() {
+            let mut rb = OrderedRingBuffer {
+                inner: Names {},
+                created,
+            };
+            let page = Page::new(Order::Created);
+            let expected = ["01", "03", "00"].into_iter().map(Name::from).collect();
+            assert_eq!(
+                rb.handle(Request::ListPage(page)),
+                Event::NamesGot(expected)
+            );
+
+            let page = Page::new(Order::Created).with_offset(1).with_limit(1);
+            let expected = vec![Name::from("03")];
+            assert_eq!(
+                rb.handle(Request::ListPage(page)),
+                Event::NamesGot(expected)
+            );
+        }
+
+        #[test]
+        fn test_slot() {
+            let mut rb = OrderedRingBuffer {
+                inner: Names {},
+                created,
+            };
+            let page = Page::new(Order::Slot).with_offset(3).with_limit(2);
+            let expected = vec![Name::from("03")];
+            assert_eq!(
+                rb.handle(Request::ListPage(page)),
+                Event::NamesGot(expected)
+            );
+
+            let page = Page::new(Order::Sequence);
+            assert_eq!(rb.handle(Request::ListPage(page)), Event::BadRequest);
+        }
+    }
+
+    mod created_getter_new {
+        use std::path::Path;
+        use std::time::{Duration, SystemTime};
+
+        use crate::checksum::Crc32c;
+        use crate::clock::ManualClock;
+        use crate::full::Layout;
+        use crate::header::ItemFormat;
+        use crate::item::{Item, Name, NamedItem};
+        use crate::meta::Meta;
+        use crate::order;
+        use crate::write::{self, WriteMode};
+
+        #[test]
+        #[ignore]
+        fn test_extensions() {
+            let dirname = Path::new("./test.d/order/created_getter_new/extensions.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let created = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+            let w = write::writer_unchecked_new_layout_with_format_clock(
+                Layout::flat(dirname),
+                ItemFormat::new(Crc32c),
+                WriteMode::InPlace,
+                ManualClock::new(created),
+            );
+            let item = Item::from(b"item".as_slice())
+                .with_deadline(created + Duration::from_secs(60))
+                .with_meta(Meta::new().with("k", "v"));
+            w(NamedItem::new(item, Name::from("00"))).unwrap();
+
+            let get = order::created_getter_new(move |n: Name| dirname.join(n.as_str()));
+            assert_eq!(get(&Name::from("00")), Ok(Some(created)));
+            assert_eq!(get(&Name::from("01")), Ok(None));
+        }
+    }
+}
@@ -2,14 +2,30 @@ use std::path::Path;
 
 use crate::evt::Event;
 use crate::item::{Item, Name, NamedItem};
-use crate::write;
+use crate::write::{self, WriteMode};
+
+/// A list of strategies to push an item into a full buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FullPolicy {
+    /// Rejects the pushed item(`Event::TooManyItemsAlready`).
+    #[default]
+    Reject,
+
+    /// Overwrites the oldest item(`Event::ItemOverwrote`).
+    OverwriteOldest,
+
+    /// Drops the pushed item(`Event::ItemDropped`).
+    DropIncoming,
+}
 
 /// Creates new pusher which uses closures to get/set name and write `NamedItem`.
+///
+/// Returns the `Name` of the written item.
 pub fn push_new<G, S, W>(
     mut get_name: G,
     mut set_next: S,
     wtr: W,
-) -> impl FnMut(Item) -> Result<(), Event>
+) -> impl FnMut(Item) -> Result<Name, Event>
 where
     G: FnMut() -> Result<Name, Event>,
     S: FnMut(Name) -> Result<(), Event>,
@@ -20,15 +36,15 @@ where
         let named: NamedItem = NamedItem::new(item, name);
         match wtr(named) {
             Ok(prev) => {
-                set_next(prev)?;
-                Ok(())
+                set_next(prev.clone())?;
+                Ok(prev)
             }
             Err(evt) => Err(evt),
         }
     }
 }
 
-fn push_new_unmanaged<G, W>(mut get_name: G, wtr: W) -> impl FnMut(Item) -> Result<(), Event>
+fn push_new_unmanaged<G, W>(mut get_name: G, wtr: W) -> impl FnMut(Item) -> Result<Name, Event>
 where
     G: FnMut() -> Result<Name, Event>,
     W: Fn(NamedItem) -> Result<Name, Event>,
@@ -36,19 +52,108 @@ where
     move |item: Item| {
         let name: Name = get_name()?;
         let named: NamedItem = NamedItem::new(item, name);
-        wtr(named)?;
-        Ok(())
+        wtr(named)
     }
 }
 
 /// Creates new unmanaged push handler which uses closures to get `Name` and write `NamedItem`.
+///
+/// The `Name` of the written item will be returned as `Event::ItemWrote`.
 pub fn push_handler_new_unmanaged<G, W>(get_name: G, wtr: W) -> impl FnMut(Item) -> Event
 where
     G: FnMut() -> Result<Name, Event>,
     W: Fn(NamedItem) -> Result<Name, Event>,
 {
     let mut f = push_new_unmanaged(get_name, wtr);
-    move |i: Item| f(i).map(|_| Event::Success).unwrap_or_else(|e| e)
+    move |i: Item| f(i).map(Event::ItemWrote).unwrap_or_else(|e| e)
+}
+
+/// Default number of names to try(`push_handler_new_unmanaged_retry`).
+pub const RETRY_LIMIT: usize = 16;
+
+/// Creates new unmanaged push handler which retries with next names on `Event::Again`.
+///
+/// `Event::Again` will be returned if all names were used or claimed by other writers.
+///
+/// # Arguments
+/// - get_name: Gets next name.
+/// - wtr:      Writes `NamedItem`(`Event::Again` if the slot is used).
+/// - limit:    Number of names to try.
+pub fn push_handler_new_unmanaged_retry<G, W>(
+    mut get_name: G,
+    wtr: W,
+    limit: usize,
+) -> impl FnMut(Item) -> Event
+where
+    G: FnMut() -> Result<Name, Event>,
+    W: Fn(NamedItem) -> Result<Name, Event>,
+{
+    move |item: Item| {
+        let mut last: Event = Event::Again;
+        for _ in 0..limit {
+            let name: Name = match get_name() {
+                Ok(name) => name,
+                Err(e) => return e,
+            };
+            match wtr(NamedItem::new(item.clone(), name)) {
+                Ok(name) => return Event::ItemWrote(name),
+                Err(Event::Again) => last = Event::Again,
+                Err(e) => return e,
+            }
+        }
+        last
+    }
+}
+
+fn push_full<O>(item: Item, policy: FullPolicy, overwrite: &mut O) -> Event
+where
+    O: FnMut(Item) -> Result<Name, Event>,
+{
+    match policy {
+        FullPolicy::Reject => Event::TooManyItemsAlready,
+        FullPolicy::DropIncoming => Event::ItemDropped,
+        FullPolicy::OverwriteOldest => overwrite(item)
+            .map(Event::ItemOverwrote)
+            .unwrap_or_else(|e| e),
+    }
+}
+
+/// Creates new push handler which applies `FullPolicy` if the buffer is full.
+///
+/// `FullPolicy::Reject` pushes items without full check; the buffer will be checked only if
+/// no slot was available(`Event::Again`) to tell a full buffer from busy slots.
+///
+/// # Arguments
+/// - push:      Pushes an item.
+/// - is_full:   Checks if the buffer is full.
+/// - overwrite: Overwrites the oldest item and returns its `Name`.
+/// - policy:    Strategy to push an item into a full buffer.
+pub fn push_handler_new_with_policy<P, F, O>(
+    mut push: P,
+    mut is_full: F,
+    mut overwrite: O,
+    policy: FullPolicy,
+) -> impl FnMut(Item) -> Event
+where
+    P: FnMut(Item) -> Event,
+    F: FnMut() -> Result<bool, Event>,
+    O: FnMut(Item) -> Result<Name, Event>,
+{
+    move |item: Item| match policy {
+        FullPolicy::Reject => match push(item) {
+            Event::Again => match is_full() {
+                Ok(true) => Event::TooManyItemsAlready,
+                Ok(false) => Event::Again,
+                Err(e) => e,
+            },
+            evt => evt,
+        },
+        _ => match is_full() {
+            Ok(true) => push_full(item, policy, &mut overwrite),
+            Ok(false) => push(item),
+            Err(e) => e,
+        },
+    }
 }
 
 /// Creates new checked unmanaged push handler which uses default writer to write `NamedItem`.
@@ -57,20 +162,46 @@ where
 /// - get_name: Gets next name.
 /// - dirname:  Path to store buffer files.
 /// - checksum:     Computes checksum.
-pub fn push_handler_new_unmanaged_default_with_checksum<G, P, C>(
+/// - mode:         Strategy to write an item file.
+pub fn push_handler_new_unmanaged_default_with_checksum_mode<G, P, C>(
     get_name: G,
     dirname: P,
     checksum: C,
+    mode: WriteMode,
 ) -> impl FnMut(Item) -> Event
 where
     G: FnMut() -> Result<Name, Event>,
     P: AsRef<Path>,
     C: Fn(&[u8]) -> Vec<u8>,
 {
-    let wtr = write::writer_checked_new_default_with_checksum(dirname, checksum);
+    let wtr = write::writer_checked_new_default_with_checksum_mode(dirname, checksum, mode);
     push_handler_new_unmanaged(get_name, wtr)
 }
 
+/// Creates new checked unmanaged push handler which uses default writer to write `NamedItem`.
+///
+/// # Arguments
+/// - get_name: Gets next name.
+/// - dirname:  Path to store buffer files.
+/// - checksum:     Computes checksum.
+pub fn push_handler_new_unmanaged_default_with_checksum<G, P, C>(
+    get_name: G,
+    dirname: P,
+    checksum: C,
+) -> impl FnMut(Item) -> Event
+where
+    G: FnMut() -> Result<Name, Event>,
+    P: AsRef<Path>,
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    push_handler_new_unmanaged_default_with_checksum_mode(
+        get_name,
+        dirname,
+        checksum,
+        WriteMode::InPlace,
+    )
+}
+
 fn checksum_nop(_: &[u8]) -> Vec<u8> {
     vec![]
 }
@@ -106,5 +237,153 @@ mod test_push {
             let r = p(Item::from(vec![]));
             assert_eq!(r, Err(Event::TooManyItemsAlready));
         }
+
+        #[test]
+        fn test_name() {
+            let get_name = || Ok(Name::from("42"));
+            let set_next = |_: Name| Ok(());
+            let wtr = |i: NamedItem| Ok(i.into());
+            let mut p = push::push_new(get_name, set_next, wtr);
+            let r = p(Item::from(vec![]));
+            assert_eq!(r, Ok(Name::from("42")));
+        }
+    }
+
+    mod push_handler_new_with_policy {
+
+        use crate::evt::Event;
+        use crate::item::{Item, Name};
+
+        use crate::push::{self, FullPolicy};
+
+        fn push_new(policy: FullPolicy) -> impl FnMut(Item) -> Event {
+            let push = |_: Item| Event::ItemWrote(Name::from("42"));
+            let is_full = || Ok(true);
+            let overwrite = |_: Item| Ok(Name::from("01"));
+            push::push_handler_new_with_policy(push, is_full, overwrite, policy)
+        }
+
+        #[test]
+        fn test_reject() {
+            let mut p = push_new(FullPolicy::Reject);
+            assert_eq!(p(Item::from(vec![])), Event::ItemWrote(Name::from("42")));
+        }
+
+        #[test]
+        fn test_reject_full() {
+            let push = |_: Item| Event::Again;
+            let overwrite = |_: Item| Ok(Name::from("01"));
+            let mut p = push::push_handler_new_with_policy(
+                push,
+                || Ok(true),
+                overwrite,
+                FullPolicy::Reject,
+            );
+            assert_eq!(p(Item::from(vec![])), Event::TooManyItemsAlready);
+
+            let mut p = push::push_handler_new_with_policy(
+                push,
+                || Ok(false),
+                overwrite,
+                FullPolicy::Reject,
+            );
+            assert_eq!(p(Item::from(vec![])), Event::Again);
+        }
+
+        #[test]
+        fn test_overwrite() {
+            let mut p = push_new(FullPolicy::OverwriteOldest);
+            assert_eq!(
+                p(Item::from(vec![])),
+                Event::ItemOverwrote(Name::from("01"))
+            );
+        }
+
+        #[test]
+        fn test_drop() {
+            let mut p = push_new(FullPolicy::DropIncoming);
+            assert_eq!(p(Item::from(vec![])), Event::ItemDropped);
+        }
+
+        #[test]
+        fn test_not_full() {
+            let push = |_: Item| Event::ItemWrote(Name::from("42"));
+            let is_full = || Ok(false);
+            let overwrite = |_: Item| Ok(Name::from("01"));
+            let mut p = push::push_handler_new_with_policy(
+                push,
+                is_full,
+                overwrite,
+                FullPolicy::OverwriteOldest,
+            );
+            assert_eq!(p(Item::from(vec![])), Event::ItemWrote(Name::from("42")));
+        }
+    }
+
+    mod push_handler_new_unmanaged_retry {
+
+        use std::cell::Cell;
+
+        use crate::evt::Event;
+        use crate::item::{Item, Name, NamedItem};
+
+        use crate::push;
+
+        #[test]
+        fn test_retry() {
+            let cnt: Cell<u8> = Cell::new(0);
+            let get_name = || {
+                cnt.set(cnt.get() + 1);
+                Ok(Name::from(cnt.get()))
+            };
+            let wtr = |i: NamedItem| match i.as_name().as_str() {
+                "03" => Ok(i.into()),
+                _ => Err(Event::Again),
+            };
+            let mut p = push::push_handler_new_unmanaged_retry(get_name, wtr, 16);
+            assert_eq!(p(Item::from(vec![])), Event::ItemWrote(Name::from("03")));
+        }
+
+        #[test]
+        fn test_exhausted() {
+            let get_name = || Ok(Name::from("42"));
+            let wtr = |_: NamedItem| Err(Event::Again);
+            let mut p = push::push_handler_new_unmanaged_retry(get_name, wtr, 3);
+            assert_eq!(p(Item::from(vec![])), Event::Again);
+        }
+
+        #[test]
+        fn test_err() {
+            let get_name = || Ok(Name::from("42"));
+            let wtr = |_: NamedItem| Err(Event::TooManyItemsAlready);
+            let mut p = push::push_handler_new_unmanaged_retry(get_name, wtr, 3);
+            assert_eq!(p(Item::from(vec![])), Event::TooManyItemsAlready);
+        }
+    }
+
+    mod push_handler_new_unmanaged {
+
+        use crate::evt::Event;
+        use crate::item::{Item, Name, NamedItem};
+
+        use crate::push;
+
+        #[test]
+        fn test_wrote() {
+            let get_name = || Ok(Name::from("42"));
+            let wtr = |i: NamedItem| Ok(i.into());
+            let mut p = push::push_handler_new_unmanaged(get_name, wtr);
+            let evt: Event = p(Item::from(vec![]));
+            assert_eq!(evt, Event::ItemWrote(Name::from("42")));
+        }
+
+        #[test]
+        fn test_again() {
+            let get_name = || Ok(Name::from("42"));
+            let wtr = |_: NamedItem| Err(Event::Again);
+            let mut p = push::push_handler_new_unmanaged(get_name, wtr);
+            let evt: Event = p(Item::from(vec![]));
+            assert_eq!(evt, Event::Again);
+        }
     }
 }
@@ -0,0 +1,292 @@
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use crate::batch;
+use crate::evt::Event;
+use crate::full;
+use crate::item::{Item, Name};
+use crate::push::FullPolicy;
+use crate::request::Request;
+use crate::RingBuffer;
+
+/// Max bytes an item file may have in addition to its item size.
+///
+/// header with extensions(37) + sealed envelope(29) + checksum(255)
+pub const ITEM_OVERHEAD_MAX: u64 = 37 + 29 + 255;
+
+/// Byte limits of a ring buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Quota {
+    /// Max total bytes of item files(`None`: unlimited).
+    ///
+    /// Pushes will be checked by the item size; the header and the checksum of the last item
+    /// may exceed the quota(up to `ITEM_OVERHEAD_MAX`).
+    pub max_bytes: Option<u64>,
+
+    /// Max item size(payload + metadata bytes; `None`: unlimited).
+    pub max_item: Option<u64>,
+}
+
+impl Quota {
+    /// Creates new quota which limits total bytes of item files.
+    pub fn with_max_bytes(self, max_bytes: u64) -> Self {
+        Self {
+            max_bytes: Some(max_bytes),
+            ..self
+        }
+    }
+
+    /// Creates new quota which limits the size of an item.
+    pub fn with_max_item(self, max_item: u64) -> Self {
+        Self {
+            max_item: Some(max_item),
+            ..self
+        }
+    }
+
+    /// Max byte length of an item file to read.
+    pub fn max_file(&self) -> Option<u64> {
+        self.max_item
+            .map(|m: u64| m.saturating_add(ITEM_OVERHEAD_MAX))
+    }
+}
+
+/// Computes the size of an item(payload + metadata bytes).
+pub fn item_size(item: &Item) -> Result<u64, Event> {
+    let meta: Vec<u8> = Vec::try_from(item.meta())?;
+    Ok((item.as_slice().len() + meta.len()) as u64)
+}
+
+/// Creates new file size getter which uses a closure to build path from `Name`.
+///
+/// A missing file will be reported as `None`.
+pub fn size_getter_new<B>(path_builder: B) -> impl Fn(&Name) -> Result<Option<u64>, Event>
+where
+    B: Fn(Name) -> PathBuf,
+{
+    move |n: &Name| {
+        full::name_check(n)?;
+        match std::fs::metadata(path_builder(n.clone())) {
+            Ok(m) => Ok(Some(m.len())),
+            Err(e) => match e.kind() {
+                ErrorKind::NotFound => Ok(None),
+                _ => Err(Event::UnexpectedError(format!(
+                    "Unable to get file size: {}",
+                    e
+                ))),
+            },
+        }
+    }
+}
+
+/// Ring buffer wrapper which enforces the byte quota on push and the item size limit.
+///
+/// The oldest items will be removed to make room if the policy is `OverwriteOldest`.
+/// Files over the limit will be refused on get(`Event::ItemTooLarge`) without reading them; other
+/// reads must be limited by the inner read handler(see `header::ItemFormat::max_file`).
+/// Items pushed by a batch count against the quota before their files are renamed(atomic
+/// writes).
+pub struct QuotaRingBuffer<R, U, O, S> {
+    pub inner: R,
+
+    pub quota: Quota,
+
+    /// Gets total bytes of item files.
+    pub usage: U,
+
+    /// Finds the oldest item(eviction).
+    pub oldest: O,
+
+    /// Gets the file size of a named item.
+    pub size: S,
+
+    /// Strategy to push an item into a buffer over quota.
+    pub policy: FullPolicy,
+}
+
+impl<R, U, O, S> QuotaRingBuffer<R, U, O, S>
+where
+    R: RingBuffer,
+    U: Fn() -> Result<u64, Event>,
+    O: Fn() -> Result<Name, Event>,
+    S: Fn(&Name) -> Result<Option<u64>, Event>,
+{
+    /// Makes room for the item(`Ok(false)` if the item must be dropped).
+    ///
+    /// The pending bytes(pushed items not visible yet) count as used.
+    fn reserve(&mut self, size: u64, pending: u64, max_bytes: u64) -> Result<bool, Event> {
+        if max_bytes < size {
+            return Err(Event::QuotaExceeded);
+        }
+        let required: u64 = size.saturating_add(pending);
+        while max_bytes < (self.usage)()?.saturating_add(required) {
+            match self.policy {
+                FullPolicy::Reject => return Err(Event::QuotaExceeded),
+                FullPolicy::DropIncoming => return Ok(false),
+                FullPolicy::OverwriteOldest => {
+                    let oldest: Name = (self.oldest)()?;
+                    match self.inner.handle(Request::Del(oldest)) {
+                        Event::Success => {}
+                        e => return Err(e),
+                    }
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Gets bytes of the pushed item not counted by the usage yet(e.g, a deferred rename).
+    fn invisible(&self, pushed: &Event, size: u64) -> u64 {
+        match pushed {
+            Event::ItemWrote(n) | Event::ItemOverwrote(n) => {
+                let visible: u64 = (self.size)(n).ok().flatten().unwrap_or(0);
+                size.saturating_sub(visible)
+            }
+            _ => 0,
+        }
+    }
+
+    /// Pushes the item(the bytes not visible yet will be added to the pending bytes).
+    fn push(&mut self, item: Item, pending: &mut u64) -> Result<Event, Event> {
+        let size: u64 = item_size(&item)?;
+        if self.quota.max_item.is_some_and(|max: u64| max < size) {
+            return Err(Event::ItemTooLarge(size));
+        }
+        let max_bytes: u64 = match self.quota.max_bytes {
+            None => return Ok(self.inner.handle(Request::Push(item))),
+            Some(max_bytes) => max_bytes,
+        };
+        if !self.reserve(size, *pending, max_bytes)? {
+            return Ok(Event::ItemDropped);
+        }
+        let pushed: Event = self.inner.handle(Request::Push(item));
+        *pending = pending.saturating_add(self.invisible(&pushed, size));
+        Ok(pushed)
+    }
+
+    fn push_batch(&mut self, items: Vec<Item>) -> Event {
+        let mut pending: u64 = 0;
+        let results: Vec<Event> = batch::durable(|| {
+            items
+                .into_iter()
+                .map(|item: Item| self.push(item, &mut pending).unwrap_or_else(|e| e))
+                .collect()
+        });
+        Event::Batch(results)
+    }
+
+    fn get(&mut self, name: Name) -> Result<Event, Event> {
+        if let Some(max) = self.quota.max_file() {
+            let size: Option<u64> = (self.size)(&name)?;
+            if let Some(size) = size.filter(|s: &u64| max < *s) {
+                return Err(Event::ItemTooLarge(size));
+            }
+        }
+        Ok(self.inner.handle(Request::Get(name)))
+    }
+}
+
+impl<R, U, O, S> RingBuffer for QuotaRingBuffer<R, U, O, S>
+where
+    R: RingBuffer,
+    U: Fn() -> Result<u64, Event>,
+    O: Fn() -> Result<Name, Event>,
+    S: Fn(&Name) -> Result<Option<u64>, Event>,
+{
+    fn handle(&mut self, req: Request) -> Event {
+        match req {
+            Request::Push(item) => self.push(item, &mut 0).unwrap_or_else(|e| e),
+            Request::Get(name) => self.get(name).unwrap_or_else(|e| e),
+            Request::PushBatch(items) => self.push_batch(items),
+            Request::GetMany(names) => {
+                batch::handle_many(self, names.into_iter().map(Request::Get).collect())
+            }
+            q => self.inner.handle(q),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_quota {
+
+    mod quota_ring_buffer {
+        use std::time::SystemTime;
+
+        use crate::evt::Event;
+        use crate::item::{Item, Name};
+        use crate::mem::{MemSlots, RawSlots};
+        use crate::naming::HexNames;
+        use crate::push::FullPolicy;
+        use crate::quota::{Quota, QuotaRingBuffer, ITEM_OVERHEAD_MAX};
+        use crate::request::Request;
+        use crate::RingBuffer;
+
+        fn quota_new(slots: &MemSlots, quota: Quota, policy: FullPolicy) -> impl RingBuffer {
+            let (u, o, s) = (slots.clone(), slots.clone(), slots.clone());
+            QuotaRingBuffer {
+                inner: RawSlots {
+                    slots: slots.clone(),
+                },
+                quota,
+                usage: move || Ok(u.used(&HexNames::u8()).iter().map(|t| t.1).sum()),
+                oldest: move || {
+                    o.used(&HexNames::u8())
+                        .into_iter()
+                        .map(|t| t.0)
+                        .next()
+                        .ok_or(Event::BadRequest)
+                },
+                size: move |n: &Name| {
+                    let raw: Option<Vec<u8>> = s.raw(n).filter(|v| !v.is_empty());
+                    Ok(raw.map(|v| v.len() as u64))
+                },
+                policy,
+            }
+        }
+
+        #[test]
+        fn test_item_too_large() {
+            let slots = MemSlots::default();
+            let mut rb = quota_new(
+                &slots,
+                Quota::default().with_max_item(4),
+                FullPolicy::Reject,
+            );
+            let push = |rb: &mut dyn RingBuffer, b: &[u8]| rb.handle(Request::Push(Item::from(b)));
+            assert_eq!(push(&mut rb, b"12345"), Event::ItemTooLarge(5));
+            assert!(matches!(push(&mut rb, b"1234"), Event::ItemWrote(_)));
+
+            let big: Vec<u8> = vec![0; 5 + ITEM_OVERHEAD_MAX as usize];
+            slots.set_raw(&Name::from("ff"), big.clone(), SystemTime::now());
+            assert_eq!(
+                rb.handle(Request::Get(Name::from("ff"))),
+                Event::ItemTooLarge(big.len() as u64)
+            );
+        }
+
+        #[test]
+        fn test_policies() {
+            let slots = MemSlots::default();
+            let quota = Quota::default().with_max_bytes(8);
+            let mut rb = quota_new(&slots, quota, FullPolicy::Reject);
+            let mut push = |b: &[u8]| rb.handle(Request::Push(Item::from(b)));
+            assert!(matches!(push(b"1234"), Event::ItemWrote(_)));
+            assert!(matches!(push(b"5678"), Event::ItemWrote(_)));
+            assert_eq!(push(b"9"), Event::QuotaExceeded);
+            assert_eq!(push(b"123456789"), Event::QuotaExceeded);
+
+            let mut rb = quota_new(&slots, quota, FullPolicy::DropIncoming);
+            assert_eq!(
+                rb.handle(Request::Push(Item::from(b"9".as_slice()))),
+                Event::ItemDropped
+            );
+
+            let mut rb = quota_new(&slots, quota, FullPolicy::OverwriteOldest);
+            let pushed = rb.handle(Request::Push(Item::from(b"abcde".as_slice())));
+            assert!(matches!(pushed, Event::ItemWrote(_)));
+            let used = slots.used(&HexNames::u8());
+            let kept: Vec<Vec<u8>> = used.iter().flat_map(|t| slots.raw(&t.0)).collect();
+            assert_eq!(kept, vec![b"abcde".to_vec()]);
+        }
+    }
+}
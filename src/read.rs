@@ -2,9 +2,15 @@ use std::fs::File;
 use std::io::{BufReader, ErrorKind, Read};
 use std::path::{Path, PathBuf};
 
+use crate::checksum::Checksum;
+use crate::clock::Clock;
+use crate::codec::Codec;
+use crate::envelope::{self, Envelope};
 use crate::evt::Event;
 use crate::full;
+use crate::header::{self, Compat, Header, ItemFormat};
 use crate::item::{Item, Name, NamedItem};
+use crate::ttl::Ttl;
 
 fn kind2event(n: Name, k: ErrorKind) -> Event {
     match k {
@@ -30,7 +36,7 @@ fn kind2event(n: Name, k: ErrorKind) -> Event {
     }
 }
 
-fn err2event(n: Name, e: std::io::Error, io_error_num: i32) -> Event {
+pub(crate) fn err2event(n: Name, e: std::io::Error, io_error_num: i32) -> Event {
     e.raw_os_error()
         .and_then(|raw_err_num: i32| {
             raw_err_num
@@ -60,7 +66,7 @@ fn data2checked(
         .ok_or(Event::Broken(n))
 }
 
-fn raw2item_with_checksum<C>(
+pub(crate) fn raw2item_with_checksum<C>(
     n: Name,
     mut raw: Vec<u8>,
     checksize: usize,
@@ -78,21 +84,29 @@ where
     data2checked(n, raw, chk, computed)
 }
 
-fn read2item_with_checksum<R, C>(
-    n: Name,
-    r: R,
-    checksize: usize,
-    checksum: &C,
-    io_error_num: i32,
-) -> Result<Item, Event>
+/// Reads the file up to the max byte length(`Event::ItemTooLarge` if longer).
+fn file2raw(n: Name, f: File, max_file: Option<u64>, io_err_num: i32) -> Result<Vec<u8>, Event> {
+    let limit: u64 = max_file
+        .map(|m: u64| m.saturating_add(1))
+        .unwrap_or(u64::MAX);
+    let mut buf: Vec<u8> = Vec::new();
+    read2buf((&f).take(limit), &mut buf).map_err(|e| err2event(n, e, io_err_num))?;
+    let read: u64 = buf.len() as u64;
+    match max_file.filter(|max: &u64| *max < read) {
+        None => Ok(buf),
+        Some(_) => Err(Event::ItemTooLarge(
+            f.metadata().map(|m| m.len()).unwrap_or(read),
+        )),
+    }
+}
+
+fn path2raw<P>(n: Name, p: P, max_file: Option<u64>, io_err_num: i32) -> Result<Vec<u8>, Event>
 where
-    R: Read,
-    C: Fn(&[u8]) -> Vec<u8>,
+    P: AsRef<Path>,
 {
-    let mut buf: Vec<u8> = Vec::new();
-    read2buf(r, &mut buf)
-        .map_err(|e| err2event(n.clone(), e, io_error_num))
-        .and_then(|_| raw2item_with_checksum(n, buf, checksize, checksum))
+    File::open(p)
+        .map_err(|e| err2event(n.clone(), e, io_err_num))
+        .and_then(|f: File| file2raw(n, f, max_file, io_err_num))
 }
 
 fn path2item_with_checksum<P, C>(
@@ -100,42 +114,68 @@ fn path2item_with_checksum<P, C>(
     p: P,
     checksize: usize,
     checksum: &C,
+    max_file: Option<u64>,
     io_err_num: i32,
 ) -> Result<Item, Event>
 where
     P: AsRef<Path>,
     C: Fn(&[u8]) -> Vec<u8>,
 {
-    File::open(p)
-        .map_err(|e| err2event(n.clone(), e, io_err_num))
-        .and_then(|f: File| read2item_with_checksum(n, f, checksize, checksum, io_err_num))
+    path2raw(n.clone(), p, max_file, io_err_num)
+        .and_then(|raw: Vec<u8>| raw2item_with_checksum(n, raw, checksize, checksum))
 }
 
-/// Creates default checked read handler which uses default path builder.
+/// Creates checked read handler which refuses files over the max byte length.
+///
+/// Files longer than the max will be reported as `Event::ItemTooLarge`(at most max + 1 bytes
+/// will be read).
 ///
 /// # Arguments
 /// - path_builder: Builds a path for a named item.
 /// - checksize: Checksum byte length.
 /// - checksum:  Computes checksum.
-pub fn read_handler_new_with_checksum<B, C>(
+/// - max_file:  Max byte length of an item file(`None`: unlimited; see `quota::Quota::max_file`).
+pub fn read_handler_new_with_checksum_max<B, C>(
     path_builder: B,
     checksize: usize,
     checksum: C,
+    max_file: Option<u64>,
 ) -> impl Fn(Name) -> Event
 where
     B: Fn(Name) -> PathBuf,
     C: Fn(&[u8]) -> Vec<u8>,
 {
     move |n: Name| {
+        if let Err(e) = full::name_check(&n) {
+            return e;
+        }
         let p: PathBuf = path_builder(n.clone());
         // libc::EIO = 5(linux, windows, macos)
-        match path2item_with_checksum(n.clone(), p, checksize, &checksum, 5) {
+        match path2item_with_checksum(n.clone(), p, checksize, &checksum, max_file, 5) {
             Ok(item) => Event::ItemGot(NamedItem::new(item, n)),
             Err(e) => e,
         }
     }
 }
 
+/// Creates default checked read handler which uses default path builder.
+///
+/// # Arguments
+/// - path_builder: Builds a path for a named item.
+/// - checksize: Checksum byte length.
+/// - checksum:  Computes checksum.
+pub fn read_handler_new_with_checksum<B, C>(
+    path_builder: B,
+    checksize: usize,
+    checksum: C,
+) -> impl Fn(Name) -> Event
+where
+    B: Fn(Name) -> PathBuf,
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    read_handler_new_with_checksum_max(path_builder, checksize, checksum, None)
+}
+
 /// Creates default checked read handler which uses default path builder.
 ///
 /// # Arguments
@@ -155,6 +195,171 @@ where
     read_handler_new_with_checksum(path_builder, checksize, checksum)
 }
 
+/// Creates new read handler which opens payloads sealed by `write::writer_sealed_new`.
+///
+/// The name will be authenticated with the payload; the deadline and the metadata(if any) will be
+/// kept.
+/// A payload sealed by an unknown key will be reported as `Event::UnsupportedFormat`; an
+/// authentication failure as `Event::Broken`(see `envelope::open_named`).
+///
+/// # Arguments
+/// - get:      Gets a named item(with checksum verified).
+/// - envelope: Opens sealed payloads.
+pub fn read_handler_opened_new<G, E>(get: G, envelope: E) -> impl Fn(Name) -> Event
+where
+    G: Fn(Name) -> Event,
+    E: Envelope,
+{
+    move |n: Name| match get(n) {
+        Event::ItemGot(named) => {
+            let (name, sealed) = named.into_pair();
+            let aad: &[u8] = name.as_str().as_bytes();
+            match envelope::open_named(&envelope, &name, aad, sealed.as_slice()) {
+                Ok(dat) => Event::ItemGot(NamedItem::new(sealed.with_raw(dat), name)),
+                Err(e) => e,
+            }
+        }
+        e => e,
+    }
+}
+
+fn unframe_unexpired<K, D, E, T>(
+    n: Name,
+    framed: Item,
+    format: &ItemFormat<K, D, E>,
+    ttl: &Ttl<T>,
+) -> Result<Item, Event>
+where
+    K: Checksum,
+    D: Codec,
+    E: Envelope,
+    T: Clock,
+{
+    let (h, payload) = header::unframe_compat(n.clone(), framed, format)?;
+    let expired: bool = h.map(|h: Header| ttl.is_expired(&h)).unwrap_or(false);
+    match expired {
+        true => Err(Event::Expired(n)),
+        false => Ok(payload),
+    }
+}
+
+/// Creates checked read handler which uses `ItemFormat` and expiry rules.
+///
+/// The item header will be validated(the checksum size and algorithm come from the checker).
+/// Items of another version, unknown flags, another checksum or an unknown codec will be reported
+/// as `Event::UnsupportedFormat`(kept by `Request::Vacuum`).
+/// Sealed payloads will be opened by the format envelope(`Event::Broken` on authentication failure).
+/// Payloads will be decoded by the codec id in the header(built-in codecs or the format codec).
+/// An expired item will be reported as `Event::Expired`(headerless items never expire).
+/// Files over the max byte length of the format will be reported as `Event::ItemTooLarge`.
+///
+/// # Arguments
+/// - path_builder: Builds a path for a named item.
+/// - format: Checksum, codec, envelope and item formats to accept.
+/// - ttl: Expiry rules.
+pub fn read_handler_new_with_format<B, K, D, E, T>(
+    path_builder: B,
+    format: ItemFormat<K, D, E>,
+    ttl: Ttl<T>,
+) -> impl Fn(Name) -> Event
+where
+    B: Fn(Name) -> PathBuf,
+    K: Checksum,
+    D: Codec,
+    E: Envelope,
+    T: Clock,
+{
+    let checksize: usize = format.checker.size();
+    move |n: Name| {
+        if let Err(e) = full::name_check(&n) {
+            return e;
+        }
+        let p: PathBuf = path_builder(n.clone());
+        let chk = |dat: &[u8]| format.checker.compute(dat);
+        let checked = |raw: Vec<u8>| {
+            header::check_checksum_id(&n, &raw, &format.checker)?;
+            raw2item_with_checksum(n.clone(), raw, checksize, &chk)
+        };
+        // libc::EIO = 5(linux, windows, macos)
+        let got: Result<Item, Event> = path2raw(n.clone(), p, format.max_file, 5)
+            .and_then(checked)
+            .and_then(|framed: Item| unframe_unexpired(n.clone(), framed, &format, &ttl));
+        match got {
+            Ok(item) => Event::ItemGot(NamedItem::new(item, n)),
+            Err(e) => e,
+        }
+    }
+}
+
+/// Creates checked read handler which uses `Checksum`, the compatibility option and expiry rules.
+///
+/// # Arguments
+/// - path_builder: Builds a path for a named item.
+/// - checker: Computes checksum.
+/// - compat: Item formats to accept.
+/// - ttl: Expiry rules.
+pub fn read_handler_new_with_checker_ttl<B, K, T>(
+    path_builder: B,
+    checker: K,
+    compat: Compat,
+    ttl: Ttl<T>,
+) -> impl Fn(Name) -> Event
+where
+    B: Fn(Name) -> PathBuf,
+    K: Checksum,
+    T: Clock,
+{
+    let format = ItemFormat::new(checker).with_compat(compat);
+    read_handler_new_with_format(path_builder, format, ttl)
+}
+
+/// Creates checked read handler which uses `Checksum` and the compatibility option.
+///
+/// Items which have a deadline will be reported as `Event::Expired` after the deadline.
+///
+/// # Arguments
+/// - path_builder: Builds a path for a named item.
+/// - checker: Computes checksum.
+/// - compat: Item formats to accept.
+pub fn read_handler_new_with_checker_compat<B, K>(
+    path_builder: B,
+    checker: K,
+    compat: Compat,
+) -> impl Fn(Name) -> Event
+where
+    B: Fn(Name) -> PathBuf,
+    K: Checksum,
+{
+    read_handler_new_with_checker_ttl(path_builder, checker, compat, Ttl::default())
+}
+
+/// Creates checked read handler which uses `Checksum`(headerless items will be rejected).
+///
+/// # Arguments
+/// - path_builder: Builds a path for a named item.
+/// - checker: Computes checksum.
+pub fn read_handler_new_with_checker<B, K>(path_builder: B, checker: K) -> impl Fn(Name) -> Event
+where
+    B: Fn(Name) -> PathBuf,
+    K: Checksum,
+{
+    read_handler_new_with_checker_compat(path_builder, checker, Compat::Strict)
+}
+
+/// Creates checked read handler which uses default path builder and `Checksum`.
+///
+/// # Arguments
+/// - dirname: Path to open buffer files.
+/// - checker: Computes checksum.
+pub fn read_handler_new_default_with_checker<P, K>(dirname: P, checker: K) -> impl Fn(Name) -> Event
+where
+    P: AsRef<Path>,
+    K: Checksum,
+{
+    let path_builder = full::fullpath_builder_new(dirname);
+    read_handler_new_with_checker(path_builder, checker)
+}
+
 fn checksum_nop(_: &[u8]) -> Vec<u8> {
     vec![]
 }
@@ -212,4 +417,194 @@ mod test_read {
             assert_eq!(evt, Event::NoEntry(Name::from("not-exist.dat")));
         }
     }
+
+    #[cfg(feature = "chacha20poly1305")]
+    mod read_handler_opened_new {
+        use std::path::Path;
+        use std::time::SystemTime;
+
+        use crate::checksum::{self, Crc32c};
+        use crate::envelope::{ChaCha20Poly1305, Envelope};
+        use crate::evt::Event;
+        use crate::item::{Item, Name, NamedItem};
+        use crate::meta::Meta;
+        use crate::read;
+        use crate::write;
+
+        #[test]
+        fn test_fields() {
+            let e = ChaCha20Poly1305::new(1, [0x01; 32]);
+            let sealed: Vec<u8> = e.seal(b"00", b"pii").unwrap();
+            let now = SystemTime::now();
+            let get = |n: Name| {
+                let item = Item::from(sealed.clone())
+                    .with_meta(Meta::new().with("k", "v"))
+                    .with_deadline(now);
+                Event::ItemGot(NamedItem::new(item, n))
+            };
+            let f = read::read_handler_opened_new(get, &e);
+            let got: Item = f(Name::from("00")).try_into().unwrap();
+            assert_eq!(got.as_slice(), b"pii");
+            assert_eq!(got.meta(), &Meta::new().with("k", "v"));
+            assert_eq!(got.deadline(), Some(now));
+
+            // sealed for another slot
+            assert_eq!(f(Name::from("01")), Event::Broken(Name::from("01")));
+        }
+
+        #[test]
+        #[ignore]
+        fn test_sealed() {
+            let dirname = Path::new("./test.d/read/read_handler_opened_new/sealed.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+            let old = ChaCha20Poly1305::new(1, [0x01; 32]);
+            let w = write::writer_sealed_new(
+                write::writer_checked_new_default_with_checksum(
+                    dirname,
+                    checksum::checksum2fn(Crc32c),
+                ),
+                old,
+            );
+            w(NamedItem::new(
+                Item::from(b"pii".as_slice()),
+                Name::from("00"),
+            ))
+            .unwrap();
+            w(NamedItem::new(
+                Item::from(b"pii".as_slice()),
+                Name::from("01"),
+            ))
+            .unwrap();
+            let raw: Vec<u8> = std::fs::read(dirname.join("00")).unwrap();
+            assert!(!raw.windows(3).any(|w: &[u8]| w == b"pii"));
+
+            // rotated: new items will be sealed by the key 2.
+            let rotated = ChaCha20Poly1305::new(2, [0x02; 32]).with_key(1, [0x01; 32]);
+            let f = read::read_handler_opened_new(
+                read::read_handler_new_default_with_checksum(
+                    dirname,
+                    4,
+                    checksum::checksum2fn(Crc32c),
+                ),
+                rotated,
+            );
+            let got: Item = f(Name::from("00")).try_into().unwrap();
+            assert_eq!(got, Item::from(b"pii".as_slice()));
+
+            let g = read::read_handler_opened_new(
+                read::read_handler_new_default_with_checksum(
+                    dirname,
+                    4,
+                    checksum::checksum2fn(Crc32c),
+                ),
+                ChaCha20Poly1305::new(2, [0x02; 32]),
+            );
+            assert_eq!(
+                g(Name::from("01")),
+                Event::UnsupportedFormat(Name::from("01"))
+            );
+        }
+    }
+
+    mod read_handler_new_with_checksum_max {
+        use std::path::Path;
+
+        use crate::checksum::{self, Crc32c};
+        use crate::evt::Event;
+        use crate::item::{Item, Name, NamedItem};
+        use crate::read;
+        use crate::write;
+
+        #[test]
+        #[ignore]
+        fn test_max() {
+            let dirname = Path::new("./test.d/read/read_handler_new_with_checksum_max/max.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+            let w = write::writer_checked_new_default_with_checksum(
+                dirname,
+                checksum::checksum2fn(Crc32c),
+            );
+            w(NamedItem::new(
+                Item::from(b"item".as_slice()),
+                Name::from("00"),
+            ))
+            .unwrap();
+
+            let new = |max: u64| {
+                read::read_handler_new_with_checksum_max(
+                    move |n: Name| dirname.join(n.as_str()),
+                    4,
+                    checksum::checksum2fn(Crc32c),
+                    Some(max),
+                )
+            };
+            let got: Item = new(8)(Name::from("00")).try_into().unwrap();
+            assert_eq!(got, Item::from(b"item".as_slice()));
+            assert_eq!(new(7)(Name::from("00")), Event::ItemTooLarge(8));
+        }
+    }
+
+    mod read_handler_new_default_with_checker {
+        use std::path::Path;
+
+        use crate::checksum::{Checksum, Crc32c, Crc64};
+        use crate::evt::Event;
+        use crate::header::Compat;
+        use crate::item::{Item, Name, NamedItem};
+        use crate::read;
+        use crate::write::{self, WriteMode};
+
+        #[test]
+        #[ignore]
+        fn test_checked() {
+            let dirname =
+                Path::new("./test.d/read/read_handler_new_default_with_checker/checked.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+            let w = write::writer_checked_new_default_with_checker_mode(
+                dirname,
+                Crc32c,
+                WriteMode::InPlace,
+            );
+            w(NamedItem::new(
+                Item::from(b"item".as_slice()),
+                Name::from("00"),
+            ))
+            .unwrap();
+
+            let f = read::read_handler_new_default_with_checker(dirname, Crc32c);
+            let got: Item = f(Name::from("00")).try_into().unwrap();
+            assert_eq!(got, Item::from(b"item".as_slice()));
+
+            let g = read::read_handler_new_default_with_checker(dirname, Crc64);
+            assert_eq!(
+                g(Name::from("00")),
+                Event::UnsupportedFormat(Name::from("00"))
+            );
+        }
+
+        #[test]
+        #[ignore]
+        fn test_legacy() {
+            let dirname = Path::new("./test.d/read/read_handler_new_default_with_checker/legacy.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+            let mut dat: Vec<u8> = b"item".to_vec();
+            dat.extend(Crc32c.compute(b"item"));
+            std::fs::write(dirname.join("00"), dat).unwrap();
+
+            let f = read::read_handler_new_default_with_checker(dirname, Crc32c);
+            assert_eq!(f(Name::from("00")), Event::Broken(Name::from("00")));
+
+            let g = read::read_handler_new_with_checker_compat(
+                move |n: Name| dirname.join(n.as_str()),
+                Crc32c,
+                Compat::Legacy,
+            );
+            let got: Item = g(Name::from("00")).try_into().unwrap();
+            assert_eq!(got, Item::from(b"item".as_slice()));
+        }
+    }
 }
@@ -1,4 +1,5 @@
 use crate::item::{Item, Name};
+use crate::order::Page;
 
 /// A list of supported operations.
 #[non_exhaustive]
@@ -9,12 +10,48 @@ pub enum Request {
     /// Remove a named item.
     Del(Name),
 
-    /// Push an item.
+    /// Push an item(`Event::ItemWrote` contains the name of the pushed item).
     Push(Item),
 
+    /// Push items with a single durability barrier(`Event::Batch` contains results per item).
+    PushBatch(Vec<Item>),
+
+    /// Get named items(`Event::Batch` contains results per name).
+    GetMany(Vec<Name>),
+
+    /// Remove named items with a single durability barrier(`Event::Batch` contains results per name).
+    DelMany(Vec<Name>),
+
     /// List names.
     List,
 
+    /// List a page of names in the order(`Event::NamesGot`; see `order::Order`).
+    ListPage(Page),
+
     /// Remove broken items.
     Vacuum,
+
+    /// Remove expired items.
+    Expire,
+
+    /// Get occupancy and byte usage(`Event::Stat`).
+    Stat,
+
+    /// Get and remove the oldest item(sequential buffer only; expired items will be skipped).
+    Pop,
+
+    /// Get the oldest item without removing it(sequential buffer only).
+    PeekOldest,
+
+    /// Reserve an available item for this consumer(consumer buffer only).
+    ///
+    /// The item will be hidden from other consumers until `Request::Ack`, `Request::Nack`
+    /// or the visibility timeout.
+    Claim,
+
+    /// Remove a claimed item(consumer buffer only).
+    Ack(Name),
+
+    /// Release a claimed item for other consumers(consumer buffer only).
+    Nack(Name),
 }
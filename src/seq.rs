@@ -0,0 +1,403 @@
+use crate::batch;
+use crate::cursor::Cursor;
+use crate::evt::Event;
+use crate::item::{Item, Name, NamedItem};
+use crate::order::{Order, Page};
+use crate::push::FullPolicy;
+use crate::request::Request;
+use crate::RingBuffer;
+
+/// Sequential(FIFO) ring buffer which uses a persisted `Cursor` to pick slots.
+///
+/// Handles `Request::Push`, `Request::Pop`, `Request::PeekOldest` and
+/// `Request::ListPage`(`Order::Sequence`).
+/// Other requests will be handled by the inner ring buffer.
+pub struct SeqRingBuffer<R, W, E, N, S> {
+    /// Handles get/del/list/vacuum.
+    pub inner: R,
+
+    /// Writes `NamedItem` without empty check.
+    pub write: W,
+
+    /// Checks if `Name` is empty.
+    pub is_empty: E,
+
+    /// Converts a slot number(0..capacity) to `Name`.
+    pub seq2name: N,
+
+    /// Saves the cursor.
+    pub save: S,
+
+    /// Current cursor(loaded from storage).
+    pub cursor: Cursor,
+
+    /// Number of slots.
+    pub capacity: u64,
+
+    /// Strategy to push an item into a full buffer.
+    pub policy: FullPolicy,
+}
+
+impl<R, W, E, N, S> SeqRingBuffer<R, W, E, N, S>
+where
+    R: RingBuffer,
+    W: Fn(NamedItem) -> Result<Name, Event>,
+    E: Fn(&Name) -> Result<bool, Event>,
+    N: Fn(u64) -> Name,
+    S: Fn(&Cursor) -> Result<(), Event>,
+{
+    fn slot_name(&self, seq: u64) -> Result<Name, Event> {
+        seq.checked_rem(self.capacity)
+            .map(&self.seq2name)
+            .ok_or(Event::BadRequest)
+    }
+
+    fn save_cursor(&mut self, c: Cursor) -> Result<(), Event> {
+        (self.save)(&c)?;
+        self.cursor = c;
+        Ok(())
+    }
+
+    /// Writes an item to the tail slot and saves the next cursor.
+    fn write_tail(&mut self, item: Item, next: Cursor) -> Result<Name, Event> {
+        let name: Name = self.slot_name(self.cursor.tail())?;
+        let wrote: Name = (self.write)(NamedItem::new(item, name))?;
+        self.save_cursor(next)?;
+        Ok(wrote)
+    }
+
+    fn push(&mut self, item: Item) -> Result<Event, Event> {
+        let full: bool = self.capacity <= self.cursor.len();
+        match (full, self.policy) {
+            (false, _) => self
+                .write_tail(item, self.cursor.pushed())
+                .map(Event::ItemWrote),
+            (true, FullPolicy::Reject) => Err(Event::TooManyItemsAlready),
+            (true, FullPolicy::DropIncoming) => Ok(Event::ItemDropped),
+            // the tail slot of a full buffer is the head slot.
+            (true, FullPolicy::OverwriteOldest) => self
+                .write_tail(item, self.cursor.popped().pushed())
+                .map(Event::ItemOverwrote),
+        }
+    }
+
+    /// Finds the oldest non-empty slot from the cursor(empty slots will be skipped).
+    fn oldest(&self, mut c: Cursor) -> Result<(Cursor, Name), Event> {
+        loop {
+            let name: Name = self.slot_name(c.head())?;
+            if c.is_empty() {
+                return Err(Event::NoEntry(name));
+            }
+            match (self.is_empty)(&name)? {
+                true => c = c.popped(),
+                false => return Ok((c, name)),
+            }
+        }
+    }
+
+    /// Gets the oldest unexpired item(expired items will be skipped).
+    fn peek(&mut self) -> Result<Event, Event> {
+        let mut c: Cursor = self.cursor;
+        loop {
+            let (found, name) = self.oldest(c)?;
+            match self.inner.handle(Request::Get(name)) {
+                Event::Expired(_) => c = found.popped(),
+                got => return Ok(got),
+            }
+        }
+    }
+
+    /// Removes the oldest item(expired items will be removed and skipped).
+    fn pop(&mut self) -> Result<Event, Event> {
+        loop {
+            let (c, name) = self.oldest(self.cursor)?;
+            let got: Event = self.inner.handle(Request::Get(name.clone()));
+            match got {
+                Event::ItemGot(_) => {}
+                Event::Broken(_) => {}
+                Event::Expired(_) => {}
+                e => return Err(e),
+            }
+            self.save_cursor(c.popped())?;
+            // the slot is out of the cursor now; a later push overwrites it even if truncate failed.
+            self.inner.handle(Request::Del(name));
+            match got {
+                Event::Expired(_) => continue,
+                _ => return Ok(got),
+            }
+        }
+    }
+
+    /// Lists non-empty slots from the head(only slots up to the page end will be checked).
+    fn list_sequence(&self, page: Page) -> Result<Vec<Name>, Event> {
+        let c: Cursor = self.cursor;
+        let end: usize = page
+            .limit
+            .map_or(usize::MAX, |l| page.offset.saturating_add(l));
+        let mut found: Vec<Name> = vec![];
+        for seq in c.head()..c.tail() {
+            if end <= found.len() {
+                break;
+            }
+            let name: Name = self.slot_name(seq)?;
+            if !(self.is_empty)(&name)? {
+                found.push(name);
+            }
+        }
+        Ok(page.apply(found.into_iter()))
+    }
+
+    fn handle_push(&mut self, item: Item) -> Event {
+        self.push(item).unwrap_or_else(|e| e)
+    }
+
+    fn handle_pop(&mut self) -> Event {
+        self.pop().unwrap_or_else(|e| e)
+    }
+
+    fn handle_peek(&mut self) -> Event {
+        self.peek().unwrap_or_else(|e| e)
+    }
+}
+
+impl<R, W, E, N, S> RingBuffer for SeqRingBuffer<R, W, E, N, S>
+where
+    R: RingBuffer,
+    W: Fn(NamedItem) -> Result<Name, Event>,
+    E: Fn(&Name) -> Result<bool, Event>,
+    N: Fn(u64) -> Name,
+    S: Fn(&Cursor) -> Result<(), Event>,
+{
+    fn handle(&mut self, req: Request) -> Event {
+        match req {
+            Request::Push(item) => self.handle_push(item),
+            Request::PushBatch(items) => {
+                batch::handle_many(self, items.into_iter().map(Request::Push).collect())
+            }
+            Request::Pop => self.handle_pop(),
+            Request::PeekOldest => self.handle_peek(),
+            Request::ListPage(page) if page.order == Order::Sequence => self
+                .list_sequence(page)
+                .map(Event::NamesGot)
+                .unwrap_or_else(|e| e),
+            q => self.inner.handle(q),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_seq {
+
+    mod seq_ring_buffer {
+        use std::path::Path;
+        use std::time::SystemTime;
+
+        use crate::cursor::Cursor;
+        use crate::evt::Event;
+        use crate::item::{Item, Name, NamedItem};
+        use crate::mem::{MemSlots, RawSlots};
+        use crate::naming::HexNames;
+        use crate::order::{Order, Page};
+        use crate::push::FullPolicy;
+        use crate::request::Request;
+        use crate::seq::SeqRingBuffer;
+        use crate::u::buf;
+        use crate::RingBuffer;
+
+        fn seq_new(slots: &MemSlots, cursor: Cursor, capacity: u64) -> impl RingBuffer {
+            seq_new_with_policy(slots, cursor, capacity, FullPolicy::Reject)
+        }
+
+        fn seq_new_with_policy(
+            slots: &MemSlots,
+            cursor: Cursor,
+            capacity: u64,
+            policy: FullPolicy,
+        ) -> impl RingBuffer {
+            let raw = RawSlots {
+                slots: slots.clone(),
+            };
+            let (w, e) = (raw.clone(), slots.clone());
+            SeqRingBuffer {
+                inner: raw,
+                write: move |named: NamedItem| w.write(named),
+                is_empty: move |n: &Name| Ok(e.is_empty(n)),
+                seq2name: |u: u64| Name::from(format!("{:02x}", u)),
+                save: |_: &Cursor| Ok(()),
+                cursor,
+                capacity,
+                policy,
+            }
+        }
+
+        fn pop2item(evt: Event) -> Item {
+            evt.try_into().unwrap()
+        }
+
+        #[test]
+        fn test_fifo() {
+            let slots = MemSlots::default();
+            let mut rb = seq_new(&slots, Cursor::default(), 3);
+            for b in [b"a", b"b", b"c"] {
+                let evt: Event = rb.handle(Request::Push(Item::from(b.as_slice())));
+                assert!(matches!(evt, Event::ItemWrote(_)));
+            }
+            assert_eq!(
+                rb.handle(Request::Push(Item::from(vec![]))),
+                Event::TooManyItemsAlready
+            );
+
+            let peek: Item = pop2item(rb.handle(Request::PeekOldest));
+            assert_eq!(peek, Item::from(b"a".as_slice()));
+
+            assert_eq!(
+                pop2item(rb.handle(Request::Pop)),
+                Item::from(b"a".as_slice())
+            );
+            assert_eq!(
+                pop2item(rb.handle(Request::Pop)),
+                Item::from(b"b".as_slice())
+            );
+
+            let evt: Event = rb.handle(Request::Push(Item::from(b"d".as_slice())));
+            assert_eq!(evt, Event::ItemWrote(Name::from("00")));
+
+            assert_eq!(
+                pop2item(rb.handle(Request::Pop)),
+                Item::from(b"c".as_slice())
+            );
+            assert_eq!(
+                pop2item(rb.handle(Request::Pop)),
+                Item::from(b"d".as_slice())
+            );
+            assert_eq!(rb.handle(Request::Pop), Event::NoEntry(Name::from("01")));
+        }
+
+        #[test]
+        fn test_skip_empty() {
+            let slots = MemSlots::default();
+            slots.set_raw(&Name::from("02"), b"c".to_vec(), SystemTime::now());
+            let mut rb = seq_new(&slots, Cursor::new(0, 3).unwrap(), 4);
+            assert_eq!(
+                pop2item(rb.handle(Request::Pop)),
+                Item::from(b"c".as_slice())
+            );
+            assert_eq!(rb.handle(Request::Pop), Event::NoEntry(Name::from("03")));
+        }
+
+        #[test]
+        fn test_skip_expired() {
+            let slots = MemSlots::default();
+            slots.set_raw(&Name::from("00"), b"expired".to_vec(), SystemTime::now());
+            slots.set_raw(&Name::from("01"), b"b".to_vec(), SystemTime::now());
+            let mut rb = seq_new(&slots, Cursor::new(0, 2).unwrap(), 4);
+            assert_eq!(
+                pop2item(rb.handle(Request::PeekOldest)),
+                Item::from(b"b".as_slice())
+            );
+            assert!(!slots.is_empty(&Name::from("00")));
+            assert_eq!(
+                pop2item(rb.handle(Request::Pop)),
+                Item::from(b"b".as_slice())
+            );
+            assert!(slots.used(&HexNames::u8()).is_empty());
+        }
+
+        #[test]
+        fn test_overwrite() {
+            let slots = MemSlots::default();
+            let mut rb =
+                seq_new_with_policy(&slots, Cursor::default(), 2, FullPolicy::OverwriteOldest);
+            rb.handle(Request::Push(Item::from(b"a".as_slice())));
+            rb.handle(Request::Push(Item::from(b"b".as_slice())));
+            let evt: Event = rb.handle(Request::Push(Item::from(b"c".as_slice())));
+            assert_eq!(evt, Event::ItemOverwrote(Name::from("00")));
+            let evt: Event = rb.handle(Request::Push(Item::from(b"d".as_slice())));
+            assert_eq!(evt, Event::ItemOverwrote(Name::from("01")));
+
+            assert_eq!(
+                pop2item(rb.handle(Request::Pop)),
+                Item::from(b"c".as_slice())
+            );
+            assert_eq!(
+                pop2item(rb.handle(Request::Pop)),
+                Item::from(b"d".as_slice())
+            );
+        }
+
+        #[test]
+        fn test_drop() {
+            let slots = MemSlots::default();
+            let mut rb =
+                seq_new_with_policy(&slots, Cursor::default(), 1, FullPolicy::DropIncoming);
+            rb.handle(Request::Push(Item::from(b"a".as_slice())));
+            let evt: Event = rb.handle(Request::Push(Item::from(b"b".as_slice())));
+            assert_eq!(evt, Event::ItemDropped);
+            assert_eq!(
+                pop2item(rb.handle(Request::Pop)),
+                Item::from(b"a".as_slice())
+            );
+        }
+
+        #[test]
+        #[ignore]
+        fn test_restart() {
+            let dirname = Path::new("./test.d/seq/seq_ring_buffer/restart.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let mut rb = buf::ring_buffer_impl_u8_new_fifo(dirname).unwrap();
+            for b in [b"a", b"b", b"c"] {
+                let evt: Event = rb.handle(Request::Push(Item::from(b.as_slice())));
+                assert!(matches!(evt, Event::ItemWrote(_)));
+            }
+            assert_eq!(
+                pop2item(rb.handle(Request::Pop)),
+                Item::from(b"a".as_slice())
+            );
+            drop(rb);
+
+            let mut restarted = buf::ring_buffer_impl_u8_new_fifo(dirname).unwrap();
+            assert_eq!(
+                pop2item(restarted.handle(Request::Pop)),
+                Item::from(b"b".as_slice())
+            );
+            assert_eq!(
+                pop2item(restarted.handle(Request::Pop)),
+                Item::from(b"c".as_slice())
+            );
+            assert_eq!(
+                restarted.handle(Request::Pop),
+                Event::NoEntry(Name::from("03"))
+            );
+        }
+
+        #[test]
+        fn test_list_sequence() {
+            let slots = MemSlots::default();
+            let mut rb =
+                seq_new_with_policy(&slots, Cursor::default(), 3, FullPolicy::OverwriteOldest);
+            for b in [b"a", b"b", b"c", b"d"] {
+                rb.handle(Request::Push(Item::from(b.as_slice())));
+            }
+            slots.truncate(&Name::from("02"));
+
+            let page = Page::new(Order::Sequence);
+            let expected = vec![Name::from("01"), Name::from("00")];
+            assert_eq!(
+                rb.handle(Request::ListPage(page)),
+                Event::NamesGot(expected)
+            );
+
+            let page = Page::new(Order::Sequence).with_offset(1).with_limit(1);
+            let expected = vec![Name::from("00")];
+            assert_eq!(
+                rb.handle(Request::ListPage(page)),
+                Event::NamesGot(expected)
+            );
+
+            let page = Page::new(Order::Created);
+            assert_eq!(rb.handle(Request::ListPage(page)), Event::BadRequest);
+        }
+    }
+}
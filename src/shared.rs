@@ -0,0 +1,383 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+
+use crate::batch;
+use crate::evt::Event;
+use crate::item::{Item, Name, NamedItem};
+use crate::push;
+use crate::request::Request;
+use crate::RingBuffer;
+
+/// Per slot reader/writer locks.
+///
+/// Readers of a slot run in parallel; a writer excludes others on the same slot only.
+#[derive(Debug, Default)]
+pub struct SlotLocks {
+    // > 0: number of readers, -1: a writer
+    state: Mutex<HashMap<Name, i64>>,
+    released: Condvar,
+}
+
+/// Releases a slot lock on drop.
+pub struct SlotGuard<'a> {
+    locks: &'a SlotLocks,
+    name: Name,
+}
+
+impl SlotLocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, HashMap<Name, i64>> {
+        // the state is a plain counter map; it is consistent even if a holder panicked.
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn acquire(&self, name: &Name, write: bool) -> SlotGuard<'_> {
+        let mut state = self.state();
+        loop {
+            let cnt: i64 = state.get(name).copied().unwrap_or(0);
+            let busy: bool = match write {
+                true => cnt != 0,
+                false => cnt < 0,
+            };
+            if !busy {
+                state.insert(name.clone(), if write { -1 } else { cnt + 1 });
+                break;
+            }
+            state = self
+                .released
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        SlotGuard {
+            locks: self,
+            name: name.clone(),
+        }
+    }
+
+    /// Locks the slot for reading(waits while a writer holds the slot).
+    pub fn read(&self, name: &Name) -> SlotGuard<'_> {
+        self.acquire(name, false)
+    }
+
+    /// Locks the slot for writing(waits while others hold the slot).
+    pub fn write(&self, name: &Name) -> SlotGuard<'_> {
+        self.acquire(name, true)
+    }
+}
+
+impl Drop for SlotGuard<'_> {
+    fn drop(&mut self) {
+        let mut state = self.locks.state();
+        let cnt: i64 = state.get(&self.name).copied().unwrap_or(0);
+        match cnt {
+            2.. => {
+                state.insert(self.name.clone(), cnt - 1);
+            }
+            _ => {
+                state.remove(&self.name);
+            }
+        }
+        drop(state);
+        self.locks.released.notify_all();
+    }
+}
+
+struct Shared<G, D, N, W, L, F, V> {
+    get: G,
+    del: D,
+    next: Mutex<N>,
+    write: W,
+    list: L,
+    is_full: F,
+    vacuum: V,
+    slots: SlotLocks,
+}
+
+/// Ring buffer which can be shared between threads(random ring buffer only).
+///
+/// Clones share the same buffer.
+/// `Request::Get` and `Request::List` run in parallel.
+/// Mutations(`Request::Push`, `Request::Del`) on the same slot will be serialized.
+/// A push into a full buffer will be rejected(`Event::TooManyItemsAlready`).
+pub struct SharedRingBuffer<G, D, N, W, L, F, V> {
+    shared: Arc<Shared<G, D, N, W, L, F, V>>,
+}
+
+impl<G, D, N, W, L, F, V> Clone for SharedRingBuffer<G, D, N, W, L, F, V> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<G, D, N, W, L, F, V> SharedRingBuffer<G, D, N, W, L, F, V>
+where
+    G: Fn(Name) -> Event,
+    D: Fn(Name) -> Event,
+    N: FnMut() -> Result<Name, Event>,
+    W: Fn(NamedItem) -> Result<Name, Event>,
+    L: Fn() -> Event,
+    F: Fn() -> Result<bool, Event>,
+    V: Fn() -> Result<u64, Event>,
+{
+    /// Creates new shared ring buffer.
+    ///
+    /// # Arguments
+    /// - get:     Gets a named item.
+    /// - del:     Removes a named item.
+    /// - next:    Gets a name to push an item(serialized).
+    /// - write:   Writes an item if the slot is empty(`Event::Again` otherwise; push retries).
+    /// - list:    Lists names.
+    /// - is_full: Checks if the buffer is full(pushes will be rejected).
+    /// - vacuum:  Extra cleanup before `Request::Vacuum`(orphaned temporary files, ...).
+    pub fn new(get: G, del: D, next: N, write: W, list: L, is_full: F, vacuum: V) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                get,
+                del,
+                next: Mutex::new(next),
+                write,
+                list,
+                is_full,
+                vacuum,
+                slots: SlotLocks::new(),
+            }),
+        }
+    }
+
+    fn next_name(&self) -> Result<Name, Event> {
+        let mut next = self
+            .shared
+            .next
+            .lock()
+            .map_err(|e| Event::UnexpectedError(format!("Unable to get next name: {}", e)))?;
+        (next)()
+    }
+
+    fn push_named(&self, item: Item, name: Name) -> Result<Name, Event> {
+        let _slot = self.shared.slots.write(&name);
+        (self.shared.write)(NamedItem::new(item, name))
+    }
+
+    fn full(&self) -> Option<Event> {
+        match (self.shared.is_full)() {
+            Ok(true) => Some(Event::TooManyItemsAlready),
+            Ok(false) => None,
+            Err(e) => Some(e),
+        }
+    }
+
+    /// Pushes the item into a random empty slot.
+    ///
+    /// The buffer will be checked before retries and after running out of retries(a buffer
+    /// filled by other threads is full, not busy).
+    fn push(&self, item: Item) -> Event {
+        if let Some(e) = self.full() {
+            return e;
+        }
+        let mut last: Event = Event::Again;
+        for _ in 0..push::RETRY_LIMIT {
+            let pushed = self
+                .next_name()
+                .and_then(|name: Name| self.push_named(item.clone(), name));
+            match pushed {
+                Ok(name) => return Event::ItemWrote(name),
+                Err(Event::Again) => last = Event::Again,
+                Err(e) => return e,
+            }
+        }
+        self.full().unwrap_or(last)
+    }
+
+    /// Handles the request(can be called from many threads at once).
+    pub fn handle_shared(&self, req: Request) -> Event {
+        let s: &Shared<G, D, N, W, L, F, V> = &self.shared;
+        match req {
+            Request::Get(name) => {
+                let _slot = s.slots.read(&name);
+                (s.get)(name)
+            }
+            Request::Del(name) => {
+                let _slot = s.slots.write(&name);
+                (s.del)(name)
+            }
+            Request::Push(item) => self.push(item),
+            Request::List => (s.list)(),
+            Request::PushBatch(items) => batch::handle_many(
+                &mut self.clone(),
+                items.into_iter().map(Request::Push).collect(),
+            ),
+            Request::GetMany(names) => batch::handle_many(
+                &mut self.clone(),
+                names.into_iter().map(Request::Get).collect(),
+            ),
+            Request::DelMany(names) => batch::handle_many(
+                &mut self.clone(),
+                names.into_iter().map(Request::Del).collect(),
+            ),
+            Request::Vacuum => match (s.vacuum)() {
+                Ok(_) => crate::remove_broken_buffers(&mut self.clone()),
+                Err(e) => e,
+            },
+            Request::Expire => crate::remove_expired_buffers(&mut self.clone()),
+            _ => Event::BadRequest,
+        }
+    }
+}
+
+impl<G, D, N, W, L, F, V> RingBuffer for SharedRingBuffer<G, D, N, W, L, F, V>
+where
+    G: Fn(Name) -> Event,
+    D: Fn(Name) -> Event,
+    N: FnMut() -> Result<Name, Event>,
+    W: Fn(NamedItem) -> Result<Name, Event>,
+    L: Fn() -> Event,
+    F: Fn() -> Result<bool, Event>,
+    V: Fn() -> Result<u64, Event>,
+{
+    fn handle(&mut self, req: Request) -> Event {
+        self.handle_shared(req)
+    }
+}
+
+/// Creates new request handler which can be cloned and sent to other threads.
+pub fn ring_buffer_new<G, D, N, W, L, F, V>(
+    r: SharedRingBuffer<G, D, N, W, L, F, V>,
+) -> impl Fn(Request) -> Event + Clone + Send + Sync
+where
+    G: Fn(Name) -> Event + Send + Sync,
+    D: Fn(Name) -> Event + Send + Sync,
+    N: FnMut() -> Result<Name, Event> + Send,
+    W: Fn(NamedItem) -> Result<Name, Event> + Send + Sync,
+    L: Fn() -> Event + Send + Sync,
+    F: Fn() -> Result<bool, Event> + Send + Sync,
+    V: Fn() -> Result<u64, Event> + Send + Sync,
+{
+    move |req: Request| r.handle_shared(req)
+}
+
+#[cfg(test)]
+mod test_shared {
+
+    mod slot_locks {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        use crate::item::Name;
+        use crate::shared::SlotLocks;
+
+        #[test]
+        fn test_readers() {
+            let locks = SlotLocks::new();
+            let n = Name::from("00");
+            let _a = locks.read(&n);
+            let _b = locks.read(&n);
+            let _c = locks.write(&Name::from("01"));
+        }
+
+        #[test]
+        fn test_writer() {
+            let locks = Arc::new(SlotLocks::new());
+            let done = Arc::new(AtomicBool::new(false));
+            let n = Name::from("00");
+            let g = locks.write(&n);
+            let t = {
+                let (locks, done, n) = (locks.clone(), done.clone(), n.clone());
+                std::thread::spawn(move || {
+                    let _r = locks.read(&n);
+                    done.store(true, Ordering::SeqCst);
+                })
+            };
+            std::thread::sleep(Duration::from_millis(50));
+            assert!(!done.load(Ordering::SeqCst));
+            drop(g);
+            t.join().unwrap();
+            assert!(done.load(Ordering::SeqCst));
+        }
+    }
+
+    mod shared_ring_buffer {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        use crate::evt::Event;
+        use crate::item::{Item, Name, NamedItem};
+        use crate::mem::RawSlots;
+        use crate::request::Request;
+        use crate::shared::{self, SharedRingBuffer};
+
+        #[test]
+        fn test_threads() {
+            let raw = RawSlots::default();
+            let (g, d, w, l) = (raw.clone(), raw.clone(), raw.clone(), raw.clone());
+            let cnt = AtomicU64::new(0);
+            let rb = SharedRingBuffer::new(
+                move |n: Name| g.get(n),
+                move |n: Name| d.del(n),
+                move || Ok(Name::from(cnt.fetch_add(1, Ordering::SeqCst) as u8)),
+                move |named: NamedItem| match w.slots.is_empty(named.as_name()) {
+                    true => w.write(named),
+                    false => Err(Event::Again),
+                },
+                move || Event::NamesGot(l.names()),
+                || Ok(false),
+                || Ok(0),
+            );
+            let f = shared::ring_buffer_new(rb);
+            let threads: Vec<_> = (0..8)
+                .map(|_| {
+                    let f = f.clone();
+                    std::thread::spawn(move || {
+                        (0..16).for_each(|_| {
+                            let n: Name = f(Request::Push(Item::from(b"item".as_slice())))
+                                .try_into()
+                                .unwrap();
+                            let got: Item = f(Request::Get(n)).try_into().unwrap();
+                            assert_eq!(got, Item::from(b"item".as_slice()));
+                        })
+                    })
+                })
+                .collect();
+            threads.into_iter().for_each(|t| t.join().unwrap());
+            let names: Vec<Name> = f(Request::List).try_into().unwrap();
+            assert_eq!(names.len(), 128);
+            assert_eq!(f(Request::Del(Name::from("00"))), Event::Success);
+            assert_eq!(f(Request::Pop), Event::BadRequest);
+        }
+
+        #[test]
+        fn test_full() {
+            let raw = RawSlots::default();
+            let (g, d, w, l, u) = (
+                raw.clone(),
+                raw.clone(),
+                raw.clone(),
+                raw.clone(),
+                raw.clone(),
+            );
+            let cnt = AtomicU64::new(0);
+            let rb = SharedRingBuffer::new(
+                move |n: Name| g.get(n),
+                move |n: Name| d.del(n),
+                move || Ok(Name::from((cnt.fetch_add(1, Ordering::SeqCst) % 4) as u8)),
+                move |named: NamedItem| match w.slots.is_empty(named.as_name()) {
+                    true => w.write(named),
+                    false => Err(Event::Again),
+                },
+                move || Event::NamesGot(l.names()),
+                move || Ok(4 <= u.names().len()),
+                || Ok(0),
+            );
+            let f = shared::ring_buffer_new(rb);
+            let push = || f(Request::Push(Item::from(b"item".as_slice())));
+            (0..4).for_each(|_| assert!(matches!(push(), Event::ItemWrote(_))));
+            assert_eq!(push(), Event::TooManyItemsAlready);
+            assert_eq!(f(Request::Del(Name::from("02"))), Event::Success);
+            assert_eq!(push(), Event::ItemWrote(Name::from("02")));
+        }
+    }
+}
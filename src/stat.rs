@@ -0,0 +1,228 @@
+use std::collections::HashSet;
+use std::fs::Metadata;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::batch;
+use crate::evt::Event;
+use crate::item::{Item, Name};
+use crate::request::Request;
+use crate::RingBuffer;
+
+/// Occupancy and byte usage of a ring buffer(see `Request::Stat`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Stat {
+    /// Number of non-empty slots.
+    pub used: u64,
+
+    /// Number of empty slots.
+    pub free: u64,
+
+    /// Number of slots.
+    pub capacity: u64,
+
+    /// Total byte length of item files(headers and checksums included).
+    pub bytes: u64,
+
+    /// The oldest modified time of items.
+    pub oldest: Option<SystemTime>,
+
+    /// The newest modified time of items.
+    pub newest: Option<SystemTime>,
+
+    /// Number of broken items found since the last `Request::Vacuum`.
+    pub broken: u64,
+}
+
+impl Stat {
+    pub(crate) fn with_item(self, size: u64, modified: SystemTime) -> Self {
+        Self {
+            used: self.used + 1,
+            free: self.free.saturating_sub(1),
+            bytes: self.bytes + size,
+            oldest: Some(self.oldest.map_or(modified, |o| o.min(modified))),
+            newest: Some(self.newest.map_or(modified, |n| n.max(modified))),
+            ..self
+        }
+    }
+}
+
+fn name2meta<B>(path_builder: &B, n: Name) -> Result<Option<Metadata>, Event>
+where
+    B: Fn(Name) -> PathBuf,
+{
+    match std::fs::metadata(path_builder(n)) {
+        Ok(m) => Ok(Some(m)),
+        // removed after the list
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Event::UnexpectedError(format!(
+            "Unable to get metadata: {}",
+            e
+        ))),
+    }
+}
+
+/// Creates new stat getter which uses metadata of listed items(broken count will be 0).
+///
+/// # Arguments
+/// - list: Gets non-empty names.
+/// - path_builder: Builds a path for a named item.
+/// - capacity: Number of slots.
+pub fn stat_new<L, B>(list: L, path_builder: B, capacity: u64) -> impl Fn() -> Result<Stat, Event>
+where
+    L: Fn() -> Result<Vec<Name>, Event>,
+    B: Fn(Name) -> PathBuf,
+{
+    move || {
+        let empty = Stat {
+            free: capacity,
+            capacity,
+            ..Stat::default()
+        };
+        list()?.into_iter().try_fold(empty, |s: Stat, n: Name| {
+            let meta: Option<Metadata> = name2meta(&path_builder, n)?;
+            match meta {
+                None => Ok(s),
+                Some(m) => m
+                    .modified()
+                    .map(|t: SystemTime| s.with_item(m.len(), t))
+                    .map_err(|e| {
+                        Event::UnexpectedError(format!("Unable to get modified time: {}", e))
+                    }),
+            }
+        })
+    }
+}
+
+/// Ring buffer wrapper which reports the stat(`Request::Stat`) with the broken count.
+///
+/// Broken items found by `Request::Get` will be counted until `Request::Vacuum`(removed items
+/// and overwritten slots will not be counted).
+pub struct StatRingBuffer<R, S> {
+    pub inner: R,
+
+    /// Gets occupancy and byte usage(see `stat_new`).
+    pub stat: S,
+
+    broken: HashSet<Name>,
+}
+
+impl<R, S> StatRingBuffer<R, S>
+where
+    R: RingBuffer,
+    S: Fn() -> Result<Stat, Event>,
+{
+    pub fn new(inner: R, stat: S) -> Self {
+        Self {
+            inner,
+            stat,
+            broken: HashSet::new(),
+        }
+    }
+
+    fn handle_get(&mut self, name: Name) -> Event {
+        let got: Event = self.inner.handle(Request::Get(name));
+        if let Event::Broken(n) = &got {
+            self.broken.insert(n.clone());
+        }
+        got
+    }
+
+    fn handle_del(&mut self, name: Name) -> Event {
+        self.broken.remove(&name);
+        self.inner.handle(Request::Del(name))
+    }
+
+    fn handle_push(&mut self, item: Item) -> Event {
+        let pushed: Event = self.inner.handle(Request::Push(item));
+        if let Event::ItemOverwrote(n) = &pushed {
+            self.broken.remove(n);
+        }
+        pushed
+    }
+
+    fn handle_vacuum(&mut self) -> Event {
+        let removed: Event = self.inner.handle(Request::Vacuum);
+        self.broken.clear();
+        removed
+    }
+
+    fn handle_stat(&mut self) -> Event {
+        match (self.stat)() {
+            Ok(s) => Event::Stat(Stat {
+                broken: self.broken.len() as u64,
+                ..s
+            }),
+            Err(e) => e,
+        }
+    }
+}
+
+impl<R, S> RingBuffer for StatRingBuffer<R, S>
+where
+    R: RingBuffer,
+    S: Fn() -> Result<Stat, Event>,
+{
+    fn handle(&mut self, req: Request) -> Event {
+        match req {
+            Request::Get(name) => self.handle_get(name),
+            Request::Del(name) => self.handle_del(name),
+            Request::Push(item) => self.handle_push(item),
+            Request::PushBatch(items) => {
+                batch::handle_many(self, items.into_iter().map(Request::Push).collect())
+            }
+            Request::GetMany(names) => {
+                batch::handle_many(self, names.into_iter().map(Request::Get).collect())
+            }
+            Request::DelMany(names) => {
+                batch::handle_many(self, names.into_iter().map(Request::Del).collect())
+            }
+            Request::Vacuum => self.handle_vacuum(),
+            Request::Stat => self.handle_stat(),
+            q => self.inner.handle(q),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_stat {
+
+    mod stat_new {
+        use std::path::{Path, PathBuf};
+        use std::time::{Duration, SystemTime};
+
+        use crate::item::Name;
+        use crate::stat::{self, Stat};
+
+        #[test]
+        fn test_empty() {
+            let f = stat::stat_new(|| Ok(vec![]), |n: Name| PathBuf::from(n.as_str()), 16);
+            let s: Stat = f().unwrap();
+            assert_eq!(s.used, 0);
+            assert_eq!(s.free, 16);
+            assert_eq!(s.capacity, 16);
+            assert_eq!(s.oldest, None);
+        }
+
+        #[test]
+        #[ignore]
+        fn test_items() {
+            let dirname = Path::new("./test.d/stat/stat_new/items.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            std::fs::write(dirname.join("00"), b"abc").unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+            std::fs::write(dirname.join("01"), b"defgh").unwrap();
+            let f = stat::stat_new(
+                || Ok(vec![Name::from("00"), Name::from("01"), Name::from("02")]),
+                |n: Name| dirname.join(n.as_str()),
+                256,
+            );
+            let s: Stat = f().unwrap();
+            assert_eq!((s.used, s.free, s.bytes), (2, 254, 8));
+            let oldest: SystemTime = s.oldest.unwrap();
+            assert!(oldest < s.newest.unwrap());
+        }
+    }
+}
@@ -0,0 +1,694 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::batch;
+use crate::checksum::{Checksum, ChecksumState};
+use crate::claim::Claim;
+use crate::clock::Clock;
+use crate::codec::{self, Codec};
+use crate::empty;
+use crate::envelope::{self, Envelope};
+use crate::evt::Event;
+use crate::full::{self, Layout};
+use crate::header::{self, Compat, Header, ItemFormat, HEADER_SIZE_MAX};
+use crate::item::Name;
+use crate::list;
+use crate::meta::Meta;
+use crate::naming::HexNames;
+use crate::next;
+use crate::push;
+use crate::read;
+use crate::tmp;
+use crate::ttl::Ttl;
+use crate::write::{self, WriteMode};
+
+const BUF_SIZE: usize = 64 * 1024;
+
+// libc::EIO = 5(linux, windows, macos)
+const IO_ERROR_NUM: i32 = 5;
+
+/// The header fields of a streamed item(the payload comes from a reader).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamHead {
+    /// The time when the item expires(per item).
+    pub deadline: Option<SystemTime>,
+
+    /// Typed key/value metadata stored after the header.
+    pub meta: Meta,
+}
+
+fn io2event(e: io::Error) -> Event {
+    Event::UnexpectedError(format!("Unable to stream item: {}", e))
+}
+
+/// Feeds `len` bytes of the reader to the checksum state.
+fn feed<R>(r: &mut R, state: &mut dyn ChecksumState, len: u64) -> io::Result<()>
+where
+    R: Read,
+{
+    let mut buf: Vec<u8> = vec![0; BUF_SIZE];
+    let mut rest: u64 = len;
+    while 0 < rest {
+        let size: usize = rest.min(BUF_SIZE as u64) as usize;
+        r.read_exact(&mut buf[..size])?;
+        state.update(&buf[..size]);
+        rest -= size as u64;
+    }
+    Ok(())
+}
+
+fn head2bytes<K>(
+    head: &StreamHead,
+    meta: &[u8],
+    length: u64,
+    created: SystemTime,
+    checker: &K,
+) -> Result<Vec<u8>, Event>
+where
+    K: Checksum,
+{
+    let h: Header = Header::new(length, created, checker)?;
+    let h: Header = match head.deadline {
+        None => h,
+        Some(d) => h.with_deadline(d),
+    };
+    let h: Header = match meta.len() {
+        0 => h,
+        len => h.with_meta_size(len.try_into().map_err(|_| Event::BadRequest)?),
+    };
+    let mut b: Vec<u8> = Vec::from(&h);
+    b.extend(meta);
+    Ok(b)
+}
+
+/// Writes header + metadata + payload + checksum to the file.
+///
+/// The payload length is unknown until the reader ends: the header will be rewritten after the
+/// payload, then the checksum will be computed by reading the file back(constant memory).
+fn stream2file<K>(
+    r: &mut dyn Read,
+    f: &mut File,
+    head: &StreamHead,
+    created: SystemTime,
+    checker: &K,
+) -> Result<u64, Event>
+where
+    K: Checksum,
+{
+    let meta: Vec<u8> = Vec::try_from(&head.meta)?;
+    let mut bw = BufWriter::with_capacity(BUF_SIZE, &mut *f);
+    bw.write_all(&head2bytes(head, &meta, 0, created, checker)?)
+        .map_err(io2event)?;
+    let length: u64 = io::copy(r, &mut bw).map_err(io2event)?;
+    bw.flush().map_err(io2event)?;
+    drop(bw);
+
+    let head: Vec<u8> = head2bytes(head, &meta, length, created, checker)?;
+    f.seek(SeekFrom::Start(0)).map_err(io2event)?;
+    f.write_all(&head).map_err(io2event)?;
+    f.seek(SeekFrom::Start(0)).map_err(io2event)?;
+    let mut state = checker.state().ok_or(Event::BadRequest)?;
+    feed(
+        &mut BufReader::with_capacity(BUF_SIZE, &mut *f),
+        state.as_mut(),
+        head.len() as u64 + length,
+    )
+    .map_err(io2event)?;
+    f.seek(SeekFrom::End(0)).map_err(io2event)?;
+    f.write_all(&state.finish()).map_err(io2event)?;
+    Ok(length)
+}
+
+fn stream2path<K>(
+    r: &mut dyn Read,
+    p: &Path,
+    head: &StreamHead,
+    created: SystemTime,
+    checker: &K,
+) -> Result<u64, Event>
+where
+    K: Checksum,
+{
+    let mut f: File = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(p)
+        .map_err(|e| {
+            Event::UnexpectedError(format!("Unable to create named item({:#?}): {}", p, e))
+        })?;
+    let length: u64 = stream2file(r, &mut f, head, created, checker)?;
+    write::sync_file(f, p)?;
+    Ok(length)
+}
+
+fn stream2path_with_mode<K>(
+    r: &mut dyn Read,
+    p: &Path,
+    head: &StreamHead,
+    created: SystemTime,
+    checker: &K,
+    mode: WriteMode,
+) -> Result<u64, Event>
+where
+    K: Checksum,
+{
+    match mode {
+        WriteMode::InPlace => stream2path(r, p, head, created, checker),
+        WriteMode::Atomic => {
+            let tmp: PathBuf = tmp::tmp_path_new(p)?;
+            stream2path(r, &tmp, head, created, checker)
+                .and_then(|length: u64| write::rename_synced(&tmp, p).map(|_| length))
+                .inspect_err(|_| {
+                    std::fs::remove_file(&tmp).ok();
+                })
+        }
+    }
+}
+
+/// Creates new unchecked stream writer which writes a payload from a reader(with an item header).
+///
+/// Payloads will be stored as is(the format codec will not be used).
+/// `Event::BadRequest` will be returned if the format envelope has a key(sealing needs the
+/// whole payload) or if the checksum has no incremental state(see `Checksum::state`).
+///
+/// # Arguments
+/// - layout: Directory layout of buffer files(shard dirs will be created on demand).
+/// - format: Checksum and envelope of items(the compatibility option will be ignored).
+/// - mode:   Strategy to write an item file.
+/// - clock:  Gets the creation time of items.
+pub fn stream_writer_new_layout_with_format<K, D, E, T>(
+    layout: Layout,
+    format: ItemFormat<K, D, E>,
+    mode: WriteMode,
+    clock: T,
+) -> impl Fn(Name, &StreamHead, &mut dyn Read) -> Result<u64, Event>
+where
+    K: Checksum,
+    E: Envelope,
+    T: Clock,
+{
+    move |name: Name, head: &StreamHead, r: &mut dyn Read| {
+        let streamable: bool =
+            format.envelope.key_id().is_none() && format.checker.state().is_some();
+        if !streamable {
+            return Err(Event::BadRequest);
+        }
+        let p: PathBuf = layout.path_checked(&name)?;
+        write::create_shard_dir(&layout, &name, mode)?;
+        stream2path_with_mode(r, &p, head, clock.now(), &format.checker, mode)
+    }
+}
+
+/// Creates new stream push handler(random ring buffer; slots will be claimed before writing).
+///
+/// The payload will be read from the reader after an empty slot was claimed.
+/// A full buffer rejects pushes(`Event::TooManyItemsAlready`).
+///
+/// # Arguments
+/// - layout: Directory layout of buffer files(flat or sharded).
+/// - names:  Naming scheme(capacity and name width).
+/// - format: Checksum and envelope of items(see `stream_writer_new_layout_with_format`).
+/// - mode:   Strategy to write an item file.
+/// - clock:  Gets the creation time of items.
+pub fn stream_push_new_layout_with_format<K, D, E, T>(
+    layout: Layout,
+    names: HexNames,
+    format: ItemFormat<K, D, E>,
+    mode: WriteMode,
+    clock: T,
+) -> Result<impl FnMut(&StreamHead, &mut dyn Read) -> Event, Event>
+where
+    K: Checksum,
+    E: Envelope,
+    T: Clock,
+{
+    let mut get_name = next::u::next_random_hex_new_from_path_default(names)?;
+    let is_empty = empty::name2empty_fs_new(layout.path_builder());
+    let used = list::list_scanned_new(layout.clone(), names);
+    let capacity: u64 = names.capacity();
+    let wtr = stream_writer_new_layout_with_format(layout.clone(), format, mode, clock);
+    let mut claim = move || -> Result<Option<(Name, Claim)>, Event> {
+        let full: bool = capacity <= used()?.len() as u64;
+        if full {
+            return Err(Event::TooManyItemsAlready);
+        }
+        let name: Name = get_name()?;
+        write::create_shard_dir(&layout, &name, mode)?;
+        let c: Claim = match Claim::acquire(layout.path(&name)) {
+            Ok(c) => c,
+            Err(Event::Again) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        match is_empty(name.clone())? {
+            true => Ok(Some((name, c))),
+            false => Ok(None),
+        }
+    };
+    Ok(move |head: &StreamHead, r: &mut dyn Read| {
+        for _ in 0..push::RETRY_LIMIT {
+            let (name, c) = match claim() {
+                Ok(Some(claimed)) => claimed,
+                Ok(None) => continue,
+                Err(e) => return e,
+            };
+            let wrote: Result<u64, Event> = wtr(name.clone(), head, r);
+            // a running batch holds the claim until its renames are done
+            let _claim: Option<Claim> = batch::defer_release(c);
+            return match wrote {
+                Ok(_) => Event::ItemWrote(name),
+                Err(e) => e,
+            };
+        }
+        Event::Again
+    })
+}
+
+/// A payload reader of a validated item.
+///
+/// The checksum, the header and the deadline were verified before the first read.
+/// Bytes will be read from the opened file without being verified again: an item overwritten in
+/// place(`WriteMode::InPlace`) while reading may return mixed bytes, and a file truncated while
+/// reading will be reported as `ErrorKind::UnexpectedEof`. Use `WriteMode::Atomic` to keep the
+/// opened file intact(the rename replaces the path, not the file).
+pub struct ItemReader {
+    header: Option<Header>,
+    meta: Meta,
+    payload: Box<dyn Read>,
+}
+
+impl ItemReader {
+    /// The item header(`None` for legacy headerless items).
+    pub fn header(&self) -> Option<&Header> {
+        self.header.as_ref()
+    }
+
+    pub fn meta(&self) -> &Meta {
+        &self.meta
+    }
+}
+
+impl Read for ItemReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.payload.read(buf)
+    }
+}
+
+/// Reads exactly `rest` bytes(a short read is an error, not the end of the payload).
+struct Exact<R> {
+    inner: R,
+    rest: u64,
+}
+
+impl<R> Read for Exact<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let size: usize = buf.len().min(self.rest.try_into().unwrap_or(usize::MAX));
+        if size == 0 {
+            return Ok(0);
+        }
+        let cnt: usize = self.inner.read(&mut buf[..size])?;
+        if cnt == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("Item file truncated: {} bytes missing", self.rest),
+            ));
+        }
+        self.rest -= cnt as u64;
+        Ok(cnt)
+    }
+}
+
+fn verify_checksum<K>(n: &Name, f: &mut File, checker: &K) -> Result<u64, Event>
+where
+    K: Checksum,
+{
+    let ioerr = |e: io::Error| read::err2event(n.clone(), e, IO_ERROR_NUM);
+    let len: u64 = f.metadata().map_err(ioerr)?.len();
+    let checksize: u64 = checker.size() as u64;
+    let body: u64 = len
+        .checked_sub(checksize)
+        .ok_or_else(|| Event::Broken(n.clone()))?;
+    let mut state = checker.state().ok_or(Event::BadRequest)?;
+    let mut br = BufReader::with_capacity(BUF_SIZE, &mut *f);
+    feed(&mut br, state.as_mut(), body).map_err(ioerr)?;
+    let mut chk: Vec<u8> = vec![0; checker.size()];
+    br.read_exact(&mut chk).map_err(ioerr)?;
+    (chk == state.finish())
+        .then_some(body)
+        .ok_or_else(|| Event::Broken(n.clone()))
+}
+
+fn payload2reader<D, E>(
+    n: &Name,
+    h: &Header,
+    meta: &[u8],
+    payload: Box<dyn Read>,
+    codec: &D,
+    envelope: &E,
+) -> Result<Box<dyn Read>, Event>
+where
+    D: Codec,
+    E: Envelope,
+{
+    let broken = |_: Event| Event::Broken(n.clone());
+    let plain: bool = !h.is_sealed() && h.codec_id() == codec::ID_IDENTITY;
+    if plain {
+        return Ok(payload);
+    }
+    // sealed/encoded payloads can not be streamed(the whole payload is needed)
+    let mut dat: Vec<u8> = Vec::new();
+    let mut payload = payload;
+    payload
+        .read_to_end(&mut dat)
+        .map_err(|_| Event::Broken(n.clone()))?;
+    let dat: Vec<u8> = match h.is_sealed() {
+        false => dat,
+        true => envelope::open_named(envelope, n, &header::aad(h, meta, n), &dat)?,
+    };
+    let dat: Vec<u8> = match h.codec_id() {
+        codec::ID_IDENTITY => dat,
+        id => codec::decode_by_id(id, &dat, codec).map_err(broken)?,
+    };
+    Ok(Box::new(io::Cursor::new(dat)))
+}
+
+fn path2reader<K, D, E, T>(
+    n: Name,
+    p: PathBuf,
+    format: &ItemFormat<K, D, E>,
+    ttl: &Ttl<T>,
+) -> Result<ItemReader, Event>
+where
+    K: Checksum,
+    D: Codec,
+    E: Envelope,
+    T: Clock,
+{
+    let ioerr = |e: io::Error| read::err2event(n.clone(), e, IO_ERROR_NUM);
+    let broken = |_| Event::Broken(n.clone());
+    let mut f: File = File::open(p).map_err(ioerr)?;
+    let mut head: Vec<u8> = Vec::with_capacity(HEADER_SIZE_MAX);
+    Read::by_ref(&mut f)
+        .take(HEADER_SIZE_MAX as u64)
+        .read_to_end(&mut head)
+        .map_err(ioerr)?;
+    header::check_checksum_id(&n, &head, &format.checker)?;
+
+    let len: u64 = f.metadata().map_err(ioerr)?.len();
+    if format.max_file.is_some_and(|max: u64| max < len) {
+        return Err(Event::ItemTooLarge(len));
+    }
+    f.seek(SeekFrom::Start(0)).map_err(ioerr)?;
+    let body: u64 = verify_checksum(&n, &mut f, &format.checker)?;
+    head.truncate(body.min(HEADER_SIZE_MAX as u64) as usize);
+    let legacy: bool = format.compat == Compat::Legacy && !header::has_magic(&head);
+    if legacy {
+        f.seek(SeekFrom::Start(0)).map_err(ioerr)?;
+        return Ok(ItemReader {
+            header: None,
+            meta: Meta::new(),
+            payload: Box::new(Exact {
+                inner: BufReader::new(f),
+                rest: body,
+            }),
+        });
+    }
+
+    let h: Header = Header::try_from(head.as_slice()).map_err(broken)?;
+    let supported: bool =
+        h.validate(&format.checker).is_ok() && codec::is_known(h.codec_id(), &format.codec);
+    supported
+        .then_some(())
+        .ok_or_else(|| Event::UnsupportedFormat(n.clone()))?;
+    let offset: u64 = (h.size() + h.meta_size()) as u64;
+    (offset.checked_add(h.length()) == Some(body))
+        .then_some(())
+        .ok_or_else(|| Event::Broken(n.clone()))?;
+    if ttl.is_expired(&h) {
+        return Err(Event::Expired(n));
+    }
+
+    f.seek(SeekFrom::Start(h.size() as u64)).map_err(ioerr)?;
+    let mut meta: Vec<u8> = vec![0; h.meta_size()];
+    f.read_exact(&mut meta).map_err(ioerr)?;
+    let payload: Box<dyn Read> = Box::new(Exact {
+        inner: BufReader::new(f),
+        rest: h.length(),
+    });
+    let payload: Box<dyn Read> =
+        payload2reader(&n, &h, &meta, payload, &format.codec, &format.envelope)?;
+    let meta: Meta = Meta::try_from(meta.as_slice()).map_err(broken)?;
+    Ok(ItemReader {
+        header: Some(h),
+        meta,
+        payload,
+    })
+}
+
+/// Creates new stream reader which returns a validated payload reader of a named item.
+///
+/// The whole file will be read once to verify the checksum(constant memory), then the reader
+/// will be positioned at the payload.
+/// Sealed or encoded payloads will be opened/decoded in memory.
+/// `Event::BadRequest` will be returned if the checksum has no incremental state.
+/// Files over the max byte length of the format will be reported as `Event::ItemTooLarge`.
+///
+/// # Arguments
+/// - path_builder: Builds a path for a named item.
+/// - format: Checksum, codec, envelope and item formats to accept.
+/// - ttl: Expiry rules(`Event::Expired` for expired items).
+pub fn stream_reader_new_with_format<B, K, D, E, T>(
+    path_builder: B,
+    format: ItemFormat<K, D, E>,
+    ttl: Ttl<T>,
+) -> impl Fn(Name) -> Result<ItemReader, Event>
+where
+    B: Fn(Name) -> PathBuf,
+    K: Checksum,
+    D: Codec,
+    E: Envelope,
+    T: Clock,
+{
+    move |n: Name| {
+        full::name_check(&n)?;
+        let p: PathBuf = path_builder(n.clone());
+        path2reader(n, p, &format, &ttl)
+    }
+}
+
+#[cfg(test)]
+mod test_stream {
+
+    mod stream_push_new_layout_with_format {
+        use std::fs::OpenOptions;
+        use std::io::{ErrorKind, Read};
+        use std::path::Path;
+        use std::time::{Duration, SystemTime};
+
+        use crate::checksum::{Checksum, XxHash64};
+        use crate::clock::SystemClock;
+        #[cfg(feature = "chacha20poly1305")]
+        use crate::envelope::ChaCha20Poly1305;
+        use crate::evt::Event;
+        use crate::full::Layout;
+        use crate::header::ItemFormat;
+        use crate::item::{Item, Name, NamedItem};
+        use crate::meta::Meta;
+        use crate::naming::HexNames;
+        use crate::read;
+        use crate::stream::{self, ItemReader, StreamHead};
+        use crate::ttl::Ttl;
+        use crate::write::{self, WriteMode};
+
+        #[test]
+        #[ignore]
+        fn test_roundtrip() {
+            let dirname =
+                Path::new("./test.d/stream/stream_push_new_layout_with_format/roundtrip.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let layout = Layout::flat(dirname);
+            let format = ItemFormat::new(XxHash64::new(42));
+            let mut push = stream::stream_push_new_layout_with_format(
+                layout.clone(),
+                HexNames::u8(),
+                format,
+                WriteMode::Atomic,
+                SystemClock,
+            )
+            .unwrap();
+            let dat: Vec<u8> = (0..300_000u32).map(|i: u32| (i % 251) as u8).collect();
+            let deadline = SystemTime::now() + Duration::from_secs(3600);
+            let head = StreamHead {
+                deadline: Some(deadline),
+                meta: Meta::new().with("content-type", "video/mp4"),
+            };
+            let n: Name = push(&head, &mut dat.as_slice()).try_into().unwrap();
+
+            let get = stream::stream_reader_new_with_format(
+                layout.path_builder(),
+                format,
+                Ttl::default(),
+            );
+            let mut r: ItemReader = get(n.clone()).unwrap();
+            assert_eq!(r.meta(), &head.meta);
+            assert_eq!(r.header().unwrap().length(), dat.len() as u64);
+            let mut got: Vec<u8> = Vec::new();
+            r.read_to_end(&mut got).unwrap();
+            assert_eq!(got, dat);
+
+            // streamed items are regular items
+            let read =
+                read::read_handler_new_with_format(layout.path_builder(), format, Ttl::default());
+            let item: Item = read(n.clone()).try_into().unwrap();
+            assert_eq!(item.as_slice(), dat.as_slice());
+            assert_eq!(item.meta(), &head.meta);
+
+            let p = layout.path(&n);
+            let mut raw: Vec<u8> = std::fs::read(&p).unwrap();
+            raw[100_000] ^= 0x01;
+            std::fs::write(&p, raw).unwrap();
+            assert_eq!(get(n.clone()).err(), Some(Event::Broken(n)));
+        }
+
+        #[derive(Clone, Copy)]
+        struct Sum;
+
+        impl Checksum for Sum {
+            fn size(&self) -> usize {
+                1
+            }
+
+            fn compute(&self, dat: &[u8]) -> Vec<u8> {
+                vec![dat.iter().fold(0, |s: u8, b: &u8| s.wrapping_add(*b))]
+            }
+        }
+
+        #[test]
+        #[ignore]
+        fn test_truncated() {
+            let dirname =
+                Path::new("./test.d/stream/stream_push_new_layout_with_format/truncated.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let layout = Layout::flat(dirname);
+            let format = ItemFormat::new(XxHash64::new(42));
+            let mut push = stream::stream_push_new_layout_with_format(
+                layout.clone(),
+                HexNames::u8(),
+                format,
+                WriteMode::InPlace,
+                SystemClock,
+            )
+            .unwrap();
+            let dat: Vec<u8> = vec![0x42; 100_000];
+            let n: Name = push(&StreamHead::default(), &mut dat.as_slice())
+                .try_into()
+                .unwrap();
+
+            let get = stream::stream_reader_new_with_format(
+                layout.path_builder(),
+                format,
+                Ttl::default(),
+            );
+            let mut r: ItemReader = get(n.clone()).unwrap();
+            let f = OpenOptions::new()
+                .write(true)
+                .open(layout.path(&n))
+                .unwrap();
+            f.set_len(1000).unwrap();
+            let mut got: Vec<u8> = Vec::new();
+            let e = r.read_to_end(&mut got).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+            assert!(got.len() < dat.len());
+
+            // checksums without incremental state can not be streamed
+            let stateless = ItemFormat::new(Sum);
+            let mut push = stream::stream_push_new_layout_with_format(
+                layout.clone(),
+                HexNames::u8(),
+                stateless,
+                WriteMode::InPlace,
+                SystemClock,
+            )
+            .unwrap();
+            assert_eq!(
+                push(&StreamHead::default(), &mut dat.as_slice()),
+                Event::BadRequest
+            );
+            let w = write::writer_unchecked_new_layout_with_format_clock(
+                layout.clone(),
+                stateless,
+                WriteMode::InPlace,
+                SystemClock,
+            );
+            let n: Name = w(NamedItem::new(Item::from(dat.as_slice()), n)).unwrap();
+            let get = stream::stream_reader_new_with_format(
+                layout.path_builder(),
+                stateless,
+                Ttl::default(),
+            );
+            assert_eq!(get(n).err(), Some(Event::BadRequest));
+        }
+
+        #[cfg(feature = "chacha20poly1305")]
+        #[test]
+        #[ignore]
+        fn test_sealed() {
+            let dirname = Path::new("./test.d/stream/stream_push_new_layout_with_format/sealed.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let e = ChaCha20Poly1305::new(1, [0x42; 32]);
+            let format = ItemFormat::new(XxHash64::new(42)).with_envelope(&e);
+            let mut push = stream::stream_push_new_layout_with_format(
+                Layout::flat(dirname),
+                HexNames::u8(),
+                format,
+                WriteMode::Atomic,
+                SystemClock,
+            )
+            .unwrap();
+            let head = StreamHead::default();
+            assert_eq!(push(&head, &mut b"pii".as_slice()), Event::BadRequest);
+
+            // sealed items can be read as a stream
+            let w = write::writer_unchecked_new_layout_with_format_clock(
+                Layout::flat(dirname),
+                format,
+                WriteMode::Atomic,
+                SystemClock,
+            );
+            let n: Name = w(NamedItem::new(
+                Item::from(b"pii".as_slice()),
+                Name::from("00"),
+            ))
+            .unwrap();
+            let get = stream::stream_reader_new_with_format(
+                Layout::flat(dirname).path_builder(),
+                format,
+                Ttl::default(),
+            );
+            let mut got: Vec<u8> = Vec::new();
+            get(n.clone()).unwrap().read_to_end(&mut got).unwrap();
+            assert_eq!(got, b"pii");
+
+            let other = ItemFormat::new(XxHash64::new(42))
+                .with_envelope(ChaCha20Poly1305::new(2, [0x42; 32]));
+            let get = stream::stream_reader_new_with_format(
+                Layout::flat(dirname).path_builder(),
+                other,
+                Ttl::default(),
+            );
+            assert_eq!(get(n.clone()).err(), Some(Event::UnsupportedFormat(n)));
+        }
+    }
+}
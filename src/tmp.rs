@@ -0,0 +1,287 @@
+use std::ffi::OsStr;
+use std::fs::DirEntry;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+use crate::claim;
+use crate::evt::Event;
+use crate::lease;
+
+const TMP_PREFIX: &str = ".";
+const TMP_SUFFIX: &str = ".tmp";
+
+/// Temporary files modified within this duration will not be removed by the orphan cleaner.
+pub const ORPHAN_AGE: Duration = Duration::from_secs(60);
+
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Checks if the file name is a temporary file name created by `tmp_path_new`.
+///
+/// * .42.1234-0.tmp -> true
+/// * .42.1234.tmp   -> true
+/// * 42           -> false
+/// * 42.tmp       -> false
+pub fn is_tmp_name(s: &str) -> bool {
+    s.len() > TMP_PREFIX.len() + TMP_SUFFIX.len()
+        && s.starts_with(TMP_PREFIX)
+        && s.ends_with(TMP_SUFFIX)
+}
+
+fn name2tmp(name: &OsStr, pid: u32, seq: u64) -> Option<String> {
+    name.to_str()
+        .map(|s: &str| format!("{}{}.{}-{}{}", TMP_PREFIX, s, pid, seq, TMP_SUFFIX))
+}
+
+/// Gets the process id which created the temporary file.
+///
+/// * .42.1234-0.tmp -> 1234
+/// * .42.1234.tmp   -> 1234
+fn tmp2pid(s: &str) -> Option<u32> {
+    s.strip_prefix(TMP_PREFIX)
+        .and_then(|s: &str| s.strip_suffix(TMP_SUFFIX))
+        .and_then(|s: &str| s.rsplit_once('.'))
+        .and_then(|(_, owner)| owner.split('-').next())
+        .and_then(|pid: &str| pid.parse().ok())
+}
+
+/// Checks if the process is running(`None` if unknown).
+pub(crate) fn pid_alive(pid: u32) -> Option<bool> {
+    if pid == std::process::id() {
+        return Some(true);
+    }
+    Path::new("/proc/self")
+        .exists()
+        .then(|| Path::new("/proc").join(pid.to_string()).exists())
+}
+
+/// Checks if the file was not modified within the duration.
+pub(crate) fn is_old(d: &DirEntry, age: Duration) -> bool {
+    d.metadata()
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t: SystemTime| SystemTime::now().duration_since(t).ok())
+        .map(|elapsed: Duration| age <= elapsed)
+        .unwrap_or(false)
+}
+
+/// Creates a temporary path for the item path(same directory, hidden, unique per write).
+///
+/// * ./ring.d/42 -> ./ring.d/.42.1234-0.tmp (process id 1234, first write)
+pub fn tmp_path_new<P>(p: P) -> Result<PathBuf, Event>
+where
+    P: AsRef<Path>,
+{
+    let p: &Path = p.as_ref();
+    p.file_name()
+        .and_then(|name: &OsStr| {
+            let seq: u64 = TMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+            name2tmp(name, std::process::id(), seq)
+        })
+        .map(|tmp: String| p.with_file_name(tmp))
+        .ok_or_else(|| Event::UnexpectedError(format!("Invalid item path: {:#?}", p)))
+}
+
+fn is_orphan(d: &DirEntry, name: &str, age: Duration) -> bool {
+    let live: bool = tmp2pid(name).and_then(pid_alive).unwrap_or(false);
+    is_tmp_name(name) && !live && is_old(d, age)
+}
+
+fn remove_tmp(d: DirEntry, age: Duration) -> Result<u64, Event> {
+    let is_dir: bool = d.file_type().map(|t| t.is_dir()).unwrap_or(false);
+    if is_dir {
+        return remove_orphans_older(d.path(), age);
+    }
+    let name = d.file_name();
+    let orphan: bool = name
+        .to_str()
+        .map(|s: &str| is_orphan(&d, s, age))
+        .unwrap_or(false);
+    match orphan {
+        false => Ok(0),
+        true => match std::fs::remove_file(d.path()) {
+            Ok(_) => Ok(1),
+            Err(e) => match e.kind() {
+                ErrorKind::NotFound => Ok(0),
+                _ => Err(Event::UnexpectedError(format!(
+                    "Unable to remove temporary file: {}",
+                    e
+                ))),
+            },
+        },
+    }
+}
+
+/// Removes orphaned temporary files(left by interrupted atomic writes).
+///
+/// Files of running processes and files modified within `ORPHAN_AGE` will be kept.
+/// Shard directories will be scanned recursively.
+/// Returns the number of removed files.
+pub fn remove_orphans<P>(dirname: P) -> Result<u64, Event>
+where
+    P: AsRef<Path>,
+{
+    remove_orphans_older(dirname, ORPHAN_AGE)
+}
+
+/// Removes orphaned temporary files not modified within the duration.
+///
+/// Files created by running processes will be kept.
+pub fn remove_orphans_older<P>(dirname: P, age: Duration) -> Result<u64, Event>
+where
+    P: AsRef<Path>,
+{
+    let dirents = std::fs::read_dir(dirname)
+        .map_err(|e| Event::UnexpectedError(format!("Unable to read dir: {}", e)))?;
+    dirents.into_iter().try_fold(0, |tot, r| {
+        let d: DirEntry =
+            r.map_err(|e| Event::UnexpectedError(format!("Unable to read dir entry: {}", e)))?;
+        remove_tmp(d, age).map(|cnt: u64| cnt + tot)
+    })
+}
+
+/// Creates new orphan cleaner which uses dirname to find temporary files, stale claims and
+/// leases of removed or replaced items(see `lease::remove_stale`).
+///
+/// Claims are taken by every push(`WriteMode::InPlace` as well), so a crashed writer may leave
+/// a claim even if no temporary file was written.
+pub fn orphan_cleaner_new_default<P>(dirname: P) -> impl Fn() -> Result<u64, Event>
+where
+    P: AsRef<Path>,
+{
+    move || {
+        let tmp: u64 = remove_orphans(dirname.as_ref())?;
+        let claims: u64 = claim::remove_stale(dirname.as_ref(), claim::CLAIM_AGE)?;
+        let leases: u64 = lease::remove_stale(dirname.as_ref())?;
+        Ok(tmp + claims + leases)
+    }
+}
+
+/// Writes an orphaned temporary file(owned by a dead process, not modified recently).
+#[cfg(test)]
+pub(crate) fn orphan_write<P>(p: P, dat: &[u8])
+where
+    P: AsRef<Path>,
+{
+    std::fs::write(p.as_ref(), dat).unwrap();
+    std::fs::File::options()
+        .write(true)
+        .open(p)
+        .unwrap()
+        .set_modified(SystemTime::UNIX_EPOCH)
+        .unwrap();
+}
+
+#[cfg(test)]
+mod test_tmp {
+
+    mod is_tmp_name {
+        use crate::tmp;
+
+        #[test]
+        fn test_tmp() {
+            assert!(tmp::is_tmp_name(".42.1234.tmp"));
+            assert!(tmp::is_tmp_name(".42.1234-0.tmp"));
+        }
+
+        #[test]
+        fn test_item() {
+            assert!(!tmp::is_tmp_name("42"));
+        }
+
+        #[test]
+        fn test_visible() {
+            assert!(!tmp::is_tmp_name("42.tmp"));
+        }
+
+        #[test]
+        fn test_too_short() {
+            assert!(!tmp::is_tmp_name(".tmp"));
+        }
+    }
+
+    mod tmp_path_new {
+        use std::path::{Path, PathBuf};
+
+        use crate::tmp;
+
+        #[test]
+        fn test_same_dir() {
+            let p: PathBuf = tmp::tmp_path_new("./test.d/42").unwrap();
+            assert_eq!(p.parent(), Some(Path::new("./test.d")));
+            let name: &str = p.file_name().unwrap().to_str().unwrap();
+            assert!(tmp::is_tmp_name(name));
+        }
+
+        #[test]
+        fn test_no_name() {
+            let r = tmp::tmp_path_new("/");
+            assert!(r.is_err());
+        }
+
+        #[test]
+        fn test_unique() {
+            let a: PathBuf = tmp::tmp_path_new("./test.d/42").unwrap();
+            let b: PathBuf = tmp::tmp_path_new("./test.d/42").unwrap();
+            assert_ne!(a, b);
+            let name: &str = a.file_name().unwrap().to_str().unwrap();
+            assert_eq!(tmp::tmp2pid(name), Some(std::process::id()));
+        }
+    }
+
+    mod tmp2pid {
+        use crate::tmp;
+
+        #[test]
+        fn test_owner() {
+            assert_eq!(tmp::tmp2pid(".42.1234-7.tmp"), Some(1234));
+            assert_eq!(tmp::tmp2pid(".42.1234.tmp"), Some(1234));
+            assert_eq!(tmp::tmp2pid(".42.tmp"), None);
+        }
+    }
+
+    mod remove_orphans {
+        use std::path::Path;
+
+        use crate::tmp;
+
+        #[test]
+        #[ignore]
+        fn test_orphans() {
+            let dirname = Path::new("./test.d/tmp/remove_orphans/orphans.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+            std::fs::write(dirname.join("42"), b"item").unwrap();
+            tmp::orphan_write(dirname.join(".43.4294967295.tmp"), b"orphan");
+
+            std::fs::create_dir_all(dirname.join("ab/cd")).unwrap();
+            tmp::orphan_write(dirname.join("ab/cd/.abcd1234.4294967295-0.tmp"), b"orphan");
+
+            let cnt: u64 = tmp::remove_orphans(dirname).unwrap();
+            assert_eq!(cnt, 2);
+            assert!(dirname.join("42").exists());
+            assert!(!dirname.join(".43.4294967295.tmp").exists());
+        }
+
+        #[test]
+        #[ignore]
+        fn test_in_use() {
+            let dirname = Path::new("./test.d/tmp/remove_orphans/in_use.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            // written by this(running) process
+            let live = dirname.join(format!(".42.{}-0.tmp", std::process::id()));
+            tmp::orphan_write(&live, b"writing");
+
+            // written just now(the writer may be running on another host)
+            let recent = dirname.join(".43.4294967295-0.tmp");
+            std::fs::write(&recent, b"writing").unwrap();
+
+            assert_eq!(tmp::remove_orphans(dirname), Ok(0));
+            assert!(live.exists());
+            assert!(recent.exists());
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::time::{Duration, SystemTime};
+
+use crate::clock::{Clock, SystemClock};
+use crate::header::Header;
+
+/// Expiry rules: a ring-wide max age and per item deadlines(stored in item headers).
+#[derive(Debug, Clone)]
+pub struct Ttl<C> {
+    clock: C,
+    max_age: Option<Duration>,
+}
+
+impl<C> Ttl<C>
+where
+    C: Clock,
+{
+    /// Creates new expiry rules.
+    ///
+    /// # Arguments
+    /// - clock: Gets the current time.
+    /// - max_age: Ring-wide max age(no limit if `None`; per item deadlines still apply).
+    pub fn new(clock: C, max_age: Option<Duration>) -> Self {
+        Self { clock, max_age }
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    pub fn max_age(&self) -> Option<Duration> {
+        self.max_age
+    }
+
+    /// Gets the time when the item expires(the earlier of the max age and the item deadline).
+    pub fn deadline(&self, h: &Header) -> Option<SystemTime> {
+        let aged: Option<SystemTime> = self
+            .max_age
+            .and_then(|d: Duration| h.created().checked_add(d));
+        match (aged, h.deadline()) {
+            (Some(a), Some(d)) => Some(a.min(d)),
+            (a, d) => a.or(d),
+        }
+    }
+
+    /// Checks if the item expired.
+    pub fn is_expired(&self, h: &Header) -> bool {
+        self.deadline(h)
+            .map(|d: SystemTime| d <= self.clock.now())
+            .unwrap_or(false)
+    }
+}
+
+impl Default for Ttl<SystemClock> {
+    fn default() -> Self {
+        Self::new(SystemClock, None)
+    }
+}
+
+#[cfg(test)]
+mod test_ttl {
+
+    mod ttl {
+        use std::time::{Duration, SystemTime};
+
+        use crate::checksum::Nop;
+        use crate::clock::ManualClock;
+        use crate::header::Header;
+        use crate::ttl::Ttl;
+
+        #[test]
+        fn test_max_age() {
+            let t0: SystemTime = SystemTime::UNIX_EPOCH + Duration::from_secs(42);
+            let clock = ManualClock::new(t0);
+            let ttl = Ttl::new(clock.clone(), Some(Duration::from_secs(10)));
+            let h: Header = Header::new(0, t0, &Nop).unwrap();
+            assert!(!ttl.is_expired(&h));
+            clock.advance(Duration::from_secs(10));
+            assert!(ttl.is_expired(&h));
+        }
+
+        #[test]
+        fn test_deadline() {
+            let t0: SystemTime = SystemTime::UNIX_EPOCH + Duration::from_secs(42);
+            let clock = ManualClock::new(t0);
+            let ttl = Ttl::new(clock.clone(), Some(Duration::from_secs(10)));
+            let h: Header = Header::new(0, t0, &Nop)
+                .unwrap()
+                .with_deadline(t0 + Duration::from_secs(1));
+            assert_eq!(ttl.deadline(&h), Some(t0 + Duration::from_secs(1)));
+            clock.advance(Duration::from_secs(1));
+            assert!(ttl.is_expired(&h));
+        }
+
+        #[test]
+        fn test_no_limit() {
+            let t0: SystemTime = SystemTime::UNIX_EPOCH;
+            let clock = ManualClock::new(t0);
+            let ttl = Ttl::new(clock.clone(), None);
+            let h: Header = Header::new(0, t0, &Nop).unwrap();
+            clock.advance(Duration::from_secs(86400));
+            assert!(!ttl.is_expired(&h));
+        }
+    }
+}
@@ -1,54 +1,582 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 use crate::{FsRingBuffer, RingBuffer};
 
+use crate::checksum::Checksum;
+use crate::clock::Clock;
+use crate::codec::Codec;
+use crate::consumer::ConsumerRingBuffer;
+use crate::cursor::{self, Cursor};
+use crate::envelope::Envelope;
 use crate::evt::Event;
+use crate::item::{Item, Name, NamedItem};
+use crate::lease::Leases;
+use crate::push::{self, FullPolicy};
+use crate::quota::{self, Quota, QuotaRingBuffer};
 use crate::request::Request;
+use crate::seq::SeqRingBuffer;
+use crate::shared::{self, SharedRingBuffer};
 
+use crate::empty;
+use crate::full::Layout;
+use crate::header::ItemFormat;
+use crate::list;
+use crate::naming::HexNames;
 use crate::next;
+use crate::order::{self, OrderedRingBuffer};
 use crate::read;
+use crate::stat;
+use crate::tmp;
+use crate::ttl::Ttl;
+use crate::vacuum::VacuumRingBuffer;
+use crate::write::{self, WriteMode};
 
-/// Creates default checked random ring buffer impl which uses u8 names.
+/// Creates new handler which rejects names out of the naming scheme(`Event::BadRequest`).
+pub(crate) fn name_checked_new<H>(names: HexNames, handler: H) -> impl Fn(Name) -> Event
+where
+    H: Fn(Name) -> Event,
+{
+    move |n: Name| match names.name2slot(&n) {
+        Ok(_) => handler(n),
+        Err(e) => e,
+    }
+}
+
+pub(crate) fn ring_buffer_impl_layout_new_with_push<G, U>(
+    layout: &Layout,
+    names: HexNames,
+    get: G,
+    push: U,
+) -> Result<impl RingBuffer, Event>
+where
+    G: Fn(Name) -> Event,
+    U: FnMut(Item) -> Event,
+{
+    let get = name_checked_new(names, get);
+    let del = name_checked_new(names, crate::del::del_handler_new(layout.path_builder()));
+    let list = list::list_request_handler_new_scanned(layout.clone(), names);
+
+    let stat = stat::stat_new(
+        list::list_scanned_new(layout.clone(), names),
+        layout.path_builder(),
+        names.capacity(),
+    );
+
+    let vacuum = tmp::orphan_cleaner_new_default(layout.dirname().to_path_buf());
+    vacuum()?;
+
+    Ok(OrderedRingBuffer {
+        inner: VacuumRingBuffer {
+            inner: FsRingBuffer::new(get, del, push, list).with_stat(stat),
+            vacuum,
+        },
+        created: order::created_getter_new(layout.path_builder()),
+    })
+}
+
+/// Creates new push handler which writes items into random empty slots of the layout.
+///
+/// # Arguments
+/// - layout: Directory layout of buffer files(flat or sharded).
+/// - names: Naming scheme(capacity and name width).
+/// - get_name: Gets next random name.
+/// - unchecked: Writes an item without checking the slot.
+/// - overwriter: Writes an item into the oldest slot.
+/// - mode: Strategy to write an item file.
+/// - policy: Strategy to push an item into a full buffer.
+pub(crate) fn push_handler_layout_new<N, W, O>(
+    layout: &Layout,
+    names: HexNames,
+    get_name: N,
+    unchecked: W,
+    overwriter: O,
+    mode: WriteMode,
+    policy: FullPolicy,
+) -> impl FnMut(Item) -> Event
+where
+    N: FnMut() -> Result<Name, Event>,
+    W: Fn(NamedItem) -> Result<Name, Event>,
+    O: Fn(NamedItem) -> Result<Name, Event>,
+{
+    let empty_checker = empty::name2empty_fs_new(layout.path_builder());
+    let wtr = write::writer_claimed_new_layout(
+        layout.clone(),
+        unchecked,
+        move |n: &Name| empty_checker(n.clone()),
+        mode,
+    );
+    let push = crate::push::push_handler_new_unmanaged_retry(get_name, wtr, push::RETRY_LIMIT);
+
+    let used = list::list_scanned_new(layout.clone(), names);
+    let capacity: u64 = names.capacity();
+    let is_full = move || used().map(|v: Vec<Name>| capacity <= v.len() as u64);
+
+    let oldest = list::oldest_finder_new(
+        list::list_scanned_new(layout.clone(), names),
+        list::modified_getter_new(layout.path_builder()),
+    );
+    let overwriter =
+        write::writer_claimed_new_layout(layout.clone(), overwriter, |_: &Name| Ok(true), mode);
+    let overwrite = move |item: Item| {
+        let name: Name = oldest()?;
+        overwriter(NamedItem::new(item, name))
+    };
+
+    crate::push::push_handler_new_with_policy(push, is_full, overwrite, policy)
+}
+
+fn ring_buffer_impl_layout_new_with_writers<G, W, O>(
+    layout: Layout,
+    names: HexNames,
+    get: G,
+    unchecked: W,
+    overwriter: O,
+    mode: WriteMode,
+    policy: FullPolicy,
+) -> Result<impl RingBuffer, Event>
+where
+    G: Fn(Name) -> Event,
+    W: Fn(NamedItem) -> Result<Name, Event>,
+    O: Fn(NamedItem) -> Result<Name, Event>,
+{
+    let get_name = next::u::next_random_hex_new_from_path_default(names)?;
+    let push = push_handler_layout_new(
+        &layout, names, get_name, unchecked, overwriter, mode, policy,
+    );
+    ring_buffer_impl_layout_new_with_push(&layout, names, get, push)
+}
+
+/// Creates checked random ring buffer impl which uses the layout, naming scheme and full policy.
+///
+/// Orphaned temporary files(atomic writes) and stale slot claims will be removed on open and on `Request::Vacuum`.
+/// The oldest item(modified time) will be overwritten if the policy is `OverwriteOldest`.
+/// Items will be stored without a header(use `ring_buffer_impl_layout_new_with_format`).
+///
+/// # Arguments
+/// - layout: Directory layout of buffer files(flat or sharded).
+/// - names: Naming scheme(capacity and name width).
+/// - checksize: Checksum byte length.
+/// - check_read:  Computes checksum.
+/// - check_write:  Computes checksum(use same closure for read).
+/// - mode: Strategy to write an item file.
+/// - policy: Strategy to push an item into a full buffer.
+pub fn ring_buffer_impl_layout_new_with_policy<C>(
+    layout: Layout,
+    names: HexNames,
+    checksize: usize,
+    check_read: C,
+    check_write: C,
+    mode: WriteMode,
+    policy: FullPolicy,
+) -> Result<impl RingBuffer, Event>
+where
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    let check_write = Arc::new(check_write);
+    let check_push = check_write.clone();
+    let get = read::read_handler_new_with_checksum(layout.path_builder(), checksize, check_read);
+    let unchecked = write::writer_unchecked_new_layout_with_checksum_mode(
+        layout.clone(),
+        move |dat: &[u8]| check_push(dat),
+        mode,
+    );
+    let overwriter = write::writer_unchecked_new_layout_with_checksum_mode(
+        layout.clone(),
+        move |dat: &[u8]| check_write(dat),
+        mode,
+    );
+    ring_buffer_impl_layout_new_with_writers(
+        layout, names, get, unchecked, overwriter, mode, policy,
+    )
+}
+
+/// Creates checked random ring buffer impl which uses the layout and `ItemFormat`.
+///
+/// Items will be stored with a header(see `header::Header`).
+/// The format checker computes checksum of both read and write; the checksum size comes from the checker.
+///
+/// # Arguments
+/// - layout: Directory layout of buffer files(flat or sharded).
+/// - names: Naming scheme(capacity and name width).
+/// - format: Checksum, codec, envelope and item formats to accept on read.
+/// - ttl: Expiry rules(the clock also stamps the creation time of items).
+/// - mode: Strategy to write an item file.
+/// - policy: Strategy to push an item into a full buffer.
+pub fn ring_buffer_impl_layout_new_with_format<K, D, E, T>(
+    layout: Layout,
+    names: HexNames,
+    format: ItemFormat<K, D, E>,
+    ttl: Ttl<T>,
+    mode: WriteMode,
+    policy: FullPolicy,
+) -> Result<impl RingBuffer, Event>
+where
+    K: Checksum + Clone,
+    D: Codec + Clone,
+    E: Envelope + Clone,
+    T: Clock + Clone,
+{
+    let clock: T = ttl.clock().clone();
+    let unchecked = write::writer_unchecked_new_layout_with_format_clock(
+        layout.clone(),
+        format.clone(),
+        mode,
+        clock.clone(),
+    );
+    let overwriter = write::writer_unchecked_new_layout_with_format_clock(
+        layout.clone(),
+        format.clone(),
+        mode,
+        clock,
+    );
+    let get = read::read_handler_new_with_format(layout.path_builder(), format, ttl);
+    ring_buffer_impl_layout_new_with_writers(
+        layout, names, get, unchecked, overwriter, mode, policy,
+    )
+}
+
+/// Creates default checked random ring buffer impl which uses the naming scheme and full policy.
+///
+/// Orphaned temporary files(atomic writes) and stale slot claims will be removed on open and on `Request::Vacuum`.
+/// The oldest item(modified time) will be overwritten if the policy is `OverwriteOldest`.
 ///
 /// # Arguments
 /// - dirname: Path to read/write buffer files.
+/// - names: Naming scheme(capacity and name width).
 /// - checksize: Checksum byte length.
 /// - check_read:  Computes checksum.
 /// - check_write:  Computes checksum(use same closure for read).
-pub fn ring_buffer_impl_u8_new_default_with_checksum<P, C>(
+/// - mode: Strategy to write an item file.
+/// - policy: Strategy to push an item into a full buffer.
+pub fn ring_buffer_impl_hex_new_with_policy<P, C>(
     dirname: P,
+    names: HexNames,
     checksize: usize,
     check_read: C,
     check_write: C,
+    mode: WriteMode,
+    policy: FullPolicy,
 ) -> Result<impl RingBuffer, Event>
 where
     P: AsRef<Path>,
     C: Fn(&[u8]) -> Vec<u8>,
 {
-    let p: &Path = dirname.as_ref();
+    ring_buffer_impl_layout_new_with_policy(
+        Layout::flat(dirname),
+        names,
+        checksize,
+        check_read,
+        check_write,
+        mode,
+        policy,
+    )
+}
 
-    let get = read::read_handler_new_default_with_checksum(p.to_path_buf(), checksize, check_read);
-    let del = crate::del::del_handler_new_default(p.to_path_buf());
-    let list = crate::list::list_request_handler_new_default(
-        crate::list::u::list_names_u8_all_new(),
-        p.to_path_buf(),
-    );
+/// Creates default checked random ring buffer impl which uses u8 names and full policy.
+///
+/// # Arguments
+/// - dirname: Path to read/write buffer files.
+/// - checksize: Checksum byte length.
+/// - check_read:  Computes checksum.
+/// - check_write:  Computes checksum(use same closure for read).
+/// - mode: Strategy to write an item file.
+/// - policy: Strategy to push an item into a full buffer.
+pub fn ring_buffer_impl_u8_new_default_with_policy<P, C>(
+    dirname: P,
+    checksize: usize,
+    check_read: C,
+    check_write: C,
+    mode: WriteMode,
+    policy: FullPolicy,
+) -> Result<impl RingBuffer, Event>
+where
+    P: AsRef<Path>,
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    ring_buffer_impl_hex_new_with_policy(
+        dirname,
+        HexNames::u8(),
+        checksize,
+        check_read,
+        check_write,
+        mode,
+        policy,
+    )
+}
 
-    let get_name = next::u::next_random_u8_new_from_path_default()?;
-    let push = crate::push::push_handler_new_unmanaged_default_with_checksum(
-        get_name,
-        p.to_path_buf(),
+/// Creates default checked random ring buffer impl which uses u8 names and write mode.
+///
+/// Orphaned temporary files(atomic writes) and stale slot claims will be removed on open and on `Request::Vacuum`.
+///
+/// # Arguments
+/// - dirname: Path to read/write buffer files.
+/// - checksize: Checksum byte length.
+/// - check_read:  Computes checksum.
+/// - check_write:  Computes checksum(use same closure for read).
+/// - mode: Strategy to write an item file.
+pub fn ring_buffer_impl_u8_new_default_with_checksum_mode<P, C>(
+    dirname: P,
+    checksize: usize,
+    check_read: C,
+    check_write: C,
+    mode: WriteMode,
+) -> Result<impl RingBuffer, Event>
+where
+    P: AsRef<Path>,
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    ring_buffer_impl_u8_new_default_with_policy(
+        dirname,
+        checksize,
+        check_read,
         check_write,
-    );
+        mode,
+        FullPolicy::Reject,
+    )
+}
+
+fn ring_buffer_impl_layout_new_fifo_with_writer<G, W>(
+    layout: Layout,
+    names: HexNames,
+    get: G,
+    write: W,
+    policy: FullPolicy,
+) -> Result<impl RingBuffer, Event>
+where
+    G: Fn(Name) -> Event,
+    W: Fn(NamedItem) -> Result<Name, Event>,
+{
+    let push = |_: Item| Event::BadRequest;
+    let inner = ring_buffer_impl_layout_new_with_push(&layout, names, get, push)?;
 
-    Ok(FsRingBuffer {
-        get,
-        del,
-        push,
-        list,
+    let empty_checker = empty::name2empty_fs_new(layout.path_builder());
+    let is_empty = move |n: &Name| empty_checker(n.clone());
+
+    let capacity: u64 = names.capacity();
+    let cursor: Cursor = cursor::cursor_loader_new_default(layout.dirname())()?;
+    (cursor.len() <= capacity)
+        .then_some(())
+        .ok_or_else(|| Event::UnexpectedError(format!("Invalid cursor: {:#?}", cursor)))?;
+
+    Ok(SeqRingBuffer {
+        inner,
+        write,
+        is_empty,
+        seq2name: move |u: u64| names.slot2name(u),
+        save: cursor::cursor_saver_new_default(layout.dirname().to_path_buf()),
+        cursor,
+        capacity,
+        policy,
     })
 }
 
+/// Creates checked sequential(FIFO) ring buffer impl which uses the layout and naming scheme.
+///
+/// The head/tail cursor will be stored in the buffer directory(`cursor::CURSOR_NAME`).
+/// Items will be stored without a header(use `ring_buffer_impl_layout_new_fifo_with_format`).
+///
+/// # Arguments
+/// - layout: Directory layout of buffer files(flat or sharded).
+/// - names: Naming scheme(capacity and name width).
+/// - checksize: Checksum byte length.
+/// - check_read:  Computes checksum.
+/// - check_write:  Computes checksum(use same closure for read).
+/// - mode: Strategy to write an item file.
+/// - policy: Strategy to push an item into a full buffer.
+pub fn ring_buffer_impl_layout_new_fifo_with_policy<C>(
+    layout: Layout,
+    names: HexNames,
+    checksize: usize,
+    check_read: C,
+    check_write: C,
+    mode: WriteMode,
+    policy: FullPolicy,
+) -> Result<impl RingBuffer, Event>
+where
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    let get = read::read_handler_new_with_checksum(layout.path_builder(), checksize, check_read);
+    let write =
+        write::writer_unchecked_new_layout_with_checksum_mode(layout.clone(), check_write, mode);
+    ring_buffer_impl_layout_new_fifo_with_writer(layout, names, get, write, policy)
+}
+
+/// Creates checked sequential(FIFO) ring buffer impl which uses the layout and `ItemFormat`.
+///
+/// Items will be stored with a header(see `header::Header`).
+///
+/// # Arguments
+/// - layout: Directory layout of buffer files(flat or sharded).
+/// - names: Naming scheme(capacity and name width).
+/// - format: Checksum, codec, envelope and item formats to accept on read.
+/// - ttl: Expiry rules(the clock also stamps the creation time of items).
+/// - mode: Strategy to write an item file.
+/// - policy: Strategy to push an item into a full buffer.
+pub fn ring_buffer_impl_layout_new_fifo_with_format<K, D, E, T>(
+    layout: Layout,
+    names: HexNames,
+    format: ItemFormat<K, D, E>,
+    ttl: Ttl<T>,
+    mode: WriteMode,
+    policy: FullPolicy,
+) -> Result<impl RingBuffer, Event>
+where
+    K: Checksum + Clone,
+    D: Codec + Clone,
+    E: Envelope + Clone,
+    T: Clock + Clone,
+{
+    let write = write::writer_unchecked_new_layout_with_format_clock(
+        layout.clone(),
+        format.clone(),
+        mode,
+        ttl.clock().clone(),
+    );
+    let get = read::read_handler_new_with_format(layout.path_builder(), format, ttl);
+    ring_buffer_impl_layout_new_fifo_with_writer(layout, names, get, write, policy)
+}
+
+/// Creates default checked sequential(FIFO) ring buffer impl which uses the naming scheme.
+///
+/// The head/tail cursor will be stored in the buffer directory(`cursor::CURSOR_NAME`).
+///
+/// # Arguments
+/// - dirname: Path to read/write buffer files.
+/// - names: Naming scheme(capacity and name width).
+/// - checksize: Checksum byte length.
+/// - check_read:  Computes checksum.
+/// - check_write:  Computes checksum(use same closure for read).
+/// - mode: Strategy to write an item file.
+/// - policy: Strategy to push an item into a full buffer.
+pub fn ring_buffer_impl_hex_new_fifo_with_policy<P, C>(
+    dirname: P,
+    names: HexNames,
+    checksize: usize,
+    check_read: C,
+    check_write: C,
+    mode: WriteMode,
+    policy: FullPolicy,
+) -> Result<impl RingBuffer, Event>
+where
+    P: AsRef<Path>,
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    ring_buffer_impl_layout_new_fifo_with_policy(
+        Layout::flat(dirname),
+        names,
+        checksize,
+        check_read,
+        check_write,
+        mode,
+        policy,
+    )
+}
+
+/// Creates default checked sequential(FIFO) ring buffer impl which uses u8 names and full policy.
+///
+/// # Arguments
+/// - dirname: Path to read/write buffer files.
+/// - checksize: Checksum byte length.
+/// - check_read:  Computes checksum.
+/// - check_write:  Computes checksum(use same closure for read).
+/// - mode: Strategy to write an item file.
+/// - policy: Strategy to push an item into a full buffer.
+pub fn ring_buffer_impl_u8_new_fifo_with_policy<P, C>(
+    dirname: P,
+    checksize: usize,
+    check_read: C,
+    check_write: C,
+    mode: WriteMode,
+    policy: FullPolicy,
+) -> Result<impl RingBuffer, Event>
+where
+    P: AsRef<Path>,
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    ring_buffer_impl_hex_new_fifo_with_policy(
+        dirname,
+        HexNames::u8(),
+        checksize,
+        check_read,
+        check_write,
+        mode,
+        policy,
+    )
+}
+
+/// Creates default checked sequential(FIFO) ring buffer impl which uses u8 names.
+///
+/// The head/tail cursor will be stored in the buffer directory(`cursor::CURSOR_NAME`).
+///
+/// # Arguments
+/// - dirname: Path to read/write buffer files.
+/// - checksize: Checksum byte length.
+/// - check_read:  Computes checksum.
+/// - check_write:  Computes checksum(use same closure for read).
+/// - mode: Strategy to write an item file.
+pub fn ring_buffer_impl_u8_new_fifo_with_checksum_mode<P, C>(
+    dirname: P,
+    checksize: usize,
+    check_read: C,
+    check_write: C,
+    mode: WriteMode,
+) -> Result<impl RingBuffer, Event>
+where
+    P: AsRef<Path>,
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    ring_buffer_impl_u8_new_fifo_with_policy(
+        dirname,
+        checksize,
+        check_read,
+        check_write,
+        mode,
+        FullPolicy::Reject,
+    )
+}
+
+/// Creates default sequential(FIFO) ring buffer impl which uses u8 names.
+pub fn ring_buffer_impl_u8_new_fifo<P>(dirname: P) -> Result<impl RingBuffer, Event>
+where
+    P: AsRef<Path>,
+{
+    ring_buffer_impl_u8_new_fifo_with_checksum_mode(
+        dirname,
+        0,
+        checksum_nop,
+        checksum_nop,
+        WriteMode::InPlace,
+    )
+}
+
+/// Creates default checked random ring buffer impl which uses u8 names.
+///
+/// # Arguments
+/// - dirname: Path to read/write buffer files.
+/// - checksize: Checksum byte length.
+/// - check_read:  Computes checksum.
+/// - check_write:  Computes checksum(use same closure for read).
+pub fn ring_buffer_impl_u8_new_default_with_checksum<P, C>(
+    dirname: P,
+    checksize: usize,
+    check_read: C,
+    check_write: C,
+) -> Result<impl RingBuffer, Event>
+where
+    P: AsRef<Path>,
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    ring_buffer_impl_u8_new_default_with_checksum_mode(
+        dirname,
+        checksize,
+        check_read,
+        check_write,
+        WriteMode::InPlace,
+    )
+}
+
 fn checksum_nop(_: &[u8]) -> Vec<u8> {
     vec![]
 }
@@ -83,27 +611,688 @@ where
     Ok(crate::ring_buffer_new(rb))
 }
 
-/// Creates default random ring buffer which uses u8 names.
-pub fn ring_buffer_u8_new_default<P>(dirname: P) -> Result<impl FnMut(Request) -> Event, Event>
+/// Creates default checked random ring buffer which uses u8 names and write mode.
+///
+/// # Arguments
+/// - dirname: Path to read/write buffer files.
+/// - checksize: Checksum byte length.
+/// - check_read:  Computes checksum.
+/// - check_write:  Computes checksum(use same closure for read).
+/// - mode: Strategy to write an item file.
+pub fn ring_buffer_u8_new_default_with_checksum_mode<P, C>(
+    dirname: P,
+    checksize: usize,
+    check_read: C,
+    check_write: C,
+    mode: WriteMode,
+) -> Result<impl FnMut(Request) -> Event, Event>
 where
     P: AsRef<Path>,
+    C: Fn(&[u8]) -> Vec<u8>,
 {
-    let rb = ring_buffer_impl_u8_new_default(dirname)?;
+    let rb = ring_buffer_impl_u8_new_default_with_checksum_mode(
+        dirname,
+        checksize,
+        check_read,
+        check_write,
+        mode,
+    )?;
     Ok(crate::ring_buffer_new(rb))
 }
 
-#[cfg(test)]
-mod test_buf {
-
-    mod ring_buffer_u8_new_default {
-
-        use std::io::ErrorKind;
-        use std::path::Path;
+/// Creates default checked random ring buffer which uses u8 names and full policy.
+///
+/// # Arguments
+/// - dirname: Path to read/write buffer files.
+/// - checksize: Checksum byte length.
+/// - check_read:  Computes checksum.
+/// - check_write:  Computes checksum(use same closure for read).
+/// - mode: Strategy to write an item file.
+/// - policy: Strategy to push an item into a full buffer.
+pub fn ring_buffer_u8_new_default_with_policy<P, C>(
+    dirname: P,
+    checksize: usize,
+    check_read: C,
+    check_write: C,
+    mode: WriteMode,
+    policy: FullPolicy,
+) -> Result<impl FnMut(Request) -> Event, Event>
+where
+    P: AsRef<Path>,
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    let rb = ring_buffer_impl_u8_new_default_with_policy(
+        dirname,
+        checksize,
+        check_read,
+        check_write,
+        mode,
+        policy,
+    )?;
+    Ok(crate::ring_buffer_new(rb))
+}
 
-        use crate::evt::Event;
-        use crate::item::{Item, Name};
-        use crate::request::Request;
-        use crate::u::buf;
+/// Creates default checked sequential(FIFO) ring buffer which uses u8 names and full policy.
+///
+/// # Arguments
+/// - dirname: Path to read/write buffer files.
+/// - checksize: Checksum byte length.
+/// - check_read:  Computes checksum.
+/// - check_write:  Computes checksum(use same closure for read).
+/// - mode: Strategy to write an item file.
+/// - policy: Strategy to push an item into a full buffer.
+pub fn ring_buffer_u8_new_fifo_with_policy<P, C>(
+    dirname: P,
+    checksize: usize,
+    check_read: C,
+    check_write: C,
+    mode: WriteMode,
+    policy: FullPolicy,
+) -> Result<impl FnMut(Request) -> Event, Event>
+where
+    P: AsRef<Path>,
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    let rb = ring_buffer_impl_u8_new_fifo_with_policy(
+        dirname,
+        checksize,
+        check_read,
+        check_write,
+        mode,
+        policy,
+    )?;
+    Ok(crate::ring_buffer_new(rb))
+}
+
+/// Creates default checked sequential(FIFO) ring buffer which uses u8 names.
+///
+/// # Arguments
+/// - dirname: Path to read/write buffer files.
+/// - checksize: Checksum byte length.
+/// - check_read:  Computes checksum.
+/// - check_write:  Computes checksum(use same closure for read).
+/// - mode: Strategy to write an item file.
+pub fn ring_buffer_u8_new_fifo_with_checksum_mode<P, C>(
+    dirname: P,
+    checksize: usize,
+    check_read: C,
+    check_write: C,
+    mode: WriteMode,
+) -> Result<impl FnMut(Request) -> Event, Event>
+where
+    P: AsRef<Path>,
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    let rb = ring_buffer_impl_u8_new_fifo_with_checksum_mode(
+        dirname,
+        checksize,
+        check_read,
+        check_write,
+        mode,
+    )?;
+    Ok(crate::ring_buffer_new(rb))
+}
+
+/// Creates default sequential(FIFO) ring buffer which uses u8 names.
+pub fn ring_buffer_u8_new_fifo<P>(dirname: P) -> Result<impl FnMut(Request) -> Event, Event>
+where
+    P: AsRef<Path>,
+{
+    let rb = ring_buffer_impl_u8_new_fifo(dirname)?;
+    Ok(crate::ring_buffer_new(rb))
+}
+
+/// Creates checked random ring buffer which uses the layout, naming scheme and full policy.
+///
+/// # Arguments
+/// - layout: Directory layout of buffer files(flat or sharded).
+/// - names: Naming scheme(capacity and name width).
+/// - checksize: Checksum byte length.
+/// - check_read:  Computes checksum.
+/// - check_write:  Computes checksum(use same closure for read).
+/// - mode: Strategy to write an item file.
+/// - policy: Strategy to push an item into a full buffer.
+pub fn ring_buffer_layout_new_with_policy<C>(
+    layout: Layout,
+    names: HexNames,
+    checksize: usize,
+    check_read: C,
+    check_write: C,
+    mode: WriteMode,
+    policy: FullPolicy,
+) -> Result<impl FnMut(Request) -> Event, Event>
+where
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    let rb = ring_buffer_impl_layout_new_with_policy(
+        layout,
+        names,
+        checksize,
+        check_read,
+        check_write,
+        mode,
+        policy,
+    )?;
+    Ok(crate::ring_buffer_new(rb))
+}
+
+/// Creates checked sequential(FIFO) ring buffer which uses the layout and naming scheme.
+///
+/// # Arguments
+/// - layout: Directory layout of buffer files(flat or sharded).
+/// - names: Naming scheme(capacity and name width).
+/// - checksize: Checksum byte length.
+/// - check_read:  Computes checksum.
+/// - check_write:  Computes checksum(use same closure for read).
+/// - mode: Strategy to write an item file.
+/// - policy: Strategy to push an item into a full buffer.
+pub fn ring_buffer_layout_new_fifo_with_policy<C>(
+    layout: Layout,
+    names: HexNames,
+    checksize: usize,
+    check_read: C,
+    check_write: C,
+    mode: WriteMode,
+    policy: FullPolicy,
+) -> Result<impl FnMut(Request) -> Event, Event>
+where
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    let rb = ring_buffer_impl_layout_new_fifo_with_policy(
+        layout,
+        names,
+        checksize,
+        check_read,
+        check_write,
+        mode,
+        policy,
+    )?;
+    Ok(crate::ring_buffer_new(rb))
+}
+
+/// Creates checked random ring buffer which uses the layout and `ItemFormat`.
+///
+/// # Arguments
+/// - layout: Directory layout of buffer files(flat or sharded).
+/// - names: Naming scheme(capacity and name width).
+/// - format: Checksum, codec, envelope and item formats to accept on read.
+/// - ttl: Expiry rules(the clock also stamps the creation time of items).
+/// - mode: Strategy to write an item file.
+/// - policy: Strategy to push an item into a full buffer.
+pub fn ring_buffer_layout_new_with_format<K, D, E, T>(
+    layout: Layout,
+    names: HexNames,
+    format: ItemFormat<K, D, E>,
+    ttl: Ttl<T>,
+    mode: WriteMode,
+    policy: FullPolicy,
+) -> Result<impl FnMut(Request) -> Event, Event>
+where
+    K: Checksum + Clone,
+    D: Codec + Clone,
+    E: Envelope + Clone,
+    T: Clock + Clone,
+{
+    let rb = ring_buffer_impl_layout_new_with_format(layout, names, format, ttl, mode, policy)?;
+    Ok(crate::ring_buffer_new(rb))
+}
+
+/// Creates checked sequential(FIFO) ring buffer which uses the layout and `ItemFormat`.
+///
+/// # Arguments
+/// - layout: Directory layout of buffer files(flat or sharded).
+/// - names: Naming scheme(capacity and name width).
+/// - format: Checksum, codec, envelope and item formats to accept on read.
+/// - ttl: Expiry rules(the clock also stamps the creation time of items).
+/// - mode: Strategy to write an item file.
+/// - policy: Strategy to push an item into a full buffer.
+pub fn ring_buffer_layout_new_fifo_with_format<K, D, E, T>(
+    layout: Layout,
+    names: HexNames,
+    format: ItemFormat<K, D, E>,
+    ttl: Ttl<T>,
+    mode: WriteMode,
+    policy: FullPolicy,
+) -> Result<impl FnMut(Request) -> Event, Event>
+where
+    K: Checksum + Clone,
+    D: Codec + Clone,
+    E: Envelope + Clone,
+    T: Clock + Clone,
+{
+    let rb =
+        ring_buffer_impl_layout_new_fifo_with_format(layout, names, format, ttl, mode, policy)?;
+    Ok(crate::ring_buffer_new(rb))
+}
+
+fn ring_buffer_impl_quota_new<R>(
+    inner: R,
+    layout: &Layout,
+    names: HexNames,
+    quota: Quota,
+    policy: FullPolicy,
+) -> impl RingBuffer
+where
+    R: RingBuffer,
+{
+    let scan = list::scanner_new_layout(layout.clone(), names);
+    QuotaRingBuffer {
+        inner,
+        quota,
+        usage: move || scan().map(|found: Vec<(Name, u64)>| found.iter().map(|p| p.1).sum()),
+        oldest: list::oldest_finder_new(
+            list::list_scanned_new(layout.clone(), names),
+            list::modified_getter_new(layout.path_builder()),
+        ),
+        size: quota::size_getter_new(layout.path_builder()),
+        policy,
+    }
+}
+
+/// Creates checked random ring buffer which enforces the byte quota(see `quota::Quota`).
+///
+/// Pushes over the quota will be rejected(`Event::QuotaExceeded`), dropped or make room by
+/// removing the oldest items(modified time) depending on the policy.
+/// Items larger than the max item size will be rejected(`Event::ItemTooLarge`); files over
+/// `Quota::max_file` will be refused by every read(get, pop, claim, ...).
+///
+/// # Arguments
+/// - layout: Directory layout of buffer files(flat or sharded).
+/// - names: Naming scheme(capacity and name width).
+/// - format: Checksum, codec, envelope and item formats to accept on read.
+/// - ttl: Expiry rules(the clock also stamps the creation time of items).
+/// - mode: Strategy to write an item file.
+/// - policy: Strategy to push an item into a full buffer(slots or bytes).
+/// - quota: Byte limits.
+pub fn ring_buffer_layout_new_with_format_quota<K, D, E, T>(
+    layout: Layout,
+    names: HexNames,
+    format: ItemFormat<K, D, E>,
+    ttl: Ttl<T>,
+    mode: WriteMode,
+    policy: FullPolicy,
+    quota: Quota,
+) -> Result<impl FnMut(Request) -> Event, Event>
+where
+    K: Checksum + Clone,
+    D: Codec + Clone,
+    E: Envelope + Clone,
+    T: Clock + Clone,
+{
+    let format = ItemFormat {
+        max_file: quota.max_file(),
+        ..format
+    };
+    let inner =
+        ring_buffer_impl_layout_new_with_format(layout.clone(), names, format, ttl, mode, policy)?;
+    let rb = ring_buffer_impl_quota_new(inner, &layout, names, quota, policy);
+    Ok(crate::ring_buffer_new(rb))
+}
+
+/// Creates checked sequential(FIFO) ring buffer which enforces the byte quota(see `quota::Quota`).
+///
+/// # Arguments
+/// - layout: Directory layout of buffer files(flat or sharded).
+/// - names: Naming scheme(capacity and name width).
+/// - format: Checksum, codec, envelope and item formats to accept on read.
+/// - ttl: Expiry rules(the clock also stamps the creation time of items).
+/// - mode: Strategy to write an item file.
+/// - policy: Strategy to push an item into a full buffer(slots or bytes).
+/// - quota: Byte limits.
+pub fn ring_buffer_layout_new_fifo_with_format_quota<K, D, E, T>(
+    layout: Layout,
+    names: HexNames,
+    format: ItemFormat<K, D, E>,
+    ttl: Ttl<T>,
+    mode: WriteMode,
+    policy: FullPolicy,
+    quota: Quota,
+) -> Result<impl FnMut(Request) -> Event, Event>
+where
+    K: Checksum + Clone,
+    D: Codec + Clone,
+    E: Envelope + Clone,
+    T: Clock + Clone,
+{
+    let format = ItemFormat {
+        max_file: quota.max_file(),
+        ..format
+    };
+    let inner = ring_buffer_impl_layout_new_fifo_with_format(
+        layout.clone(),
+        names,
+        format,
+        ttl,
+        mode,
+        policy,
+    )?;
+    let rb = ring_buffer_impl_quota_new(inner, &layout, names, quota, policy);
+    Ok(crate::ring_buffer_new(rb))
+}
+
+/// Creates checked random ring buffer for many consumers(`Request::Claim`/`Ack`/`Nack`).
+///
+/// Leases of claimed items are kept in files beside items and survive restarts.
+///
+/// # Arguments
+/// - layout: Directory layout of buffer files(flat or sharded).
+/// - names: Naming scheme(capacity and name width).
+/// - format: Checksum, codec, envelope and item formats to accept on read.
+/// - ttl: Expiry rules(the clock also checks the visibility timeout).
+/// - mode: Strategy to write an item file.
+/// - policy: Strategy to push an item into a full buffer.
+/// - visibility: Claimed items will be visible to other consumers again after this timeout.
+pub fn ring_buffer_layout_new_consumer_with_format<K, D, E, T>(
+    layout: Layout,
+    names: HexNames,
+    format: ItemFormat<K, D, E>,
+    ttl: Ttl<T>,
+    mode: WriteMode,
+    policy: FullPolicy,
+    visibility: Duration,
+) -> Result<impl FnMut(Request) -> Event, Event>
+where
+    K: Checksum + Clone,
+    D: Codec + Clone,
+    E: Envelope + Clone,
+    T: Clock + Clone,
+{
+    let leases = Leases::new(layout.path_builder(), ttl.clock().clone(), visibility);
+    ring_buffer_layout_new_consumer_with_leases(layout, names, format, ttl, mode, policy, leases)
+}
+
+/// Creates checked random ring buffer for many consumers with the leases.
+///
+/// Use `Leases::with_owner` to keep the consumer id across restarts(unacked leases of the
+/// previous run can be acked/nacked).
+///
+/// # Arguments
+/// - layout: Directory layout of buffer files(flat or sharded).
+/// - names: Naming scheme(capacity and name width).
+/// - format: Checksum, codec, envelope and item formats to accept on read.
+/// - ttl: Expiry rules.
+/// - mode: Strategy to write an item file.
+/// - policy: Strategy to push an item into a full buffer.
+/// - leases: Leases of claimed items(must use the path builder of the layout).
+pub fn ring_buffer_layout_new_consumer_with_leases<K, D, E, T, B, C>(
+    layout: Layout,
+    names: HexNames,
+    format: ItemFormat<K, D, E>,
+    ttl: Ttl<T>,
+    mode: WriteMode,
+    policy: FullPolicy,
+    leases: Leases<B, C>,
+) -> Result<impl FnMut(Request) -> Event, Event>
+where
+    K: Checksum + Clone,
+    D: Codec + Clone,
+    E: Envelope + Clone,
+    T: Clock + Clone,
+    B: Fn(Name) -> PathBuf,
+    C: Clock,
+{
+    let inner = ring_buffer_impl_layout_new_with_format(layout, names, format, ttl, mode, policy)?;
+    Ok(crate::ring_buffer_new(ConsumerRingBuffer { inner, leases }))
+}
+
+fn ring_buffer_shared_layout_new_with_writer<G, W>(
+    layout: Layout,
+    names: HexNames,
+    get: G,
+    unchecked: W,
+    mode: WriteMode,
+) -> Result<impl Fn(Request) -> Event + Clone + Send + Sync, Event>
+where
+    G: Fn(Name) -> Event + Send + Sync,
+    W: Fn(NamedItem) -> Result<Name, Event> + Send + Sync,
+{
+    let get = name_checked_new(names, get);
+    let del = name_checked_new(names, crate::del::del_handler_new(layout.path_builder()));
+    let list = list::list_request_handler_new_scanned(layout.clone(), names);
+    let get_name = next::u::next_random_hex_new_from_path_default(names)?;
+    let empty_checker = empty::name2empty_fs_new(layout.path_builder());
+    let wtr = write::writer_claimed_new_layout(
+        layout.clone(),
+        unchecked,
+        move |n: &Name| empty_checker(n.clone()),
+        mode,
+    );
+
+    let vacuum = tmp::orphan_cleaner_new_default(layout.dirname().to_path_buf());
+    vacuum()?;
+
+    let used = list::list_scanned_new(layout.clone(), names);
+    let capacity: u64 = names.capacity();
+    let is_full = move || Ok(capacity <= used()?.len() as u64);
+
+    let rb = SharedRingBuffer::new(get, del, get_name, wtr, list, is_full, vacuum);
+    Ok(shared::ring_buffer_new(rb))
+}
+
+/// Creates checked random ring buffer which can be shared between threads.
+///
+/// The handler can be cloned; clones share the same buffer.
+/// Gets and lists run in parallel; mutations on the same slot will be serialized.
+/// A push into a full buffer will be rejected(`Event::TooManyItemsAlready`).
+///
+/// # Arguments
+/// - layout: Directory layout of buffer files(flat or sharded).
+/// - names: Naming scheme(capacity and name width).
+/// - checksize: Checksum byte length.
+/// - check_read:  Computes checksum.
+/// - check_write:  Computes checksum(use same closure for read).
+/// - mode: Strategy to write an item file.
+pub fn ring_buffer_shared_layout_new_with_checksum_mode<C>(
+    layout: Layout,
+    names: HexNames,
+    checksize: usize,
+    check_read: C,
+    check_write: C,
+    mode: WriteMode,
+) -> Result<impl Fn(Request) -> Event + Clone + Send + Sync, Event>
+where
+    C: Fn(&[u8]) -> Vec<u8> + Send + Sync,
+{
+    let get = read::read_handler_new_with_checksum(layout.path_builder(), checksize, check_read);
+    let unchecked =
+        write::writer_unchecked_new_layout_with_checksum_mode(layout.clone(), check_write, mode);
+    ring_buffer_shared_layout_new_with_writer(layout, names, get, unchecked, mode)
+}
+
+/// Creates checked random ring buffer which uses `ItemFormat` and can be shared between threads.
+///
+/// See `ring_buffer_shared_layout_new_with_checksum_mode`.
+///
+/// # Arguments
+/// - layout: Directory layout of buffer files(flat or sharded).
+/// - names: Naming scheme(capacity and name width).
+/// - format: Checksum, codec, envelope and item formats to accept on read.
+/// - ttl: Expiry rules(the clock also stamps the creation time of items).
+/// - mode: Strategy to write an item file.
+pub fn ring_buffer_shared_layout_new_with_format<K, D, E, T>(
+    layout: Layout,
+    names: HexNames,
+    format: ItemFormat<K, D, E>,
+    ttl: Ttl<T>,
+    mode: WriteMode,
+) -> Result<impl Fn(Request) -> Event + Clone + Send + Sync, Event>
+where
+    K: Checksum + Clone + Send + Sync,
+    D: Codec + Clone + Send + Sync,
+    E: Envelope + Clone + Send + Sync,
+    T: Clock + Clone + Send + Sync,
+{
+    let unchecked = write::writer_unchecked_new_layout_with_format_clock(
+        layout.clone(),
+        format.clone(),
+        mode,
+        ttl.clock().clone(),
+    );
+    let get = read::read_handler_new_with_format(layout.path_builder(), format, ttl);
+    ring_buffer_shared_layout_new_with_writer(layout, names, get, unchecked, mode)
+}
+
+/// Creates default checked random ring buffer which uses the naming scheme and full policy.
+///
+/// # Arguments
+/// - dirname: Path to read/write buffer files.
+/// - names: Naming scheme(capacity and name width).
+/// - checksize: Checksum byte length.
+/// - check_read:  Computes checksum.
+/// - check_write:  Computes checksum(use same closure for read).
+/// - mode: Strategy to write an item file.
+/// - policy: Strategy to push an item into a full buffer.
+pub fn ring_buffer_hex_new_with_policy<P, C>(
+    dirname: P,
+    names: HexNames,
+    checksize: usize,
+    check_read: C,
+    check_write: C,
+    mode: WriteMode,
+    policy: FullPolicy,
+) -> Result<impl FnMut(Request) -> Event, Event>
+where
+    P: AsRef<Path>,
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    let rb = ring_buffer_impl_hex_new_with_policy(
+        dirname,
+        names,
+        checksize,
+        check_read,
+        check_write,
+        mode,
+        policy,
+    )?;
+    Ok(crate::ring_buffer_new(rb))
+}
+
+/// Creates default checked sequential(FIFO) ring buffer which uses the naming scheme.
+///
+/// # Arguments
+/// - dirname: Path to read/write buffer files.
+/// - names: Naming scheme(capacity and name width).
+/// - checksize: Checksum byte length.
+/// - check_read:  Computes checksum.
+/// - check_write:  Computes checksum(use same closure for read).
+/// - mode: Strategy to write an item file.
+/// - policy: Strategy to push an item into a full buffer.
+pub fn ring_buffer_hex_new_fifo_with_policy<P, C>(
+    dirname: P,
+    names: HexNames,
+    checksize: usize,
+    check_read: C,
+    check_write: C,
+    mode: WriteMode,
+    policy: FullPolicy,
+) -> Result<impl FnMut(Request) -> Event, Event>
+where
+    P: AsRef<Path>,
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    let rb = ring_buffer_impl_hex_new_fifo_with_policy(
+        dirname,
+        names,
+        checksize,
+        check_read,
+        check_write,
+        mode,
+        policy,
+    )?;
+    Ok(crate::ring_buffer_new(rb))
+}
+
+/// Creates default random ring buffer which uses u16 names(65536 slots).
+pub fn ring_buffer_u16_new_default<P>(dirname: P) -> Result<impl FnMut(Request) -> Event, Event>
+where
+    P: AsRef<Path>,
+{
+    ring_buffer_hex_new_with_policy(
+        dirname,
+        HexNames::u16(),
+        0,
+        checksum_nop,
+        checksum_nop,
+        WriteMode::InPlace,
+        FullPolicy::Reject,
+    )
+}
+
+/// Creates default sequential(FIFO) ring buffer which uses u16 names(65536 slots).
+pub fn ring_buffer_u16_new_fifo<P>(dirname: P) -> Result<impl FnMut(Request) -> Event, Event>
+where
+    P: AsRef<Path>,
+{
+    ring_buffer_hex_new_fifo_with_policy(
+        dirname,
+        HexNames::u16(),
+        0,
+        checksum_nop,
+        checksum_nop,
+        WriteMode::InPlace,
+        FullPolicy::Reject,
+    )
+}
+
+/// Creates default random ring buffer which uses u32 names and the capacity.
+pub fn ring_buffer_u32_new_default<P>(
+    dirname: P,
+    capacity: u64,
+) -> Result<impl FnMut(Request) -> Event, Event>
+where
+    P: AsRef<Path>,
+{
+    ring_buffer_hex_new_with_policy(
+        dirname,
+        HexNames::u32().with_capacity(capacity)?,
+        0,
+        checksum_nop,
+        checksum_nop,
+        WriteMode::InPlace,
+        FullPolicy::Reject,
+    )
+}
+
+/// Creates default sequential(FIFO) ring buffer which uses u32 names and the capacity.
+pub fn ring_buffer_u32_new_fifo<P>(
+    dirname: P,
+    capacity: u64,
+) -> Result<impl FnMut(Request) -> Event, Event>
+where
+    P: AsRef<Path>,
+{
+    ring_buffer_hex_new_fifo_with_policy(
+        dirname,
+        HexNames::u32().with_capacity(capacity)?,
+        0,
+        checksum_nop,
+        checksum_nop,
+        WriteMode::InPlace,
+        FullPolicy::Reject,
+    )
+}
+
+/// Creates default random ring buffer which uses u8 names.
+pub fn ring_buffer_u8_new_default<P>(dirname: P) -> Result<impl FnMut(Request) -> Event, Event>
+where
+    P: AsRef<Path>,
+{
+    let rb = ring_buffer_impl_u8_new_default(dirname)?;
+    Ok(crate::ring_buffer_new(rb))
+}
+
+#[cfg(test)]
+mod test_buf {
+
+    mod ring_buffer_u8_new_default {
+
+        use std::io::ErrorKind;
+        use std::path::Path;
+
+        use crate::evt::Event;
+        use crate::item::{Item, Name};
+        use crate::request::Request;
+        use crate::u::buf;
 
         fn dir_clean<P>(p: P) -> Result<(), ErrorKind>
         where
@@ -127,7 +1316,8 @@ mod test_buf {
             std::fs::create_dir_all(&dirname).unwrap();
             let req: Request = Request::Push(Item::from(vec![]));
             let evt: Event = f(req);
-            assert_eq!(evt, Event::Success);
+            let n: Name = evt.try_into().unwrap();
+            assert!(u8::try_from(&n).is_ok());
         }
 
         #[test]
@@ -138,13 +1328,14 @@ mod test_buf {
             dir_clean(&dirname).unwrap();
             std::fs::create_dir_all(&dirname).unwrap();
             let evt: Event = f(Request::Push(Item::from(vec![0x42])));
-            assert_eq!(evt, Event::Success);
+            let pushed: Name = evt.try_into().unwrap();
 
             let lst: Event = f(Request::List);
             let mut i = std::fs::read_dir(&dirname).unwrap();
             let dirent = i.next().unwrap().unwrap();
             let name: String = dirent.file_name().into_string().unwrap();
             assert_eq!(lst, Event::NamesGot(vec![Name::from(name)]));
+            assert_eq!(lst, Event::NamesGot(vec![pushed]));
         }
 
         #[test]
@@ -155,7 +1346,7 @@ mod test_buf {
             dir_clean(&dirname).unwrap();
             std::fs::create_dir_all(&dirname).unwrap();
             let evt: Event = f(Request::Push(Item::from((b"299792458").as_slice())));
-            assert_eq!(evt, Event::Success);
+            assert!(matches!(evt, Event::ItemWrote(_)));
 
             let lst: Event = f(Request::List);
             let names: Vec<Name> = lst.try_into().unwrap();
@@ -174,7 +1365,7 @@ mod test_buf {
             dir_clean(&dirname).unwrap();
             std::fs::create_dir_all(&dirname).unwrap();
             let evt: Event = f(Request::Push(Item::from((b"299792458").as_slice())));
-            assert_eq!(evt, Event::Success);
+            assert!(matches!(evt, Event::ItemWrote(_)));
 
             let lst: Event = f(Request::List);
             let names: Vec<Name> = lst.try_into().unwrap();
@@ -186,6 +1377,1139 @@ mod test_buf {
             let rmv: Event = f(Request::Del(tgt));
             assert_eq!(rmv, Event::Success);
         }
+
+        #[test]
+        #[ignore]
+        fn test_traversal() {
+            let dirname = Path::new("./test.d/u/buf/ring_buffer_u8_new_default/traversal.d");
+            dir_clean(dirname).unwrap();
+            std::fs::create_dir_all(dirname.join("sub")).unwrap();
+            std::fs::write(dirname.join("outside"), b"keep").unwrap();
+            let mut f = buf::ring_buffer_u8_new_default(dirname.join("sub")).unwrap();
+
+            let outside: Name = Name::from("../outside");
+            assert_eq!(f(Request::Get(outside.clone())), Event::BadRequest);
+            assert_eq!(f(Request::Del(outside)), Event::BadRequest);
+            assert_eq!(
+                f(Request::Get(Name::from("/etc/passwd"))),
+                Event::BadRequest
+            );
+            assert_eq!(f(Request::Get(Name::from("100"))), Event::BadRequest);
+            assert_eq!(std::fs::read(dirname.join("outside")).unwrap(), b"keep");
+        }
+    }
+
+    mod ring_buffer_u8_new_default_with_checksum_mode {
+
+        use std::path::Path;
+
+        use crate::evt::Event;
+        use crate::item::{Item, Name};
+        use crate::request::Request;
+        use crate::tmp;
+        use crate::u::buf;
+        use crate::write::WriteMode;
+
+        #[test]
+        #[ignore]
+        fn test_atomic() {
+            let dirname =
+                Path::new("./test.d/u/buf/ring_buffer_u8_new_default_with_checksum_mode/atomic.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+            let orphan = dirname.join(".42.4294967295.tmp");
+            tmp::orphan_write(&orphan, b"half-written");
+
+            let chk = |_: &[u8]| b"cafef00d".to_vec();
+            let mut f = buf::ring_buffer_u8_new_default_with_checksum_mode(
+                dirname,
+                8,
+                chk,
+                chk,
+                WriteMode::Atomic,
+            )
+            .unwrap();
+            assert!(!orphan.exists());
+
+            let evt: Event = f(Request::Push(Item::from((b"299792458").as_slice())));
+            assert!(matches!(evt, Event::ItemWrote(_)));
+
+            let names: Vec<Name> = f(Request::List).try_into().unwrap();
+            let n: Name = names.into_iter().next().unwrap();
+            let i: Item = f(Request::Get(n)).try_into().unwrap();
+            assert_eq!(i, Item::from((b"299792458").as_slice()));
+
+            tmp::orphan_write(&orphan, b"half-written");
+            let evt: Event = f(Request::Vacuum);
+            assert_eq!(evt, Event::BrokenItemsRemoved(0));
+            assert!(!orphan.exists());
+        }
+    }
+
+    mod ring_buffer_u8_new_fifo {
+
+        use std::path::Path;
+
+        use crate::evt::Event;
+        use crate::item::{Item, Name};
+        use crate::request::Request;
+        use crate::u::buf;
+
+        #[test]
+        #[ignore]
+        fn test_fifo() {
+            let dirname = Path::new("./test.d/u/buf/ring_buffer_u8_new_fifo/fifo.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let mut f = buf::ring_buffer_u8_new_fifo(dirname).unwrap();
+            let evt: Event = f(Request::Push(Item::from(b"first".as_slice())));
+            assert_eq!(evt, Event::ItemWrote(Name::from("00")));
+            let evt: Event = f(Request::Push(Item::from(b"second".as_slice())));
+            assert_eq!(evt, Event::ItemWrote(Name::from("01")));
+
+            let peek: Item = f(Request::PeekOldest).try_into().unwrap();
+            assert_eq!(peek, Item::from(b"first".as_slice()));
+            let pop: Item = f(Request::Pop).try_into().unwrap();
+            assert_eq!(pop, Item::from(b"first".as_slice()));
+            drop(f);
+
+            let mut restarted = buf::ring_buffer_u8_new_fifo(dirname).unwrap();
+            let evt: Event = restarted(Request::Push(Item::from(b"third".as_slice())));
+            assert_eq!(evt, Event::ItemWrote(Name::from("02")));
+            let pop: Item = restarted(Request::Pop).try_into().unwrap();
+            assert_eq!(pop, Item::from(b"second".as_slice()));
+            let pop: Item = restarted(Request::Pop).try_into().unwrap();
+            assert_eq!(pop, Item::from(b"third".as_slice()));
+            assert_eq!(restarted(Request::Pop), Event::NoEntry(Name::from("03")));
+        }
+    }
+
+    mod ring_buffer_u8_new_default_with_policy {
+
+        use std::path::Path;
+
+        use crate::evt::Event;
+        use crate::item::{Item, Name};
+        use crate::push::FullPolicy;
+        use crate::request::Request;
+        use crate::u::buf;
+        use crate::write::WriteMode;
+
+        #[test]
+        #[ignore]
+        fn test_overwrite() {
+            let dirname = Path::new("./test.d/u/buf/ring_buffer_u8_new_default_with_policy/full.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+            for u in 0..=255u8 {
+                std::fs::write(dirname.join(format!("{:02x}", u)), b"old").unwrap();
+            }
+            let oldest = std::fs::File::options()
+                .write(true)
+                .open(dirname.join("42"))
+                .unwrap();
+            oldest
+                .set_modified(std::time::SystemTime::UNIX_EPOCH)
+                .unwrap();
+            drop(oldest);
+
+            let chk = |_: &[u8]| vec![];
+            let mut f = buf::ring_buffer_u8_new_default_with_policy(
+                dirname,
+                0,
+                chk,
+                chk,
+                WriteMode::Atomic,
+                FullPolicy::OverwriteOldest,
+            )
+            .unwrap();
+            let evt: Event = f(Request::Push(Item::from(b"new".as_slice())));
+            assert_eq!(evt, Event::ItemOverwrote(Name::from("42")));
+            let got: Item = f(Request::Get(Name::from("42"))).try_into().unwrap();
+            assert_eq!(got, Item::from(b"new".as_slice()));
+        }
+
+        #[test]
+        #[ignore]
+        fn test_drop() {
+            let dirname = Path::new("./test.d/u/buf/ring_buffer_u8_new_default_with_policy/drop.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+            for u in 0..=255u8 {
+                std::fs::write(dirname.join(format!("{:02x}", u)), b"old").unwrap();
+            }
+
+            let chk = |_: &[u8]| vec![];
+            let mut f = buf::ring_buffer_u8_new_default_with_policy(
+                dirname,
+                0,
+                chk,
+                chk,
+                WriteMode::InPlace,
+                FullPolicy::DropIncoming,
+            )
+            .unwrap();
+            let evt: Event = f(Request::Push(Item::from(b"new".as_slice())));
+            assert_eq!(evt, Event::ItemDropped);
+        }
+    }
+
+    mod ring_buffer_u32_new_fifo {
+
+        use std::path::Path;
+
+        use crate::evt::Event;
+        use crate::item::{Item, Name};
+        use crate::request::Request;
+        use crate::u::buf;
+
+        #[test]
+        #[ignore]
+        fn test_capacity() {
+            let dirname = Path::new("./test.d/u/buf/ring_buffer_u32_new_fifo/capacity.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let mut f = buf::ring_buffer_u32_new_fifo(dirname, 3).unwrap();
+            for _ in 0..3 {
+                let evt: Event = f(Request::Push(Item::from(b"item".as_slice())));
+                assert!(matches!(evt, Event::ItemWrote(_)));
+            }
+            let evt: Event = f(Request::Push(Item::from(b"item".as_slice())));
+            assert_eq!(evt, Event::TooManyItemsAlready);
+
+            let names: Vec<Name> = f(Request::List).try_into().unwrap();
+            assert_eq!(
+                names,
+                vec![
+                    Name::from("00000000"),
+                    Name::from("00000001"),
+                    Name::from("00000002"),
+                ]
+            );
+        }
+
+        #[test]
+        fn test_invalid_capacity() {
+            let r = buf::ring_buffer_u32_new_fifo("./test.d/u/buf/ring_buffer_u32_new_fifo", 0);
+            assert!(r.is_err());
+        }
+    }
+
+    mod ring_buffer_layout_new_with_policy {
+
+        use std::path::Path;
+
+        use crate::evt::Event;
+        use crate::full::Layout;
+        use crate::item::{Item, Name};
+        use crate::naming::HexNames;
+        use crate::push::FullPolicy;
+        use crate::request::Request;
+        use crate::tmp;
+        use crate::u::buf;
+        use crate::write::WriteMode;
+
+        #[test]
+        #[ignore]
+        fn test_sharded() {
+            let dirname = Path::new("./test.d/u/buf/ring_buffer_layout_new_with_policy/shard.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+            let chk = |_: &[u8]| b"cafef00d".to_vec();
+
+            let mut f = buf::ring_buffer_layout_new_fifo_with_policy(
+                Layout::sharded(dirname, 1, 2).unwrap(),
+                HexNames::u16(),
+                8,
+                chk,
+                chk,
+                WriteMode::Atomic,
+                FullPolicy::Reject,
+            )
+            .unwrap();
+            let evt: Event = f(Request::Push(Item::from(b"item".as_slice())));
+            assert_eq!(evt, Event::ItemWrote(Name::from("0000")));
+            assert!(dirname.join("00/0000").is_file());
+
+            std::fs::write(dirname.join("00/0001"), b"broken").unwrap();
+            tmp::orphan_write(dirname.join("00/.0002.4294967295.tmp"), b"orphan");
+            let names: Vec<Name> = f(Request::List).try_into().unwrap();
+            assert_eq!(names, vec![Name::from("0000"), Name::from("0001")]);
+
+            assert_eq!(f(Request::Vacuum), Event::BrokenItemsRemoved(1));
+            assert!(!dirname.join("00/.0002.4294967295.tmp").exists());
+
+            assert_eq!(f(Request::Del(Name::from("0000"))), Event::Success);
+            let names: Vec<Name> = f(Request::List).try_into().unwrap();
+            assert_eq!(names, vec![]);
+        }
+
+        #[test]
+        #[ignore]
+        fn test_producers() {
+            let dirname =
+                Path::new("./test.d/u/buf/ring_buffer_layout_new_with_policy/producers.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+            let chk = |dat: &[u8]| crate::checksum::crc32c(dat).to_be_bytes().to_vec();
+
+            // independent instances(like producer processes) share one directory.
+            let producers: Vec<_> = (0..4u8)
+                .map(|_| {
+                    buf::ring_buffer_layout_new_with_policy(
+                        Layout::flat(dirname),
+                        HexNames::u8(),
+                        4,
+                        chk,
+                        chk,
+                        WriteMode::InPlace,
+                        FullPolicy::Reject,
+                    )
+                    .unwrap()
+                })
+                .collect();
+            let threads: Vec<_> = producers
+                .into_iter()
+                .enumerate()
+                .map(|(i, mut f)| {
+                    std::thread::spawn(move || {
+                        (0..32u8).for_each(|j: u8| {
+                            let item = Item::from(vec![i as u8, j]);
+                            let evt: Event = loop {
+                                match f(Request::Push(item.clone())) {
+                                    Event::Again => continue,
+                                    e => break e,
+                                }
+                            };
+                            assert!(matches!(evt, Event::ItemWrote(_)), "{:?}", evt);
+                        })
+                    })
+                })
+                .collect();
+            threads.into_iter().for_each(|t| t.join().unwrap());
+
+            let names: Vec<Name> = std::fs::read_dir(dirname)
+                .unwrap()
+                .map(|d| Name::from(d.unwrap().file_name().to_str().unwrap()))
+                .collect();
+            assert_eq!(names.len(), 128);
+            let mut items: Vec<Vec<u8>> = names
+                .iter()
+                .map(|n: &Name| std::fs::read(dirname.join(n.as_str())).unwrap()[..2].to_vec())
+                .collect();
+            items.sort();
+            items.dedup();
+            assert_eq!(items.len(), 128);
+        }
+    }
+
+    mod ring_buffer_layout_new_fifo_with_format {
+
+        use std::path::Path;
+
+        use crate::checksum::{Crc32c, XxHash64};
+        #[cfg(feature = "chacha20poly1305")]
+        use crate::envelope::ChaCha20Poly1305;
+        use crate::evt::Event;
+        use crate::full::Layout;
+        use crate::header::{ItemFormat, HEADER_SIZE};
+        use crate::item::{Item, Name};
+        use crate::meta::{Meta, Value};
+        use crate::naming::HexNames;
+        use crate::push::FullPolicy;
+        use crate::request::Request;
+        use crate::ttl::Ttl;
+        use crate::u::buf;
+        use crate::write::WriteMode;
+
+        #[test]
+        #[ignore]
+        fn test_xxh64() {
+            let dirname =
+                Path::new("./test.d/u/buf/ring_buffer_layout_new_fifo_with_format/xxh64.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let mut f = buf::ring_buffer_layout_new_fifo_with_format(
+                Layout::flat(dirname),
+                HexNames::u8(),
+                ItemFormat::new(XxHash64::new(42)),
+                Ttl::default(),
+                WriteMode::Atomic,
+                FullPolicy::Reject,
+            )
+            .unwrap();
+            f(Request::Push(Item::from(b"item".as_slice())));
+            let size: usize = HEADER_SIZE + 4 + 8;
+            assert_eq!(
+                std::fs::metadata(dirname.join("00")).unwrap().len(),
+                size as u64
+            );
+            let got: Item = f(Request::Pop).try_into().unwrap();
+            assert_eq!(got, Item::from(b"item".as_slice()));
+
+            f(Request::Push(Item::from(b"item".as_slice())));
+            let mut raw: Vec<u8> = std::fs::read(dirname.join("01")).unwrap();
+            raw[HEADER_SIZE] ^= 0x01;
+            std::fs::write(dirname.join("01"), raw).unwrap();
+            assert_eq!(f(Request::Pop), Event::Broken(Name::from("01")));
+        }
+
+        #[test]
+        #[ignore]
+        fn test_batch() {
+            let dirname =
+                Path::new("./test.d/u/buf/ring_buffer_layout_new_fifo_with_format/batch.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let mut f = buf::ring_buffer_layout_new_fifo_with_format(
+                Layout::flat(dirname),
+                HexNames::u8(),
+                ItemFormat::new(Crc32c),
+                Ttl::default(),
+                WriteMode::Atomic,
+                FullPolicy::Reject,
+            )
+            .unwrap();
+            let items: Vec<Item> = (0..3u8).map(|i: u8| Item::from(vec![i])).collect();
+            let wrote: Vec<Event> = f(Request::PushBatch(items.clone())).try_into().unwrap();
+            let names: Vec<Name> = (0..3u8).map(Name::from).collect();
+            let expected: Vec<Event> = names.iter().cloned().map(Event::ItemWrote).collect();
+            assert_eq!(wrote, expected);
+
+            let got: Vec<Event> = f(Request::GetMany(names[..2].to_vec())).try_into().unwrap();
+            let got: Vec<Item> = got.into_iter().map(|e| e.try_into().unwrap()).collect();
+            assert_eq!(got, items[..2]);
+
+            let deleted: Vec<Event> = f(Request::DelMany(names[..1].to_vec())).try_into().unwrap();
+            assert_eq!(deleted, vec![Event::Success]);
+            let popped: Item = f(Request::Pop).try_into().unwrap();
+            assert_eq!(popped, items[1]);
+        }
+
+        #[cfg(feature = "chacha20poly1305")]
+        #[test]
+        #[ignore]
+        fn test_envelope() {
+            let dirname =
+                Path::new("./test.d/u/buf/ring_buffer_layout_new_fifo_with_format/envelope.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let new = |envelope: ChaCha20Poly1305| {
+                buf::ring_buffer_layout_new_fifo_with_format(
+                    Layout::flat(dirname),
+                    HexNames::u8(),
+                    ItemFormat::new(Crc32c).with_envelope(envelope),
+                    Ttl::default(),
+                    WriteMode::Atomic,
+                    FullPolicy::Reject,
+                )
+                .unwrap()
+            };
+            let mut f = new(ChaCha20Poly1305::new(1, [0x01; 32]));
+            let item = Item::from(b"pii".as_slice()).with_meta(Meta::new().with("k", "v"));
+            f(Request::Push(item.clone()));
+            let raw: Vec<u8> = std::fs::read(dirname.join("00")).unwrap();
+            assert!(!raw.windows(3).any(|w: &[u8]| w == b"pii"));
+
+            let mut rotated = new(ChaCha20Poly1305::new(2, [0x02; 32]).with_key(1, [0x01; 32]));
+            let got: Item = rotated(Request::PeekOldest).try_into().unwrap();
+            assert_eq!(got, item);
+
+            let mut g = new(ChaCha20Poly1305::new(2, [0x02; 32]));
+            assert_eq!(
+                g(Request::PeekOldest),
+                Event::UnsupportedFormat(Name::from("00"))
+            );
+        }
+
+        #[test]
+        #[ignore]
+        fn test_meta() {
+            let dirname =
+                Path::new("./test.d/u/buf/ring_buffer_layout_new_fifo_with_format/meta.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let mut f = buf::ring_buffer_layout_new_fifo_with_format(
+                Layout::flat(dirname),
+                HexNames::u8(),
+                ItemFormat::new(Crc32c),
+                Ttl::default(),
+                WriteMode::Atomic,
+                FullPolicy::Reject,
+            )
+            .unwrap();
+            let meta = Meta::new()
+                .with("content-type", "application/json")
+                .with("producer", 42u64);
+            let item = Item::from(b"{}".as_slice()).with_meta(meta);
+            f(Request::Push(item.clone()));
+
+            let mut raw: Vec<u8> = std::fs::read(dirname.join("00")).unwrap();
+            let got: Item = f(Request::PeekOldest).try_into().unwrap();
+            assert_eq!(got, item);
+            assert_eq!(got.meta().get("producer").and_then(Value::as_u64), Some(42));
+
+            // metadata is covered by the checksum.
+            raw[HEADER_SIZE + 4 + 2] ^= 0x01;
+            std::fs::write(dirname.join("00"), raw).unwrap();
+            assert_eq!(f(Request::Pop), Event::Broken(Name::from("00")));
+        }
+    }
+
+    mod ring_buffer_layout_new_with_format_codec {
+
+        use std::path::Path;
+
+        use crate::checksum::Crc32c;
+        use crate::codec::Lz77;
+        use crate::full::Layout;
+        use crate::header::ItemFormat;
+        use crate::item::{Item, Name};
+        use crate::naming::HexNames;
+        use crate::push::FullPolicy;
+        use crate::request::Request;
+        use crate::ttl::Ttl;
+        use crate::u::buf;
+        use crate::write::WriteMode;
+
+        #[test]
+        #[ignore]
+        fn test_mixed() {
+            let dirname =
+                Path::new("./test.d/u/buf/ring_buffer_layout_new_with_format_codec/mixed.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let line: &[u8] = br#"{"level":"info","msg":"request done","status":200}"#;
+            let log = Item::from(line.repeat(100));
+            let mut plain = buf::ring_buffer_layout_new_with_format(
+                Layout::flat(dirname),
+                HexNames::u8(),
+                ItemFormat::new(Crc32c),
+                Ttl::default(),
+                WriteMode::Atomic,
+                FullPolicy::Reject,
+            )
+            .unwrap();
+            let p: Name = plain(Request::Push(log.clone())).try_into().unwrap();
+
+            let mut lz = buf::ring_buffer_layout_new_with_format(
+                Layout::flat(dirname),
+                HexNames::u8(),
+                ItemFormat::new(Crc32c).with_codec(Lz77),
+                Ttl::default(),
+                WriteMode::Atomic,
+                FullPolicy::Reject,
+            )
+            .unwrap();
+            let c: Name = lz(Request::Push(log.clone())).try_into().unwrap();
+
+            let psize: u64 = std::fs::metadata(dirname.join(p.as_str())).unwrap().len();
+            let csize: u64 = std::fs::metadata(dirname.join(c.as_str())).unwrap().len();
+            assert!(csize * 10 < psize);
+
+            for n in [p, c] {
+                let got: Item = plain(Request::Get(n.clone())).try_into().unwrap();
+                assert_eq!(got, log);
+                let got: Item = lz(Request::Get(n)).try_into().unwrap();
+                assert_eq!(got, log);
+            }
+        }
+    }
+
+    mod ring_buffer_layout_new_with_format {
+
+        use std::path::Path;
+        use std::time::{Duration, SystemTime};
+
+        use crate::checksum::{Checksum, Crc32c, Crc64};
+        use crate::clock::ManualClock;
+        use crate::codec::Identity;
+        use crate::envelope::Plain;
+        use crate::evt::Event;
+        use crate::full::Layout;
+        use crate::header::{self, ItemFormat, HEADER_SIZE};
+        use crate::item::{Item, Name};
+        use crate::naming::HexNames;
+        use crate::order::{Order, Page};
+        use crate::push::FullPolicy;
+        use crate::request::Request;
+        use crate::stat::Stat;
+        use crate::ttl::Ttl;
+        use crate::u::buf;
+        use crate::write::WriteMode;
+
+        #[test]
+        #[ignore]
+        fn test_expire() {
+            let dirname = Path::new("./test.d/u/buf/ring_buffer_layout_new_with_format/expire.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let t0: SystemTime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+            let clock = ManualClock::new(t0);
+            let mut f = buf::ring_buffer_layout_new_with_format(
+                Layout::flat(dirname),
+                HexNames::u8(),
+                ItemFormat::new(Crc32c),
+                Ttl::new(clock.clone(), Some(Duration::from_secs(60))),
+                WriteMode::Atomic,
+                FullPolicy::Reject,
+            )
+            .unwrap();
+            let short = Item::from(b"short".as_slice()).with_deadline(t0 + Duration::from_secs(1));
+            let s: Name = f(Request::Push(short.clone())).try_into().unwrap();
+            let l: Name = f(Request::Push(Item::from(b"long".as_slice())))
+                .try_into()
+                .unwrap();
+
+            let got: Item = f(Request::Get(s.clone())).try_into().unwrap();
+            assert_eq!(got, short);
+
+            clock.advance(Duration::from_secs(1));
+            assert_eq!(f(Request::Get(s.clone())), Event::Expired(s.clone()));
+            assert_eq!(f(Request::Expire), Event::ExpiredItemsRemoved(1));
+            let names: Vec<Name> = f(Request::List).try_into().unwrap();
+            assert_eq!(names, vec![l.clone()]);
+
+            clock.advance(Duration::from_secs(59));
+            assert_eq!(f(Request::Get(l.clone())), Event::Expired(l));
+            assert_eq!(f(Request::Expire), Event::ExpiredItemsRemoved(1));
+        }
+
+        #[test]
+        #[ignore]
+        fn test_unsupported() {
+            let dirname =
+                Path::new("./test.d/u/buf/ring_buffer_layout_new_with_format/unsupported.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let now = SystemTime::now();
+            let write = |name: &str, mut body: Vec<u8>, checker: &dyn Checksum| {
+                body.extend(checker.compute(&body));
+                std::fs::write(dirname.join(name), body).unwrap();
+            };
+            // a newer version
+            let mut newer: Vec<u8> = header::frame(
+                &Name::from("00"),
+                Item::from(b"v2".as_slice()),
+                now,
+                &Crc32c,
+                &Identity,
+                &Plain,
+            )
+            .unwrap()
+            .into();
+            newer[4] = 2;
+            write("00", newer, &Crc32c);
+            // another checksum algorithm
+            let crc64: Vec<u8> = header::frame(
+                &Name::from("01"),
+                Item::from(b"crc64".as_slice()),
+                now,
+                &Crc64,
+                &Identity,
+                &Plain,
+            )
+            .unwrap()
+            .into();
+            write("01", crc64, &Crc64);
+            std::fs::write(dirname.join("02"), b"broken").unwrap();
+
+            let mut f = buf::ring_buffer_layout_new_with_format(
+                Layout::flat(dirname),
+                HexNames::u8(),
+                ItemFormat::new(Crc32c),
+                Ttl::default(),
+                WriteMode::Atomic,
+                FullPolicy::Reject,
+            )
+            .unwrap();
+            for name in ["00", "01"] {
+                let n = Name::from(name);
+                assert_eq!(f(Request::Get(n.clone())), Event::UnsupportedFormat(n));
+            }
+            assert_eq!(f(Request::Vacuum), Event::BrokenItemsRemoved(1));
+            let names: Vec<Name> = f(Request::List).try_into().unwrap();
+            assert_eq!(names, vec![Name::from("00"), Name::from("01")]);
+        }
+
+        #[test]
+        #[ignore]
+        fn test_list_created() {
+            let dirname = Path::new("./test.d/u/buf/ring_buffer_layout_new_with_format/created.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let t0: SystemTime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+            let clock = ManualClock::new(t0);
+            let mut f = buf::ring_buffer_layout_new_with_format(
+                Layout::flat(dirname),
+                HexNames::u8(),
+                ItemFormat::new(Crc32c),
+                Ttl::new(clock.clone(), None),
+                WriteMode::InPlace,
+                FullPolicy::Reject,
+            )
+            .unwrap();
+            let mut pushed: Vec<Name> = vec![];
+            for b in [b"a", b"b", b"c"] {
+                pushed.push(
+                    f(Request::Push(Item::from(b.as_slice())))
+                        .try_into()
+                        .unwrap(),
+                );
+                clock.advance(Duration::from_secs(1));
+            }
+
+            let page = Page::new(Order::Created);
+            assert_eq!(f(Request::ListPage(page)), Event::NamesGot(pushed.clone()));
+            let page = Page::new(Order::Created).with_offset(1).with_limit(1);
+            assert_eq!(
+                f(Request::ListPage(page)),
+                Event::NamesGot(vec![pushed[1].clone()])
+            );
+            let page = Page::new(Order::Sequence);
+            assert_eq!(f(Request::ListPage(page)), Event::BadRequest);
+        }
+
+        #[test]
+        #[ignore]
+        fn test_stat() {
+            let dirname = Path::new("./test.d/u/buf/ring_buffer_layout_new_with_format/stat.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let mut f = buf::ring_buffer_layout_new_with_format(
+                Layout::flat(dirname),
+                HexNames::u8(),
+                ItemFormat::new(Crc32c),
+                Ttl::default(),
+                WriteMode::Atomic,
+                FullPolicy::Reject,
+            )
+            .unwrap();
+            let s: Stat = f(Request::Stat).try_into().unwrap();
+            assert_eq!((s.used, s.free, s.capacity, s.bytes), (0, 256, 256, 0));
+            assert_eq!(s.newest, None);
+
+            let a: Name = f(Request::Push(Item::from(b"a".as_slice())))
+                .try_into()
+                .unwrap();
+            f(Request::Push(Item::from(b"bb".as_slice())));
+            let s: Stat = f(Request::Stat).try_into().unwrap();
+            assert_eq!((s.used, s.free), (2, 254));
+            assert_eq!(s.bytes, 2 * (HEADER_SIZE as u64 + 4) + 3);
+            assert!(s.oldest <= s.newest);
+
+            std::fs::write(dirname.join(a.as_str()), b"broken").unwrap();
+            assert_eq!(f(Request::Get(a.clone())), Event::Broken(a.clone()));
+            assert_eq!(f(Request::Get(a.clone())), Event::Broken(a));
+            let s: Stat = f(Request::Stat).try_into().unwrap();
+            assert_eq!(s.broken, 1);
+            assert_eq!(f(Request::Vacuum), Event::BrokenItemsRemoved(1));
+            let s: Stat = f(Request::Stat).try_into().unwrap();
+            assert_eq!((s.used, s.broken), (1, 0));
+        }
+    }
+
+    mod ring_buffer_layout_new_fifo_with_format_quota {
+
+        use std::path::Path;
+
+        use crate::checksum::Crc32c;
+        use crate::evt::Event;
+        use crate::full::Layout;
+        use crate::header::ItemFormat;
+        use crate::item::{Item, Name};
+        use crate::naming::HexNames;
+        use crate::push::FullPolicy;
+        use crate::quota::Quota;
+        use crate::request::Request;
+        use crate::ttl::Ttl;
+        use crate::u::buf;
+        use crate::write::WriteMode;
+
+        #[test]
+        #[ignore]
+        fn test_evict() {
+            let dirname =
+                Path::new("./test.d/u/buf/ring_buffer_layout_new_fifo_with_format_quota/evict.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let open = |policy: FullPolicy| {
+                buf::ring_buffer_layout_new_fifo_with_format_quota(
+                    Layout::flat(dirname),
+                    HexNames::u8(),
+                    ItemFormat::new(Crc32c),
+                    Ttl::default(),
+                    WriteMode::Atomic,
+                    policy,
+                    Quota::default().with_max_bytes(256).with_max_item(100),
+                )
+                .unwrap()
+            };
+            let mut f = open(FullPolicy::Reject);
+            let item = Item::from(vec![0x42; 100]);
+            assert_eq!(
+                f(Request::Push(Item::from(vec![0x42; 101]))),
+                Event::ItemTooLarge(101)
+            );
+            f(Request::Push(item.clone()));
+            f(Request::Push(item.clone()));
+            assert_eq!(f(Request::Push(item.clone())), Event::QuotaExceeded);
+            drop(f);
+
+            let mut f = open(FullPolicy::OverwriteOldest);
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            assert!(matches!(
+                f(Request::Push(item.clone())),
+                Event::ItemWrote(_)
+            ));
+            let names: Vec<Name> = f(Request::List).try_into().unwrap();
+            assert_eq!(names, vec![Name::from("01"), Name::from("02")]);
+
+            std::fs::write(dirname.join("03"), vec![0; 1024]).unwrap();
+            assert_eq!(f(Request::Get(Name::from("03"))), Event::ItemTooLarge(1024));
+
+            // other reads are limited too
+            std::fs::write(dirname.join("01"), vec![0; 1024]).unwrap();
+            assert_eq!(f(Request::PeekOldest), Event::ItemTooLarge(1024));
+        }
+
+        #[test]
+        #[ignore]
+        fn test_batch() {
+            let dirname =
+                Path::new("./test.d/u/buf/ring_buffer_layout_new_fifo_with_format_quota/batch.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let mut f = buf::ring_buffer_layout_new_fifo_with_format_quota(
+                Layout::flat(dirname),
+                HexNames::u8(),
+                ItemFormat::new(Crc32c),
+                Ttl::default(),
+                WriteMode::Atomic,
+                FullPolicy::Reject,
+                Quota::default().with_max_bytes(256),
+            )
+            .unwrap();
+            let item = Item::from(vec![0x42; 100]);
+            let pushed = f(Request::PushBatch(vec![item.clone(); 3]));
+            let expected = Event::Batch(vec![
+                Event::ItemWrote(Name::from("00")),
+                Event::ItemWrote(Name::from("01")),
+                Event::QuotaExceeded,
+            ]);
+            assert_eq!(pushed, expected);
+            let names: Vec<Name> = f(Request::List).try_into().unwrap();
+            assert_eq!(names.len(), 2);
+        }
+    }
+
+    mod ring_buffer_layout_new_consumer_with_format {
+
+        use std::path::Path;
+        use std::time::{Duration, SystemTime};
+
+        use crate::checksum::Crc32c;
+        use crate::clock::ManualClock;
+        use crate::evt::Event;
+        use crate::full::Layout;
+        use crate::header::ItemFormat;
+        use crate::item::{Item, Name, NamedItem};
+        use crate::lease::Leases;
+        use crate::naming::HexNames;
+        use crate::push::FullPolicy;
+        use crate::request::Request;
+        use crate::ttl::Ttl;
+        use crate::u::buf;
+        use crate::write::{self, WriteMode};
+
+        fn claimed(e: Event) -> Name {
+            match e {
+                Event::ItemGot(named) => named.as_name().clone(),
+                e => panic!("Unexpected event: {:#?}", e),
+            }
+        }
+
+        #[test]
+        #[ignore]
+        fn test_claim() {
+            let dirname =
+                Path::new("./test.d/u/buf/ring_buffer_layout_new_consumer_with_format/claim.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let clock = ManualClock::new(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000));
+            let consumer = || {
+                buf::ring_buffer_layout_new_consumer_with_format(
+                    Layout::flat(dirname),
+                    HexNames::u8(),
+                    ItemFormat::new(Crc32c),
+                    Ttl::new(clock.clone(), None),
+                    WriteMode::Atomic,
+                    FullPolicy::Reject,
+                    Duration::from_secs(30),
+                )
+                .unwrap()
+            };
+            let mut a = consumer();
+            let mut b = consumer();
+            assert_eq!(a(Request::Claim), Event::NoClaimableItem);
+
+            let n: Name = a(Request::Push(Item::from(b"job".as_slice())))
+                .try_into()
+                .unwrap();
+            assert_eq!(claimed(a(Request::Claim)), n);
+            assert_eq!(b(Request::Claim), Event::NoClaimableItem);
+            assert_eq!(b(Request::Ack(n.clone())), Event::NoEntry(n.clone()));
+
+            assert_eq!(a(Request::Nack(n.clone())), Event::Success);
+            assert_eq!(claimed(b(Request::Claim)), n);
+
+            clock.advance(Duration::from_secs(30));
+            assert_eq!(claimed(a(Request::Claim)), n);
+            assert_eq!(b(Request::Ack(n.clone())), Event::Expired(n.clone()));
+            assert_eq!(a(Request::Ack(n.clone())), Event::Success);
+            let names: Vec<Name> = a(Request::List).try_into().unwrap();
+            assert!(names.is_empty());
+            assert_eq!(b(Request::Claim), Event::NoClaimableItem);
+
+            // leases survive restarts
+            let n: Name = a(Request::Push(Item::from(b"job".as_slice())))
+                .try_into()
+                .unwrap();
+            assert_eq!(claimed(a(Request::Claim)), n);
+            drop(a);
+            let mut c = consumer();
+            assert_eq!(c(Request::Claim), Event::NoClaimableItem);
+            clock.advance(Duration::from_secs(30));
+            assert_eq!(claimed(c(Request::Claim)), n);
+
+            // leases are removed with the item
+            let lease = dirname.join(format!(".{}.lease", n.as_str()));
+            assert!(lease.exists());
+            assert_eq!(c(Request::Del(n.clone())), Event::Success);
+            assert!(!lease.exists());
+            assert_eq!(c(Request::Ack(n.clone())), Event::NoEntry(n.clone()));
+        }
+
+        #[test]
+        #[ignore]
+        fn test_owner() {
+            let dirname =
+                Path::new("./test.d/u/buf/ring_buffer_layout_new_consumer_with_format/owner.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let clock = ManualClock::new(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000));
+            let consumer = |owner: u64| {
+                let layout = Layout::flat(dirname);
+                let visibility = Duration::from_secs(30);
+                let leases = Leases::new(layout.path_builder(), clock.clone(), visibility);
+                buf::ring_buffer_layout_new_consumer_with_leases(
+                    layout,
+                    HexNames::u8(),
+                    ItemFormat::new(Crc32c),
+                    Ttl::new(clock.clone(), None),
+                    WriteMode::Atomic,
+                    FullPolicy::Reject,
+                    leases.with_owner(owner),
+                )
+                .unwrap()
+            };
+
+            // held leases are reloaded by the owner id
+            let mut a = consumer(1);
+            let n: Name = a(Request::Push(Item::from(b"job".as_slice())))
+                .try_into()
+                .unwrap();
+            assert_eq!(claimed(a(Request::Claim)), n);
+            drop(a);
+            let mut b = consumer(2);
+            assert_eq!(b(Request::Ack(n.clone())), Event::NoEntry(n.clone()));
+            let mut a = consumer(1);
+            assert_eq!(a(Request::Nack(n.clone())), Event::Success);
+
+            // a replaced item is not leased
+            assert_eq!(claimed(a(Request::Claim)), n);
+            clock.advance(Duration::from_secs(1));
+            let w = write::writer_unchecked_new_layout_with_format_clock(
+                Layout::flat(dirname),
+                ItemFormat::new(Crc32c),
+                WriteMode::Atomic,
+                clock.clone(),
+            );
+            w(NamedItem::new(Item::from(b"new".as_slice()), n.clone())).unwrap();
+            assert_eq!(a(Request::Ack(n.clone())), Event::Expired(n.clone()));
+            assert_eq!(claimed(b(Request::Claim)), n);
+            assert_eq!(b(Request::Ack(n.clone())), Event::Success);
+        }
+
+        #[test]
+        #[ignore]
+        fn test_expire() {
+            let dirname =
+                Path::new("./test.d/u/buf/ring_buffer_layout_new_consumer_with_format/expire.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let clock = ManualClock::new(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000));
+            let consumer = || {
+                buf::ring_buffer_layout_new_consumer_with_format(
+                    Layout::flat(dirname),
+                    HexNames::u8(),
+                    ItemFormat::new(Crc32c),
+                    Ttl::new(clock.clone(), Some(Duration::from_secs(60))),
+                    WriteMode::Atomic,
+                    FullPolicy::Reject,
+                    Duration::from_secs(30),
+                )
+                .unwrap()
+            };
+            let mut a = consumer();
+            let mut b = consumer();
+
+            // oldest first
+            let old: Name = a(Request::Push(Item::from(b"old".as_slice())))
+                .try_into()
+                .unwrap();
+            clock.advance(Duration::from_secs(1));
+            let new: Name = a(Request::Push(Item::from(b"new".as_slice())))
+                .try_into()
+                .unwrap();
+            assert_eq!(claimed(b(Request::Claim)), old);
+            assert_eq!(claimed(a(Request::Claim)), new);
+
+            // leases are removed with the expired items
+            let lease = |n: &Name| dirname.join(format!(".{}.lease", n.as_str()));
+            assert!(lease(&old).exists());
+            assert!(lease(&new).exists());
+            clock.advance(Duration::from_secs(61));
+            assert_eq!(a(Request::Expire), Event::ExpiredItemsRemoved(2));
+            assert!(!lease(&old).exists());
+            assert!(!lease(&new).exists());
+        }
+    }
+
+    mod ring_buffer_shared_layout_new_with_format {
+
+        use std::path::Path;
+
+        use crate::checksum::Crc32c;
+        use crate::evt::Event;
+        use crate::full::Layout;
+        use crate::header::ItemFormat;
+        use crate::item::{Item, Name};
+        use crate::naming::HexNames;
+        use crate::request::Request;
+        use crate::ttl::Ttl;
+        use crate::u::buf;
+        use crate::write::WriteMode;
+
+        #[test]
+        #[ignore]
+        fn test_threads() {
+            let dirname =
+                Path::new("./test.d/u/buf/ring_buffer_shared_layout_new_with_format/threads.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let f = buf::ring_buffer_shared_layout_new_with_format(
+                Layout::flat(dirname),
+                HexNames::u8(),
+                ItemFormat::new(Crc32c),
+                Ttl::default(),
+                WriteMode::Atomic,
+            )
+            .unwrap();
+            let threads: Vec<_> = (0..4u8)
+                .map(|i: u8| {
+                    let f = f.clone();
+                    std::thread::spawn(move || {
+                        let item = Item::from(vec![i; 16]);
+                        (0..16).for_each(|_| {
+                            let n: Name = loop {
+                                match f(Request::Push(item.clone())) {
+                                    Event::Again => continue,
+                                    e => break e.try_into().unwrap(),
+                                }
+                            };
+                            let got: Item = f(Request::Get(n)).try_into().unwrap();
+                            assert_eq!(got, item);
+                        })
+                    })
+                })
+                .collect();
+            threads.into_iter().for_each(|t| t.join().unwrap());
+
+            let names: Vec<Name> = f(Request::List).try_into().unwrap();
+            assert_eq!(names.len(), 64);
+            assert_eq!(f(Request::Vacuum), Event::BrokenItemsRemoved(0));
+        }
+
+        #[test]
+        #[ignore]
+        fn test_full() {
+            let dirname =
+                Path::new("./test.d/u/buf/ring_buffer_shared_layout_new_with_format/full.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let f = buf::ring_buffer_shared_layout_new_with_format(
+                Layout::flat(dirname),
+                HexNames::u8().with_capacity(4).unwrap(),
+                ItemFormat::new(Crc32c),
+                Ttl::default(),
+                WriteMode::Atomic,
+            )
+            .unwrap();
+            let push = || f(Request::Push(Item::from(b"item".as_slice())));
+            let mut wrote: u64 = 0;
+            while wrote < 4 {
+                match push() {
+                    Event::Again => continue,
+                    e => assert!(matches!(e, Event::ItemWrote(_)), "{:#?}", e),
+                }
+                wrote += 1;
+            }
+            assert_eq!(push(), Event::TooManyItemsAlready);
+        }
+    }
+
+    mod ring_buffer_u16_new_default {
+
+        use std::path::Path;
+
+        use crate::evt::Event;
+        use crate::item::{Item, Name};
+        use crate::request::Request;
+        use crate::u::buf;
+
+        #[test]
+        #[ignore]
+        fn test_push() {
+            let dirname = Path::new("./test.d/u/buf/ring_buffer_u16_new_default/push.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+
+            let mut f = buf::ring_buffer_u16_new_default(dirname).unwrap();
+            let evt: Event = f(Request::Push(Item::from(b"item".as_slice())));
+            let n: Name = evt.try_into().unwrap();
+            assert_eq!(n.as_str().len(), 4);
+            let got: Item = f(Request::Get(n)).try_into().unwrap();
+            assert_eq!(got, Item::from(b"item".as_slice()));
+        }
     }
 
     mod ring_buffer_u8_new_default_with_checksum {
@@ -225,7 +2549,8 @@ mod test_buf {
             std::fs::create_dir_all(&dirname).unwrap();
             let req: Request = Request::Push(Item::from(vec![]));
             let evt: Event = f(req);
-            assert_eq!(evt, Event::Success);
+            let n: Name = evt.try_into().unwrap();
+            assert!(u8::try_from(&n).is_ok());
         }
 
         #[test]
@@ -0,0 +1,65 @@
+use crate::evt::Event;
+use crate::request::Request;
+use crate::RingBuffer;
+
+/// Ring buffer wrapper which runs extra cleanup before `Request::Vacuum`.
+pub struct VacuumRingBuffer<R, V> {
+    pub inner: R,
+    pub vacuum: V,
+}
+
+impl<R, V> RingBuffer for VacuumRingBuffer<R, V>
+where
+    R: RingBuffer,
+    V: Fn() -> Result<u64, Event>,
+{
+    fn handle(&mut self, req: Request) -> Event {
+        match req {
+            Request::Vacuum => match (self.vacuum)() {
+                Ok(_) => self.inner.handle(Request::Vacuum),
+                Err(e) => e,
+            },
+            q => self.inner.handle(q),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_vacuum {
+
+    mod vacuum_ring_buffer {
+        use crate::evt::Event;
+        use crate::request::Request;
+        use crate::vacuum::VacuumRingBuffer;
+        use crate::RingBuffer;
+
+        struct Nop {}
+        impl RingBuffer for Nop {
+            fn handle(&mut self, req: Request) -> Event {
+                match req {
+                    Request::Vacuum => Event::BrokenItemsRemoved(0),
+                    _ => Event::Success,
+                }
+            }
+        }
+
+        #[test]
+        fn test_vacuum() {
+            let mut rb = VacuumRingBuffer {
+                inner: Nop {},
+                vacuum: || Ok(3),
+            };
+            assert_eq!(rb.handle(Request::Vacuum), Event::BrokenItemsRemoved(0));
+            assert_eq!(rb.handle(Request::List), Event::Success);
+        }
+
+        #[test]
+        fn test_vacuum_err() {
+            let mut rb = VacuumRingBuffer {
+                inner: Nop {},
+                vacuum: || Err(Event::NoPerm("".into())),
+            };
+            assert_eq!(rb.handle(Request::Vacuum), Event::NoPerm("".into()));
+        }
+    }
+}
@@ -2,10 +2,29 @@ use std::fs::File;
 use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
 
+use crate::batch;
+use crate::checksum::{self, Checksum};
+use crate::claim::Claim;
+use crate::clock::{Clock, SystemClock};
+use crate::codec::Codec;
 use crate::empty;
+use crate::envelope::Envelope;
 use crate::evt::Event;
-use crate::full;
+use crate::full::{self, Layout};
+use crate::header::{self, ItemFormat};
 use crate::item::{Item, Name, NamedItem};
+use crate::tmp;
+
+/// A list of strategies to write an item file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WriteMode {
+    /// Writes the item file in place(a power failure may leave a half-written item).
+    #[default]
+    InPlace,
+
+    /// Writes a temporary file, then renames it to the item file and syncs the directory.
+    Atomic,
+}
 
 fn write_bytes<W>(w: &mut W, b: &[u8]) -> Result<(), Event>
 where
@@ -59,19 +78,106 @@ where
         Event::UnexpectedError(format!("Unable to create named item({:#?}): {}", p, e))
     })?;
     item2write_with_checksum(i, f.by_ref(), checksum)?;
-    f.sync_data()
-        .map_err(|e| Event::UnexpectedError(format!("Unable to save to storage: {}", e)))?;
+    sync_file(f, p)
+}
+
+/// Syncs the data of the written file(a running batch syncs all files at once).
+pub(crate) fn sync_file<P>(f: File, p: P) -> Result<(), Event>
+where
+    P: AsRef<Path>,
+{
+    match batch::defer_sync(f, p) {
+        None => Ok(()),
+        Some(f) => f
+            .sync_data()
+            .map_err(|e| Event::UnexpectedError(format!("Unable to save to storage: {}", e))),
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn sync_dir<P>(dirname: P) -> Result<(), Event>
+where
+    P: AsRef<Path>,
+{
+    File::open(dirname)
+        .and_then(|d: File| d.sync_all())
+        .map_err(|e| Event::UnexpectedError(format!("Unable to sync dir: {}", e)))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn sync_dir<P>(_dirname: P) -> Result<(), Event>
+where
+    P: AsRef<Path>,
+{
     Ok(())
 }
 
+pub(crate) fn rename_synced<P, Q>(tmp: P, p: Q) -> Result<(), Event>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    if batch::defer_rename(tmp.as_ref(), p.as_ref()) {
+        return Ok(());
+    }
+    std::fs::rename(tmp, p.as_ref())
+        .map_err(|e| Event::UnexpectedError(format!("Unable to rename temporary item: {}", e)))?;
+    let dirname: &Path = p.as_ref().parent().unwrap_or_else(|| Path::new("."));
+    sync_dir(dirname)
+}
+
+fn item2path_atomic_with_checksum<P, C>(i: Item, p: P, checksum: &C) -> Result<(), Event>
+where
+    P: AsRef<Path> + std::fmt::Debug,
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    let tmp: PathBuf = tmp::tmp_path_new(p.as_ref())?;
+    item2path_with_checksum(i, &tmp, checksum)
+        .and_then(|_| rename_synced(&tmp, p))
+        .inspect_err(|_| {
+            std::fs::remove_file(&tmp).ok();
+        })
+}
+
+/// Writes bytes to the path atomically(temporary file, rename, directory sync).
+pub fn write_atomic<P>(dat: &[u8], p: P) -> Result<(), Event>
+where
+    P: AsRef<Path> + std::fmt::Debug,
+{
+    item2path_atomic_with_checksum(Item::from(dat), p, &checksum_nop)
+}
+
+/// Checks if the item can be stored without a header(no deadline, no metadata).
+fn headerless_check(i: &Item) -> Result<(), Event> {
+    let plain: bool = i.deadline().is_none() && i.meta().is_empty();
+    plain.then_some(()).ok_or(Event::BadRequest)
+}
+
+fn item2path_with_mode<P, C>(i: Item, p: P, checksum: &C, mode: WriteMode) -> Result<(), Event>
+where
+    P: AsRef<Path> + std::fmt::Debug,
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    headerless_check(&i)?;
+    match mode {
+        WriteMode::InPlace => item2path_with_checksum(i, p, checksum),
+        WriteMode::Atomic => item2path_atomic_with_checksum(i, p, checksum),
+    }
+}
+
 /// Creates new unchecked writer which uses closures to build path and compute checksum.
 ///
+/// Items will be stored without a header: items with a deadline or metadata will be rejected
+/// (`Event::BadRequest`).
+///
 /// # Arguments
 /// - path_builder: Builds a path for a named item.
 /// - checksum:     Computes checksum.
-pub fn writer_unchecked_new_checksum<B, C>(
+/// - mode:         Strategy to write an item file.
+pub fn writer_unchecked_new_checksum_mode<B, C>(
     path_builder: B,
     checksum: C,
+    mode: WriteMode,
 ) -> impl Fn(NamedItem) -> Result<Name, Event>
 where
     B: Fn(Name) -> PathBuf,
@@ -79,12 +185,193 @@ where
 {
     move |named: NamedItem| {
         let (name, item) = named.into_pair();
+        full::name_check(&name)?;
         let p: PathBuf = path_builder(name.clone());
-        item2path_with_checksum(item, p, &checksum)?;
+        item2path_with_mode(item, p, &checksum, mode)?;
+        Ok(name)
+    }
+}
+
+pub(crate) fn create_shard_dir(layout: &Layout, name: &Name, mode: WriteMode) -> Result<(), Event> {
+    let mut d: PathBuf = layout.shard_dir(name);
+    if d.is_dir() {
+        return Ok(());
+    }
+    layout.create_shard_dir(name)?;
+    match mode {
+        WriteMode::InPlace => Ok(()),
+        WriteMode::Atomic => {
+            while d.as_path() != layout.dirname() && d.pop() {
+                sync_dir(&d)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Creates new unchecked writer which uses the layout to build path and create shard dirs.
+///
+/// Missing shard directories will be created on demand.
+/// Items will be stored without a header: items with a deadline or metadata will be rejected
+/// (`Event::BadRequest`).
+///
+/// # Arguments
+/// - layout:   Directory layout of buffer files.
+/// - checksum: Computes checksum.
+/// - mode:     Strategy to write an item file.
+pub fn writer_unchecked_new_layout_with_checksum_mode<C>(
+    layout: Layout,
+    checksum: C,
+    mode: WriteMode,
+) -> impl Fn(NamedItem) -> Result<Name, Event>
+where
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    move |named: NamedItem| {
+        let (name, item) = named.into_pair();
+        let p: PathBuf = layout.path_checked(&name)?;
+        create_shard_dir(&layout, &name, mode)?;
+        item2path_with_mode(item, p, &checksum, mode)?;
         Ok(name)
     }
 }
 
+/// Creates new writer which prepends an item header(see `header::Header`) before writing.
+///
+/// # Arguments
+/// - write:   Writes `NamedItem`(header + payload) with checksum.
+/// - checker: Computes checksum(stored in the header as an algorithm id).
+/// - format:  Checksum, codec and envelope of items(the compatibility option will be ignored).
+/// - clock:   Gets the creation time of items.
+pub fn writer_framed_new<W, K, D, E, T>(
+    write: W,
+    format: ItemFormat<K, D, E>,
+    clock: T,
+) -> impl Fn(NamedItem) -> Result<Name, Event>
+where
+    W: Fn(NamedItem) -> Result<Name, Event>,
+    K: Checksum,
+    D: Codec,
+    E: Envelope,
+    T: Clock,
+{
+    move |named: NamedItem| {
+        let (name, item) = named.into_pair();
+        let framed: Item = header::frame(
+            &name,
+            item,
+            clock.now(),
+            &format.checker,
+            &format.codec,
+            &format.envelope,
+        )?;
+        write(NamedItem::new(framed, name))
+    }
+}
+
+/// Creates new writer which seals payloads before writing(headerless items).
+///
+/// The checksum(if any) will be computed over the sealed payload by the writer.
+/// The name will be authenticated with the payload(see `envelope::Envelope`).
+/// Items with a deadline or metadata will be rejected(`Event::BadRequest`; no header to store
+/// them).
+///
+/// # Arguments
+/// - write:    Writes `NamedItem`(sealed payload) with checksum.
+/// - envelope: Seals payloads(see `envelope::Envelope`).
+pub fn writer_sealed_new<W, E>(write: W, envelope: E) -> impl Fn(NamedItem) -> Result<Name, Event>
+where
+    W: Fn(NamedItem) -> Result<Name, Event>,
+    E: Envelope,
+{
+    move |named: NamedItem| {
+        let (name, item) = named.into_pair();
+        headerless_check(&item)?;
+        let sealed: Vec<u8> = envelope.seal(name.as_str().as_bytes(), item.as_slice())?;
+        write(NamedItem::new(Item::from(sealed), name))
+    }
+}
+
+/// Creates new unchecked writer which uses the layout, `ItemFormat` and `Clock`(with an item header).
+///
+/// # Arguments
+/// - layout: Directory layout of buffer files.
+/// - format: Checksum, codec and envelope of items(the compatibility option will be ignored).
+/// - mode:   Strategy to write an item file.
+/// - clock:  Gets the creation time of items.
+pub fn writer_unchecked_new_layout_with_format_clock<K, D, E, T>(
+    layout: Layout,
+    format: ItemFormat<K, D, E>,
+    mode: WriteMode,
+    clock: T,
+) -> impl Fn(NamedItem) -> Result<Name, Event>
+where
+    K: Checksum + Clone,
+    D: Codec,
+    E: Envelope,
+    T: Clock,
+{
+    let unchecked = writer_unchecked_new_layout_with_checksum_mode(
+        layout,
+        checksum::checksum2fn(format.checker.clone()),
+        mode,
+    );
+    writer_framed_new(unchecked, format, clock)
+}
+
+/// Creates new unchecked writer which uses the layout, `Checksum` and `Clock`(with an item header).
+///
+/// # Arguments
+/// - layout:  Directory layout of buffer files.
+/// - checker: Computes checksum.
+/// - mode:    Strategy to write an item file.
+/// - clock:   Gets the creation time of items.
+pub fn writer_unchecked_new_layout_with_checker_clock<K, T>(
+    layout: Layout,
+    checker: K,
+    mode: WriteMode,
+    clock: T,
+) -> impl Fn(NamedItem) -> Result<Name, Event>
+where
+    K: Checksum + Clone,
+    T: Clock,
+{
+    writer_unchecked_new_layout_with_format_clock(layout, ItemFormat::new(checker), mode, clock)
+}
+
+/// Creates new unchecked writer which uses the layout and `Checksum`(with an item header).
+///
+/// # Arguments
+/// - layout:  Directory layout of buffer files.
+/// - checker: Computes checksum.
+/// - mode:    Strategy to write an item file.
+pub fn writer_unchecked_new_layout_with_checker_mode<K>(
+    layout: Layout,
+    checker: K,
+    mode: WriteMode,
+) -> impl Fn(NamedItem) -> Result<Name, Event>
+where
+    K: Checksum + Clone,
+{
+    writer_unchecked_new_layout_with_checker_clock(layout, checker, mode, SystemClock)
+}
+
+/// Creates new unchecked writer which uses closures to build path and compute checksum.
+///
+/// # Arguments
+/// - path_builder: Builds a path for a named item.
+/// - checksum:     Computes checksum.
+pub fn writer_unchecked_new_checksum<B, C>(
+    path_builder: B,
+    checksum: C,
+) -> impl Fn(NamedItem) -> Result<Name, Event>
+where
+    B: Fn(Name) -> PathBuf,
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    writer_unchecked_new_checksum_mode(path_builder, checksum, WriteMode::InPlace)
+}
+
 /// Creates new unchecked writer which uses a closure to build path to write a named item.
 pub fn writer_unchecked_new<B>(path_builder: B) -> impl Fn(NamedItem) -> Result<Name, Event>
 where
@@ -98,16 +385,34 @@ where
 /// # Arguments
 /// - dirname: Path to store buffer files.
 /// - checksum:     Computes checksum.
-pub fn writer_unchecked_new_default_with_checksum<P, C>(
+/// - mode:         Strategy to write an item file.
+pub fn writer_unchecked_new_default_with_checksum_mode<P, C>(
     dirname: P,
     checksum: C,
+    mode: WriteMode,
 ) -> impl Fn(NamedItem) -> Result<Name, Event>
 where
     P: AsRef<Path>,
     C: Fn(&[u8]) -> Vec<u8>,
 {
     let path_builder = full::fullpath_builder_new(dirname);
-    writer_unchecked_new_checksum(path_builder, checksum)
+    writer_unchecked_new_checksum_mode(path_builder, checksum, mode)
+}
+
+/// Creates new unchecked writer which uses default path builder.
+///
+/// # Arguments
+/// - dirname: Path to store buffer files.
+/// - checksum:     Computes checksum.
+pub fn writer_unchecked_new_default_with_checksum<P, C>(
+    dirname: P,
+    checksum: C,
+) -> impl Fn(NamedItem) -> Result<Name, Event>
+where
+    P: AsRef<Path>,
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    writer_unchecked_new_default_with_checksum_mode(dirname, checksum, WriteMode::InPlace)
 }
 
 /// Creates new unchecked writer which uses default path builder.
@@ -121,6 +426,8 @@ where
 
 /// Creates checked writer which uses closures to check and write named item.
 ///
+/// Writers in other processes may write the same slot after the check(use `writer_claimed_new_layout`).
+///
 /// # Arguments
 /// - unchecked: Writes `NamedItem` after check.
 /// - is_empty:  Checks if `Name` is empty.
@@ -142,26 +449,100 @@ where
     }
 }
 
+/// Creates new checked writer which claims the slot before the check(multi-process safe).
+///
+/// The slot will be claimed by creating its claim file exclusively(see `claim::Claim`).
+/// `Event::Again` will be returned if another writer holds the claim or the slot is used.
+///
+/// # Arguments
+/// - layout:    Directory layout of buffer files(shard dirs will be created before the claim).
+/// - unchecked: Writes `NamedItem` after check.
+/// - is_empty:  Checks if `Name` is empty(use `|_| Ok(true)` to overwrite used slots).
+/// - mode:      Strategy to write an item file.
+pub fn writer_claimed_new_layout<W, E>(
+    layout: Layout,
+    unchecked: W,
+    is_empty: E,
+    mode: WriteMode,
+) -> impl Fn(NamedItem) -> Result<Name, Event>
+where
+    W: Fn(NamedItem) -> Result<Name, Event>,
+    E: Fn(&Name) -> Result<bool, Event>,
+{
+    let checked = writer_checked_new(unchecked, is_empty);
+    move |named: NamedItem| {
+        let p: PathBuf = layout.path_checked(named.as_name())?;
+        create_shard_dir(&layout, named.as_name(), mode)?;
+        let claim: Claim = Claim::acquire(p)?;
+        let wrote: Name = checked(named)?;
+        // a running batch holds the claim until its renames are done
+        let _claim: Option<Claim> = batch::defer_release(claim);
+        Ok(wrote)
+    }
+}
+
 /// Creates new checked writer which uses default closures to write and do empty check.
 ///
 /// # Arguments
 /// - dirname: Path to store buffer files.
 /// - checksum:     Computes checksum.
-pub fn writer_checked_new_default_with_checksum<P, C>(
+/// - mode:         Strategy to write an item file.
+pub fn writer_checked_new_default_with_checksum_mode<P, C>(
     dirname: P,
     checksum: C,
+    mode: WriteMode,
 ) -> impl Fn(NamedItem) -> Result<Name, Event>
 where
     P: AsRef<Path>,
     C: Fn(&[u8]) -> Vec<u8>,
 {
     let p: &Path = dirname.as_ref();
-    let unchecked = writer_unchecked_new_default_with_checksum(p.to_path_buf(), checksum);
+    let unchecked =
+        writer_unchecked_new_default_with_checksum_mode(p.to_path_buf(), checksum, mode);
     let empty_checker = empty::empty_checker_new_default(p.to_path_buf());
     let f = move |n: &Name| empty_checker(n.clone());
     writer_checked_new(unchecked, f)
 }
 
+/// Creates new checked writer which uses default closures to write and do empty check.
+///
+/// # Arguments
+/// - dirname: Path to store buffer files.
+/// - checksum:     Computes checksum.
+pub fn writer_checked_new_default_with_checksum<P, C>(
+    dirname: P,
+    checksum: C,
+) -> impl Fn(NamedItem) -> Result<Name, Event>
+where
+    P: AsRef<Path>,
+    C: Fn(&[u8]) -> Vec<u8>,
+{
+    writer_checked_new_default_with_checksum_mode(dirname, checksum, WriteMode::InPlace)
+}
+
+/// Creates new checked writer which uses default closures and `Checksum`(with an item header).
+///
+/// # Arguments
+/// - dirname: Path to store buffer files.
+/// - checker:      Computes checksum.
+/// - mode:         Strategy to write an item file.
+pub fn writer_checked_new_default_with_checker_mode<P, K>(
+    dirname: P,
+    checker: K,
+    mode: WriteMode,
+) -> impl Fn(NamedItem) -> Result<Name, Event>
+where
+    P: AsRef<Path>,
+    K: Checksum + Clone,
+{
+    let unchecked = writer_checked_new_default_with_checksum_mode(
+        dirname,
+        checksum::checksum2fn(checker.clone()),
+        mode,
+    );
+    writer_framed_new(unchecked, ItemFormat::new(checker), SystemClock)
+}
+
 /// Creates new checked writer which uses default unchecked writer and default empty checker.
 pub fn writer_checked_new_default<P>(dirname: P) -> impl Fn(NamedItem) -> Result<Name, Event>
 where
@@ -188,6 +569,139 @@ mod test_write {
         }
     }
 
+    mod writer_sealed_new {
+        use std::time::SystemTime;
+
+        use crate::envelope::Plain;
+        use crate::evt::Event;
+        use crate::item::{Item, Name, NamedItem};
+        use crate::meta::Meta;
+        use crate::write;
+
+        #[test]
+        fn test_headerless() {
+            let unchecked = |named: NamedItem| Ok(named.as_name().clone());
+            let f = write::writer_sealed_new(unchecked, Plain);
+            let named = |item: Item| NamedItem::new(item, Name::from("42"));
+            assert_eq!(f(named(Item::from(vec![0x42]))), Ok(Name::from("42")));
+
+            let item = Item::from(vec![0x42]).with_meta(Meta::new().with("k", "v"));
+            assert_eq!(f(named(item)), Err(Event::BadRequest));
+            let item = Item::from(vec![0x42]).with_deadline(SystemTime::now());
+            assert_eq!(f(named(item)), Err(Event::BadRequest));
+        }
+    }
+
+    mod writer_unchecked_new_default_with_checksum_mode {
+        use std::path::Path;
+        use std::time::SystemTime;
+
+        use crate::evt::Event;
+        use crate::item::{Item, Name, NamedItem};
+        use crate::write::{self, WriteMode};
+
+        #[test]
+        #[ignore]
+        fn test_atomic() {
+            let dirname = Path::new(
+                "./test.d/write/writer_unchecked_new_default_with_checksum_mode/atomic.d",
+            );
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+            let chk = |_: &[u8]| b"cafef00d".to_vec();
+            let f = write::writer_unchecked_new_default_with_checksum_mode(
+                dirname,
+                chk,
+                WriteMode::Atomic,
+            );
+            let r = f(NamedItem::new(Item::from(vec![0x42]), Name::from("42")));
+            assert_eq!(r, Ok(Name::from("42")));
+
+            let names: Vec<_> = std::fs::read_dir(dirname)
+                .unwrap()
+                .map(|d| d.unwrap().file_name())
+                .collect();
+            assert_eq!(names, vec!["42"]);
+            let raw: Vec<u8> = std::fs::read(dirname.join("42")).unwrap();
+            assert_eq!(raw, b"\x42cafef00d");
+
+            // headerless items can not keep a deadline
+            let item = Item::from(vec![0x43]).with_deadline(SystemTime::now());
+            let r = f(NamedItem::new(item, Name::from("43")));
+            assert_eq!(r, Err(Event::BadRequest));
+            assert!(!dirname.join("43").exists());
+        }
+
+        #[test]
+        #[ignore]
+        fn test_dir_noent() {
+            let dirname = Path::new(
+                "./test.d/write/writer_unchecked_new_default_with_checksum_mode/dir_noent.d",
+            );
+            std::fs::remove_dir_all(dirname).ok();
+            let chk = |_: &[u8]| vec![];
+            let f = write::writer_unchecked_new_default_with_checksum_mode(
+                dirname,
+                chk,
+                WriteMode::Atomic,
+            );
+            let r = f(NamedItem::new(Item::from(vec![]), Name::from("42")));
+            assert!(r.is_err());
+        }
+    }
+
+    mod writer_unchecked_new_layout_with_checksum_mode {
+        use std::path::Path;
+
+        use crate::full::Layout;
+        use crate::item::{Item, Name, NamedItem};
+        use crate::write::{self, WriteMode};
+
+        #[test]
+        #[ignore]
+        fn test_sharded() {
+            let dirname =
+                Path::new("./test.d/write/writer_unchecked_new_layout_with_checksum_mode/shard.d");
+            std::fs::remove_dir_all(dirname).ok();
+            std::fs::create_dir_all(dirname).unwrap();
+            let layout: Layout = Layout::sharded(dirname, 2, 2).unwrap();
+            let chk = |_: &[u8]| vec![];
+            for mode in [WriteMode::InPlace, WriteMode::Atomic] {
+                let f = write::writer_unchecked_new_layout_with_checksum_mode(
+                    layout.clone(),
+                    chk,
+                    mode,
+                );
+                let r = f(NamedItem::new(
+                    Item::from(vec![0x42]),
+                    Name::from("abcd1234"),
+                ));
+                assert_eq!(r, Ok(Name::from("abcd1234")));
+            }
+            let raw: Vec<u8> = std::fs::read(dirname.join("ab/cd/abcd1234")).unwrap();
+            assert_eq!(raw, vec![0x42]);
+        }
+
+        #[test]
+        #[ignore]
+        fn test_dir_noent() {
+            let dirname = Path::new(
+                "./test.d/write/writer_unchecked_new_layout_with_checksum_mode/dir_noent.d",
+            );
+            std::fs::remove_dir_all(dirname).ok();
+            let layout: Layout = Layout::sharded(dirname, 2, 2).unwrap();
+            let chk = |_: &[u8]| vec![];
+            let f = write::writer_unchecked_new_layout_with_checksum_mode(
+                layout,
+                chk,
+                WriteMode::InPlace,
+            );
+            let r = f(NamedItem::new(Item::from(vec![]), Name::from("abcd1234")));
+            assert!(r.is_err());
+            assert!(!dirname.exists());
+        }
+    }
+
     mod writer_unchecked_new_default {
         use std::io::ErrorKind;
         use std::path::Path;